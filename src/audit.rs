@@ -0,0 +1,196 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, SystemTime};
+
+use crate::build_output;
+use crate::provenance::utc_timestamp;
+use crate::{
+    active_profile, collect_profile_directory, command_line_options, exit_codes, expand_glob, expand_tilde,
+    get_config_path, git, is_glob, load_config, porcelain, porcelain_record, profile_files_in, roots, special, tokens,
+    unicode_paths, ActiveProfile, Error, QUIET,
+};
+
+// Directories whose newest file is older than this are reported as stale
+const DEFAULT_STALE_MONTHS: u64 = 6;
+const MONTH: Duration = Duration::from_secs(30 * 86_400);
+
+// Entries that point nowhere, and the directories that do exist
+fn missing_paths(profile: &ActiveProfile, findings: &mut Vec<String>) -> Vec<PathBuf> {
+    for entry in &profile.files {
+        if git::parse_revision_entry(entry).is_some() {
+            continue;
+        }
+        if is_glob(entry, &profile.roots) {
+            if !expand_glob(entry, &profile.roots).iter().any(|path| path.is_file()) {
+                findings.push(format!("files pattern matches nothing: {}", entry));
+            }
+            continue;
+        }
+        let path = roots::resolve(entry, &profile.roots);
+        if !unicode_paths::locate(&path).unwrap_or(path).is_file() {
+            findings.push(format!("missing file: {}", entry));
+        }
+    }
+
+    let mut directories = Vec::new();
+    for dir in &profile.directories {
+        if is_glob(dir, &profile.roots) {
+            let matched: Vec<PathBuf> = expand_glob(dir, &profile.roots)
+                .into_iter()
+                .filter(|path| path.is_dir())
+                .collect();
+            if matched.is_empty() {
+                findings.push(format!("directories pattern matches nothing: {}", dir));
+            }
+            directories.extend(matched);
+            continue;
+        }
+        let path = roots::resolve(dir, &profile.roots);
+        if path.is_dir() {
+            directories.push(path);
+        } else {
+            findings.push(format!("missing directory: {}", dir));
+        }
+    }
+
+    if let Some(project) = profile.project {
+        if !expand_tilde(&project.path).is_dir() {
+            findings.push(format!("missing project path: {}", project.path));
+        }
+    }
+    for root in &profile.roots {
+        if !root.path.is_dir() {
+            findings.push(format!("missing root: {} = {}", root.label, root.path.display()));
+        }
+    }
+    directories
+}
+
+// Directories in which no collected file has changed for `months`
+fn stale_directories(profile: &ActiveProfile, directories: &[PathBuf], months: u64, findings: &mut Vec<String>) {
+    let cutoff = SystemTime::now() - MONTH * months as u32;
+    for dir in directories {
+        let newest = collect_profile_directory(dir, profile)
+            .iter()
+            .filter_map(|path| fs::metadata(path).and_then(|metadata| metadata.modified()).ok())
+            .max();
+        match newest {
+            Some(newest) if newest < cutoff => findings.push(format!(
+                "no file changes in {} months: {} (newest {})",
+                months,
+                dir.display(),
+                &utc_timestamp(newest)[..10]
+            )),
+            None => findings.push(format!("directory has no files to bundle: {}", dir.display())),
+            _ => {}
+        }
+    }
+}
+
+// Subdirectories the profile bundles that look generated, whatever they are called
+fn build_outputs(profile: &ActiveProfile, directories: &[PathBuf], findings: &mut Vec<String>) {
+    for dir in directories {
+        for suspect in build_output::suspects(dir, &collect_profile_directory(dir, profile)) {
+            findings.push(format!(
+                "looks like build output ({}): {}; add '{}' to exclude_paths",
+                suspect.reasons.join(", "),
+                suspect.dir.display(),
+                suspect.pattern
+            ));
+        }
+    }
+}
+
+// The files alone, estimated; trees and sections only add to it
+fn estimated_tokens(profile: &ActiveProfile, directories: &[PathBuf]) -> usize {
+    profile_files_in(profile, directories)
+        .iter()
+        .filter(|entry| git::parse_revision_entry(entry).is_none())
+        .map(|entry| roots::resolve(entry, &profile.roots))
+        .filter(|path| special::safe_to_read(path))
+        .filter_map(|path| fs::read_to_string(path).ok())
+        .map(|content| tokens::estimate(&content))
+        .sum()
+}
+
+pub fn run(args: &[String]) -> Result<(), Error> {
+    let usage = "Usage: fdllm audit [--months N]";
+    let mut months = DEFAULT_STALE_MONTHS;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--months" => match iter.next().and_then(|value| value.parse().ok()) {
+                Some(value) if value > 0 => months = value,
+                _ => {
+                    eprintln!("--months expects a positive number");
+                    std::process::exit(exit_codes::CONFIG);
+                }
+            },
+            _ => {
+                eprintln!("{}", usage);
+                std::process::exit(exit_codes::CONFIG);
+            }
+        }
+    }
+
+    let config = load_config(&get_config_path()?)?;
+    let mut names: Vec<&String> = config.profiles.iter().flat_map(|profiles| profiles.keys()).collect();
+    names.sort();
+    let largest_model = config
+        .models
+        .iter()
+        .flatten()
+        .max_by_key(|(_, limit)| **limit)
+        .map(|(name, limit)| (name.as_str(), *limit));
+
+    // Every profile is walked, so their own progress messages are silenced
+    QUIET.store(true, Ordering::Relaxed);
+    let mut report = Vec::new();
+    for name in &names {
+        let profile = active_profile(&config, Some(name), command_line_options())?;
+        let mut findings = Vec::new();
+        let directories = missing_paths(&profile, &mut findings);
+        stale_directories(&profile, &directories, months, &mut findings);
+        build_outputs(&profile, &directories, &mut findings);
+        if let Some((model, limit)) = largest_model {
+            let estimate = estimated_tokens(&profile, &directories);
+            if estimate > limit {
+                findings.push(format!(
+                    "files come to ~{} tokens, more than every configured model (largest: {}, {})",
+                    estimate, model, limit
+                ));
+            }
+        }
+        report.push((name.as_str(), findings));
+    }
+    QUIET.store(false, Ordering::Relaxed);
+
+    let flagged = report.iter().filter(|(_, findings)| !findings.is_empty()).count();
+    if porcelain() {
+        for (name, findings) in &report {
+            for finding in findings {
+                porcelain_record("finding", format!("{} {}", name, finding));
+            }
+        }
+    } else {
+        for (name, findings) in &report {
+            if findings.is_empty() {
+                println!("{}: ok", name);
+                continue;
+            }
+            println!("{}:", name);
+            for finding in findings {
+                println!("  {}", finding);
+            }
+        }
+        if largest_model.is_none() {
+            println!("No models configured; add `models = {{ \"gpt-4o\" = 128000 }}` to check bundle sizes");
+        }
+        println!("{} of {} profile(s) need attention", flagged, report.len());
+    }
+    if flagged > 0 {
+        std::process::exit(exit_codes::FINDINGS);
+    }
+    Ok(())
+}
@@ -0,0 +1,308 @@
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::{copy_bundle, embed, exit_codes, index, tokens, Bundle, Error, Section};
+
+const DEFAULT_BUDGET: usize = 50_000;
+
+// How many of the best-scoring files seed the import-graph expansion
+const GRAPH_SEEDS: usize = 5;
+
+// A neighbour in the import graph inherits this share of the seed's score, divided by the
+// square root of the seed's link count so hubs like main.rs don't promote every module
+const NEIGHBOUR_WEIGHT: f32 = 0.5;
+
+// Question filler that would otherwise match nearly every file
+const QUESTION_WORDS: [&str; 20] = [
+    "to", "of", "in", "on", "is", "it", "be", "as", "at", "by", "or", "an", "if", "do", "does", "why",
+    "how", "what", "when", "where",
+];
+
+struct Candidate {
+    path: PathBuf,
+    content: String,
+    tokens: usize,
+    lexical: f32,
+    indexed: f32,
+    score: f32,
+    matched_terms: Vec<String>,
+    // Set when the file was pulled in through the import graph
+    neighbour_of: Option<PathBuf>,
+}
+
+// `mod x;`, `use crate::x`, `import x`, `from "./x"`, `#include "x.h"`
+fn reference_patterns() -> &'static [Regex] {
+    static PATTERNS: OnceLock<Vec<Regex>> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        [
+            r"(?m)^\s*(?:pub\s+)?mod\s+(\w+)\s*;",
+            r"(?:crate|super|self)::(\w+)",
+            r"(?m)^\s*(?:import|from)\s+([\w.]+)",
+            r#"(?:from|require\(|import)\s*['"]([./\w-]+)['"]"#,
+            r#"(?m)^\s*#include\s+"([\w./-]+)""#,
+        ]
+        .into_iter()
+        .map(|pattern| Regex::new(pattern).expect("valid reference pattern"))
+        .collect()
+    })
+}
+
+// Module names a file refers to
+fn referenced_modules(content: &str) -> HashSet<String> {
+    let mut modules = HashSet::new();
+    for re in reference_patterns() {
+        for caps in re.captures_iter(content) {
+            // `./auth/session` and `pkg.auth.session` both point at `session`
+            let name = caps[1].trim_end_matches(".h");
+            if let Some(last) = name.rsplit(['/', '.']).find(|part| !part.is_empty()) {
+                modules.insert(last.to_string());
+            }
+        }
+    }
+    modules
+}
+
+// `src/auth/mod.rs` is module `auth`; everything else is its file stem
+fn module_name(path: &Path) -> String {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+    if stem == "mod" || stem == "index" || stem == "__init__" {
+        if let Some(parent) = path.parent().and_then(|parent| parent.file_name()) {
+            return parent.to_string_lossy().to_string();
+        }
+    }
+    stem
+}
+
+// TF-IDF over the question's terms, damped by file length
+fn apply_lexical_scores(candidates: &mut [Candidate], question: &str) {
+    let mut seen = HashSet::new();
+    let terms: Vec<String> = embed::tokenize(question)
+        .into_iter()
+        .filter(|term| !QUESTION_WORDS.contains(&term.as_str()))
+        .filter(|term| seen.insert(term.clone()))
+        .collect();
+
+    let counts: Vec<HashMap<String, usize>> = candidates
+        .iter()
+        .map(|candidate| {
+            let mut counts = HashMap::new();
+            // Path components count too: `auth/login.rs` is about login
+            let text = format!("{} {}", candidate.path.display(), candidate.content);
+            for token in embed::tokenize(&text) {
+                *counts.entry(token).or_insert(0) += 1;
+            }
+            counts
+        })
+        .collect();
+
+    let total = candidates.len() as f32;
+    let idf: HashMap<&str, f32> = terms
+        .iter()
+        .map(|term| {
+            let df = counts.iter().filter(|counts| counts.contains_key(term)).count() as f32;
+            (term.as_str(), ((total + 1.0) / (df + 1.0)).ln() + 1.0)
+        })
+        .collect();
+
+    for (candidate, counts) in candidates.iter_mut().zip(&counts) {
+        let length = counts.values().sum::<usize>().max(1) as f32;
+        for term in &terms {
+            if let Some(tf) = counts.get(term) {
+                candidate.lexical += idf[term.as_str()] * (1.0 + (*tf as f32).ln()) / (1.0 + length.ln());
+                candidate.matched_terms.push(term.clone());
+            }
+        }
+    }
+}
+
+// Best chunk similarity per file; false when the profile has no index yet
+fn apply_index_scores(candidates: &mut [Candidate], question: &str, index_name: &str) -> bool {
+    let index = match index::load(index_name) {
+        Some(index) => index,
+        None => return false,
+    };
+    let query = embed::embed(question);
+    for candidate in candidates.iter_mut() {
+        let key = candidate.path.to_string_lossy();
+        if let Some(file) = index.files.get(key.as_ref()) {
+            candidate.indexed = file
+                .chunks
+                .iter()
+                .map(|chunk| embed::similarity(&query, &chunk.vector))
+                .fold(0.0, f32::max);
+        }
+    }
+    true
+}
+
+fn normalize(values: impl Iterator<Item = f32>) -> f32 {
+    values.fold(0.0, f32::max).max(f32::EPSILON)
+}
+
+// Files the top seeds import (and files importing them) are likely part of the answer
+fn expand_import_graph(candidates: &mut [Candidate]) {
+    let mut order: Vec<usize> = (0..candidates.len()).collect();
+    order.sort_by(|&a, &b| candidates[b].score.total_cmp(&candidates[a].score));
+    let seeds: Vec<usize> = order
+        .into_iter()
+        .take(GRAPH_SEEDS)
+        .filter(|&index| candidates[index].score > 0.0)
+        .collect();
+
+    let modules: Vec<String> = candidates.iter().map(|candidate| module_name(&candidate.path)).collect();
+    let references: Vec<HashSet<String>> = candidates
+        .iter()
+        .map(|candidate| referenced_modules(&candidate.content))
+        .collect();
+
+    for seed in seeds {
+        let seed_score = candidates[seed].score;
+        let seed_path = candidates[seed].path.clone();
+        let neighbours: Vec<usize> = (0..candidates.len())
+            .filter(|&other| other != seed)
+            .filter(|&other| {
+                references[seed].contains(&modules[other]) || references[other].contains(&modules[seed])
+            })
+            .collect();
+        if neighbours.is_empty() {
+            continue;
+        }
+        let inherited = seed_score * NEIGHBOUR_WEIGHT / (neighbours.len() as f32).sqrt();
+        for other in neighbours {
+            if inherited > candidates[other].score {
+                candidates[other].score = inherited;
+                candidates[other].neighbour_of = Some(seed_path.clone());
+            }
+        }
+    }
+}
+
+fn rationale(candidate: &Candidate) -> String {
+    let mut reasons = Vec::new();
+    if !candidate.matched_terms.is_empty() {
+        reasons.push(format!("terms: {}", candidate.matched_terms.join(", ")));
+    }
+    if candidate.indexed > 0.0 {
+        reasons.push(format!("index {:.2}", candidate.indexed));
+    }
+    if let Some(seed) = &candidate.neighbour_of {
+        reasons.push(format!("linked to {}", seed.display()));
+    }
+    reasons.join("; ")
+}
+
+pub fn run(args: &[String]) -> Result<(), Error> {
+    let usage = "Usage: fdllm auto \"<question>\" [profile] [--budget TOKENS]";
+    let mut positional = Vec::new();
+    let mut budget = DEFAULT_BUDGET;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--budget" => match iter.next().and_then(|value| value.parse().ok()) {
+                Some(value) => budget = value,
+                None => {
+                    eprintln!("--budget expects a token count");
+                    std::process::exit(exit_codes::CONFIG);
+                }
+            },
+            _ if !arg.starts_with('-') && positional.len() < 2 => positional.push(arg.as_str()),
+            _ => {
+                eprintln!("{}", usage);
+                std::process::exit(exit_codes::CONFIG);
+            }
+        }
+    }
+
+    let (question, profile_name) = match positional.as_slice() {
+        [question] => (*question, None),
+        [question, profile] => (*question, Some(*profile)),
+        _ => {
+            eprintln!("{}", usage);
+            std::process::exit(exit_codes::CONFIG);
+        }
+    };
+
+    let mut candidates: Vec<Candidate> = index::profile_paths(profile_name)?
+        .into_iter()
+        .filter_map(|path| {
+            let content = fs::read_to_string(&path).ok()?;
+            Some(Candidate {
+                tokens: tokens::estimate(&content),
+                path,
+                content,
+                lexical: 0.0,
+                indexed: 0.0,
+                score: 0.0,
+                matched_terms: Vec::new(),
+                neighbour_of: None,
+            })
+        })
+        .collect();
+    if candidates.is_empty() {
+        eprintln!("No files to choose from in this profile");
+        std::process::exit(exit_codes::EMPTY);
+    }
+
+    apply_lexical_scores(&mut candidates, question);
+    let indexed = apply_index_scores(&mut candidates, question, profile_name.unwrap_or("default"));
+    if !indexed {
+        info!("No search index for this profile (run `fdllm index`); using term scores only");
+    }
+
+    let lexical_max = normalize(candidates.iter().map(|candidate| candidate.lexical));
+    let indexed_max = normalize(candidates.iter().map(|candidate| candidate.indexed));
+    for candidate in &mut candidates {
+        candidate.score = if indexed {
+            0.5 * candidate.lexical / lexical_max + 0.5 * candidate.indexed / indexed_max
+        } else {
+            candidate.lexical / lexical_max
+        };
+    }
+    expand_import_graph(&mut candidates);
+
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+    // Greedy: best score first, skipping files that would overflow the budget
+    let mut used = 0;
+    let mut selected = Vec::new();
+    info!("Selection for \"{}\" (budget {} tokens):", question, budget);
+    for candidate in candidates.iter().filter(|candidate| candidate.score > 0.0) {
+        if used + candidate.tokens > budget {
+            info!(
+                "  skip  {:.2}  {} (~{} tokens, over budget)",
+                candidate.score,
+                candidate.path.display(),
+                candidate.tokens
+            );
+            continue;
+        }
+        used += candidate.tokens;
+        info!(
+            "  take  {:.2}  {} (~{} tokens; {})",
+            candidate.score,
+            candidate.path.display(),
+            candidate.tokens,
+            rationale(candidate)
+        );
+        selected.push(candidate.path.to_string_lossy().to_string());
+    }
+
+    if selected.is_empty() {
+        eprintln!("Nothing relevant fits the budget");
+        std::process::exit(exit_codes::EMPTY);
+    }
+    info!("Selected {} file(s), ~{} tokens", selected.len(), used);
+
+    copy_bundle(Bundle {
+        files: selected,
+        sections: vec![Section {
+            title: "Question".to_string(),
+            body: question.to_string(),
+        }],
+        ..Default::default()
+    })
+}
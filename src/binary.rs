@@ -0,0 +1,16 @@
+use std::path::Path;
+
+// How much of a file is sniffed, as grep and ripgrep do
+const SAMPLE_BYTES: usize = 8 * 1024;
+
+// A NUL byte in the first 8 KiB means binary whatever the extension says; content that is not
+// UTF-8 never reaches this point, reading it already failed
+pub fn looks_binary(content: &str) -> bool {
+    content.as_bytes()[..content.len().min(SAMPLE_BYTES)].contains(&0)
+}
+
+// --include-binary-as-note: what stands in for the content
+pub fn note(path: &Path) -> String {
+    let bytes = path.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+    format!("[fdllm: binary file, {} KB, content not included]", bytes.div_ceil(1024))
+}
@@ -0,0 +1,100 @@
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::sync::OnceLock;
+
+// Shorter runs are usually hashes or ids worth keeping
+pub const DEFAULT_MIN_LENGTH: usize = 256;
+
+// Lines of a wrapped blob (PEM bodies, split base64) are at least this long
+const MIN_BLOB_LINE: usize = 40;
+
+// `elide_blobs = true` for the default minimum length, or the number of characters a base64 or
+// hex run needs before it is elided
+#[derive(Deserialize, JsonSchema, Clone, Copy)]
+#[serde(untagged)]
+pub enum ElideBlobs {
+    Enabled(bool),
+    MinLength(usize),
+}
+
+impl ElideBlobs {
+    pub fn min_length(self) -> Option<usize> {
+        match self {
+            ElideBlobs::Enabled(true) => Some(DEFAULT_MIN_LENGTH),
+            ElideBlobs::Enabled(false) => None,
+            ElideBlobs::MinLength(length) => Some(length),
+        }
+    }
+}
+
+fn marker(bytes: usize) -> String {
+    format!("[blob elided, {} KB]", bytes.div_ceil(1024))
+}
+
+fn is_blob_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '+' | '/' | '=' | '-' | '_')
+}
+
+// Long runs of dashes or a single repeated word are not data; blobs mix letters and digits
+fn looks_encoded(text: &str) -> bool {
+    text.chars().any(|c| c.is_ascii_digit()) && text.chars().any(|c| c.is_ascii_alphabetic())
+}
+
+fn is_blob_line(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.len() >= MIN_BLOB_LINE && trimmed.chars().all(is_blob_char) && looks_encoded(trimmed)
+}
+
+fn inline_blob() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"[A-Za-z0-9+/_-]{40,}={0,2}").expect("valid blob pattern"))
+}
+
+// Replaces base64 or hex runs of at least `min_length` characters: wrapped blocks spanning
+// several lines become one marker line, inline runs (data: URIs, fixtures) are replaced in place
+pub fn elide_blobs(content: String, min_length: usize) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut output = String::with_capacity(content.len());
+    let mut elided = false;
+    let mut index = 0;
+    while index < lines.len() {
+        let mut end = index;
+        while end < lines.len() && is_blob_line(lines[end]) {
+            end += 1;
+        }
+        let block_length: usize = lines[index..end].iter().map(|line| line.trim().len()).sum();
+        if end - index > 1 && block_length >= min_length {
+            let indent = &lines[index][..lines[index].len() - lines[index].trim_start().len()];
+            let bytes: usize = lines[index..end].iter().map(|line| line.len() + 1).sum();
+            output.push_str(indent);
+            output.push_str(&marker(bytes));
+            output.push('\n');
+            elided = true;
+            index = end;
+            continue;
+        }
+
+        let line = lines[index];
+        let replaced = inline_blob().replace_all(line, |captures: &regex::Captures| {
+            let run = &captures[0];
+            if run.len() >= min_length && looks_encoded(run) {
+                marker(run.len())
+            } else {
+                run.to_string()
+            }
+        });
+        elided |= replaced != line;
+        output.push_str(&replaced);
+        output.push('\n');
+        index += 1;
+    }
+
+    if !elided {
+        return content;
+    }
+    if !content.ends_with('\n') {
+        output.pop();
+    }
+    output
+}
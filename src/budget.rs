@@ -0,0 +1,189 @@
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::path::PathBuf;
+
+use crate::format::{self, Format};
+use crate::{lang, tokens, Error};
+
+// What to do when the whole bundle is over `max_tokens`
+#[derive(Deserialize, JsonSchema, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum BudgetStrategy {
+    // Deliver everything and say by how much it is over
+    #[default]
+    Warn,
+    // Cut the largest files down to a common size, keeping whole lines
+    LargestFirst,
+    // Leave out files from the end of the list until the rest fits
+    DropLast,
+    // Deliver nothing and exit with the over-budget status
+    Strict,
+}
+
+impl BudgetStrategy {
+    pub fn name(self) -> &'static str {
+        match self {
+            BudgetStrategy::Warn => "warn",
+            BudgetStrategy::LargestFirst => "largest-first",
+            BudgetStrategy::DropLast => "drop-last",
+            BudgetStrategy::Strict => "strict",
+        }
+    }
+}
+
+pub struct Budget {
+    pub max_tokens: usize,
+    pub strategy: BudgetStrategy,
+}
+
+// One file as it will appear in the bundle, with its token count (header included)
+pub struct Entry {
+    pub header: String,
+    pub path: PathBuf,
+    pub content: String,
+    pub tokens: usize,
+    pub dropped: bool,
+    format: Format,
+}
+
+impl Entry {
+    pub fn new(header: String, path: PathBuf, content: String, format: Format) -> Entry {
+        let mut entry = Entry {
+            header,
+            path,
+            content,
+            tokens: 0,
+            dropped: false,
+            format,
+        };
+        entry.tokens = tokens::count(&entry.render());
+        entry
+    }
+
+    pub fn render(&self) -> String {
+        format::file_block(self.format, &self.header, &self.path, &self.content)
+    }
+
+    // Keeps whole lines while the rendered entry stays within `limit` tokens
+    fn truncate(&mut self, limit: usize) {
+        let comment = lang::language_for(&self.path).map(lang::line_comment).unwrap_or("//");
+        let lines: Vec<&str> = self.content.lines().collect();
+        let marker = |dropped: usize| {
+            format!("{} [fdllm: truncated to fit the token budget; {} more line(s)]\n", comment, dropped)
+        };
+        let overhead = tokens::count(&format::file_block(self.format, &self.header, &self.path, ""))
+            + tokens::count(&marker(lines.len()));
+
+        let mut kept = String::new();
+        let mut used = overhead;
+        let mut kept_lines = 0;
+        for line in &lines {
+            let length = tokens::count(line) + 1;
+            if used + length > limit {
+                break;
+            }
+            used += length;
+            kept.push_str(line);
+            kept.push('\n');
+            kept_lines += 1;
+        }
+        kept.push_str(&marker(lines.len() - kept_lines));
+        self.content = kept;
+        self.tokens = tokens::count(&self.render());
+    }
+}
+
+// The largest per-file size that brings the total within `available`: files above it are cut
+// to it, files below it are left alone
+fn common_cap(sizes: &[usize], available: usize) -> usize {
+    let mut sorted = sizes.to_vec();
+    sorted.sort_unstable_by(|a, b| b.cmp(a));
+    let mut rest: usize = sorted.iter().sum();
+    for (index, size) in sorted.iter().enumerate() {
+        rest -= size;
+        if rest > available {
+            continue;
+        }
+        let cap = (available - rest) / (index + 1);
+        if cap >= sorted.get(index + 1).copied().unwrap_or(0) {
+            return cap;
+        }
+    }
+    0
+}
+
+fn total(entries: &[&mut Entry], fixed: usize) -> usize {
+    fixed + entries.iter().filter(|entry| !entry.dropped).map(|entry| entry.tokens).sum::<usize>()
+}
+
+// `fixed` covers everything that isn't a file: trees, sections, snippets and the footer
+pub fn enforce(entries: &mut [&mut Entry], fixed: usize, budget: &Budget) -> Result<(), Error> {
+    let before = total(entries, fixed);
+    if before <= budget.max_tokens {
+        return Ok(());
+    }
+    let available = budget.max_tokens.saturating_sub(fixed);
+
+    match budget.strategy {
+        BudgetStrategy::Warn => {
+            eprintln!(
+                "Bundle is {} tokens, {} over the {} token budget; set budget_strategy to \"largest-first\" or \"drop-last\" to trim it",
+                before,
+                before - budget.max_tokens,
+                budget.max_tokens
+            );
+            return Ok(());
+        }
+        BudgetStrategy::Strict => {
+            crate::porcelain_record("status", "error budget");
+            return Err(Error::OverBudget(format!(
+                "Bundle is {} tokens, {} over the {} token budget; nothing was delivered (budget_strategy = \"strict\")",
+                before,
+                before - budget.max_tokens,
+                budget.max_tokens
+            )));
+        }
+        BudgetStrategy::LargestFirst => {
+            let sizes: Vec<usize> = entries.iter().map(|entry| entry.tokens).collect();
+            let cap = common_cap(&sizes, available);
+            let mut trimmed = 0;
+            for entry in entries.iter_mut().filter(|entry| entry.tokens > cap) {
+                entry.truncate(cap);
+                trimmed += 1;
+            }
+            eprintln!(
+                "Trimmed {} file(s) to ~{} tokens each to fit the {} token budget",
+                trimmed, cap, budget.max_tokens
+            );
+        }
+        BudgetStrategy::DropLast => {
+            let mut dropped = Vec::new();
+            for index in (0..entries.len()).rev() {
+                if total(entries, fixed) <= budget.max_tokens {
+                    break;
+                }
+                entries[index].dropped = true;
+                dropped.push(entries[index].header.clone());
+            }
+            dropped.reverse();
+            eprintln!(
+                "Left out {} file(s) to fit the {} token budget: {}",
+                dropped.len(),
+                budget.max_tokens,
+                dropped.join(", ")
+            );
+        }
+    }
+
+    let after = total(entries, fixed);
+    if after > budget.max_tokens && fixed >= budget.max_tokens {
+        eprintln!(
+            "Still {} tokens over the budget: trees, sections and snippets alone take {}",
+            after - budget.max_tokens,
+            fixed
+        );
+    } else if after > budget.max_tokens {
+        eprintln!("Still {} tokens over the budget", after - budget.max_tokens);
+    }
+    Ok(())
+}
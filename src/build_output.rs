@@ -0,0 +1,189 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::file_size;
+
+// Fewer files than this say too little about how they were made
+const MIN_FILES: usize = 5;
+const LARGE_BYTES: u64 = 1 << 20;
+// Most of the files written within this many seconds of each other: one build wrote them
+const WRITTEN_TOGETHER_SECS: u64 = 120;
+// Average line length of a minified bundle; hand-written code stays far below it
+const MINIFIED_LINE_LENGTH: usize = 300;
+const SAMPLE_BYTES: usize = 8 * 1024;
+// How deep below a walked directory candidates are looked for (`web/dist/assets`)
+const MAX_DEPTH: usize = 3;
+
+struct FileStats {
+    bytes: u64,
+    modified: Option<SystemTime>,
+    // Binary, or minified text
+    generated: bool,
+}
+
+// A directory that looks generated, with the exclude that would leave it out
+pub struct Suspect {
+    pub dir: PathBuf,
+    pub pattern: String,
+    pub reasons: Vec<String>,
+}
+
+fn looks_generated(sample: &[u8]) -> bool {
+    if sample.contains(&0) {
+        return true;
+    }
+    // A character cut at the end of the sample is still text
+    let text = match std::str::from_utf8(sample) {
+        Ok(text) => text,
+        Err(err) if err.error_len().is_none() => std::str::from_utf8(&sample[..err.valid_up_to()]).unwrap_or_default(),
+        Err(_) => return true,
+    };
+    let lines = text.matches('\n').count() + 1;
+    text.len() >= 1024 && text.len() / lines > MINIFIED_LINE_LENGTH
+}
+
+fn stats(path: &Path) -> Option<FileStats> {
+    let metadata = path.metadata().ok()?;
+    let mut sample = Vec::with_capacity(SAMPLE_BYTES);
+    File::open(path).ok()?.take(SAMPLE_BYTES as u64).read_to_end(&mut sample).ok()?;
+    Some(FileStats {
+        bytes: metadata.len(),
+        modified: metadata.modified().ok(),
+        generated: looks_generated(&sample),
+    })
+}
+
+// No single sign is enough (a checked-in asset folder is large, a fresh clone is all written at
+// once), so two of the three are needed
+fn signals(files: &[&FileStats]) -> Vec<String> {
+    if files.len() < MIN_FILES {
+        return Vec::new();
+    }
+    let mut reasons = Vec::new();
+    let bytes: u64 = files.iter().map(|file| file.bytes).sum();
+    if bytes >= LARGE_BYTES {
+        reasons.push(file_size::describe(bytes));
+    }
+    // The middle 90%, so a file touched by hand afterwards doesn't hide the rest
+    let mut times: Vec<SystemTime> = files.iter().filter_map(|file| file.modified).collect();
+    times.sort();
+    if times.len() >= MIN_FILES {
+        let trim = times.len() / 20;
+        let span = times[times.len() - 1 - trim].duration_since(times[trim]).unwrap_or_default();
+        if span.as_secs() <= WRITTEN_TOGETHER_SECS {
+            reasons.push(format!("written within {}s", span.as_secs().max(1)));
+        }
+    }
+    let generated = files.iter().filter(|file| file.generated).count();
+    if generated * 2 >= files.len() {
+        reasons.push(format!("{}% binary or minified", generated * 100 / files.len()));
+    }
+    if reasons.len() < 2 {
+        reasons.clear();
+    }
+    reasons
+}
+
+// Directories below `walked`, among those holding `files`, whose content looks like a build
+// wrote it; each is reported once, as the narrowest directory that holds all of it
+pub fn suspects(walked: &Path, files: &[PathBuf]) -> Vec<Suspect> {
+    let stats: Vec<Option<FileStats>> = files.iter().map(|path| stats(path)).collect();
+    let mut by_directory: BTreeMap<PathBuf, Vec<&FileStats>> = BTreeMap::new();
+    for (path, stats) in files.iter().zip(&stats) {
+        let (Ok(relative), Some(stats)) = (path.strip_prefix(walked), stats) else {
+            continue;
+        };
+        let mut prefix = PathBuf::new();
+        let components: Vec<_> = relative.components().collect();
+        for component in components.iter().take((components.len() - 1).min(MAX_DEPTH)) {
+            prefix.push(component);
+            by_directory.entry(prefix.clone()).or_default().push(stats);
+        }
+    }
+
+    let mut found: Vec<Suspect> = Vec::new();
+    let mut covered: Vec<&Path> = Vec::new();
+    // Parents sort before their children
+    for (relative, files) in &by_directory {
+        if covered.iter().any(|dir| relative.starts_with(dir)) {
+            continue;
+        }
+        let reasons = signals(files);
+        if reasons.is_empty() {
+            continue;
+        }
+        covered.push(relative);
+        // `web/dist` rather than `web` when everything in `web` is in `dist`
+        let mut narrowest = relative.clone();
+        while let Some((child, _)) = by_directory
+            .range(narrowest.clone()..)
+            .skip(1)
+            .find(|(child, child_files)| child.parent() == Some(narrowest.as_path()) && child_files.len() == files.len())
+        {
+            narrowest = child.clone();
+        }
+        found.push(Suspect {
+            dir: walked.join(&narrowest),
+            pattern: format!("{}/**", narrowest.to_string_lossy()),
+            reasons,
+        });
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn file(bytes: u64, seconds: u64, generated: bool) -> FileStats {
+        FileStats {
+            bytes,
+            modified: Some(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds)),
+            generated,
+        }
+    }
+
+    #[test]
+    fn needs_two_signs_of_a_build() {
+        let built: Vec<FileStats> = (0..10).map(|index| file(300 << 10, 1_000 + index, index % 3 != 0)).collect();
+        assert_eq!(signals(&built.iter().collect::<Vec<_>>()), ["2.9 MB", "written within 9s", "60% binary or minified"]);
+
+        // Hand-written over months: only large
+        let written: Vec<FileStats> = (0..10).map(|index| file(300 << 10, index * 86_400, false)).collect();
+        assert!(signals(&written.iter().collect::<Vec<_>>()).is_empty());
+
+        // A fresh checkout of small sources: only written together
+        let cloned: Vec<FileStats> = (0..10).map(|_| file(2 << 10, 5_000, false)).collect();
+        assert!(signals(&cloned.iter().collect::<Vec<_>>()).is_empty());
+    }
+
+    #[test]
+    fn reports_the_narrowest_generated_directory() {
+        let walked = std::env::temp_dir().join(format!("fdllm-build-output-{}", std::process::id()));
+        let dist = walked.join("web/dist");
+        std::fs::create_dir_all(&dist).unwrap();
+        let mut files = Vec::new();
+        for index in 0..6 {
+            let path = dist.join(format!("chunk{}.js", index));
+            std::fs::write(&path, "var a=1;".repeat(1000)).unwrap();
+            files.push(path);
+        }
+        let found = suspects(&walked, &files);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].pattern, "web/dist/**");
+        assert_eq!(found[0].dir, dist);
+        std::fs::remove_dir_all(walked).unwrap();
+    }
+
+    #[test]
+    fn sniffs_minified_and_binary_content() {
+        assert!(looks_generated(b"\x7fELF\0\0\x01"));
+        assert!(looks_generated("var a=1;".repeat(200).as_bytes()));
+        assert!(!looks_generated("fn main() {\n    println!(\"hi\");\n}\n".repeat(50).as_bytes()));
+        assert!(!looks_generated(b"short"));
+    }
+}
@@ -0,0 +1,241 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::UNIX_EPOCH;
+
+use crate::{deadline, exit_codes, get_state_dir, tuning};
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct Stamp {
+    size: u64,
+    mtime_ns: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct Entry {
+    stamp: Stamp,
+    hash: String,
+}
+
+// Absolute path -> stamp and content hash of the blob holding its content
+type Index = HashMap<String, Entry>;
+
+enum Loaded {
+    Hit(String),
+    Miss(String, Entry),
+}
+
+fn cache_dir() -> PathBuf {
+    get_state_dir().join("cache")
+}
+
+fn index_path() -> PathBuf {
+    cache_dir().join("index.json")
+}
+
+fn blob_path(hash: &str) -> PathBuf {
+    cache_dir().join("blobs").join(hash)
+}
+
+fn load_index() -> Index {
+    fs::read_to_string(index_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_index(index: &Index) {
+    let path = index_path();
+    let result = fs::create_dir_all(cache_dir())
+        .and_then(|_| fs::write(&path, serde_json::to_string(index).unwrap_or_default()));
+    if let Err(err) = result {
+        eprintln!("Failed to write cache index {}: {}", path.display(), err);
+    }
+}
+
+pub fn stamp(path: &Path) -> io::Result<Stamp> {
+    let metadata = fs::metadata(path)?;
+    let mtime_ns = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos() as u64)
+        .unwrap_or_default();
+    Ok(Stamp {
+        size: metadata.len(),
+        mtime_ns,
+    })
+}
+
+fn hash_content(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
+fn cache_key(path: &Path) -> String {
+    fs::canonicalize(path)
+        .unwrap_or_else(|_| path.to_path_buf())
+        .to_string_lossy()
+        .to_string()
+}
+
+fn load_file(path: &Path, index: &Index) -> io::Result<Loaded> {
+    let current = stamp(path)?;
+
+    if let Some(entry) = index.get(&cache_key(path)) {
+        if entry.stamp == current {
+            if let Ok(content) = fs::read_to_string(blob_path(&entry.hash)) {
+                return Ok(Loaded::Hit(content));
+            }
+        }
+    }
+
+    let content = deadline::read_to_string(path)?;
+    let hash = hash_content(&content);
+    Ok(Loaded::Miss(
+        content,
+        Entry {
+            stamp: current,
+            hash,
+        },
+    ))
+}
+
+// Reads (and on a miss hashes) files on `threads` threads, keeping the input order
+fn read_parallel<T: Send>(paths: &[PathBuf], read: impl Fn(&Path) -> T + Sync) -> Vec<T> {
+    let threads = tuning::threads();
+    let chunk_size = paths.len().div_ceil(threads).max(1);
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = paths
+            .chunks(chunk_size)
+            .map(|chunk| {
+                let read = &read;
+                scope.spawn(move || chunk.iter().map(|path| read(path)).collect::<Vec<T>>())
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("File reader thread panicked"))
+            .collect()
+    })
+}
+
+fn store_blob(entry: &Entry, content: &str) {
+    let path = blob_path(&entry.hash);
+    if path.exists() {
+        return;
+    }
+    let result = fs::create_dir_all(cache_dir().join("blobs")).and_then(|_| fs::write(&path, content));
+    if let Err(err) = result {
+        eprintln!("Failed to write cache blob {}: {}", path.display(), err);
+    }
+}
+
+pub fn read_files(paths: &[PathBuf], use_cache: bool) -> Vec<io::Result<String>> {
+    if !use_cache {
+        return read_parallel(paths, deadline::read_to_string);
+    }
+
+    let mut index = load_index();
+    let loaded = read_parallel(paths, |path| load_file(path, &index));
+
+    let mut hits = 0;
+    let mut replaced = Vec::new();
+    let mut contents = Vec::with_capacity(paths.len());
+    for (path, result) in paths.iter().zip(loaded) {
+        contents.push(match result {
+            Ok(Loaded::Hit(content)) => {
+                hits += 1;
+                Ok(content)
+            }
+            Ok(Loaded::Miss(content, entry)) => {
+                store_blob(&entry, &content);
+                if let Some(previous) = index.insert(cache_key(path), entry) {
+                    replaced.push(previous.hash);
+                }
+                Ok(content)
+            }
+            Err(err) => Err(err),
+        });
+    }
+
+    if hits < paths.len() {
+        save_index(&index);
+
+        // Drop blobs of old file versions nothing points at anymore
+        let referenced: HashSet<&str> = index.values().map(|entry| entry.hash.as_str()).collect();
+        for hash in replaced.iter().filter(|hash| !referenced.contains(hash.as_str())) {
+            let _ = fs::remove_file(blob_path(hash));
+        }
+    }
+    info!("Cache: {} of {} files unchanged", hits, paths.len());
+
+    contents
+}
+
+fn dir_size(dir: &Path) -> (usize, u64) {
+    let mut count = 0;
+    let mut size = 0;
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            if let Ok(metadata) = entry.metadata() {
+                count += 1;
+                size += metadata.len();
+            }
+        }
+    }
+    (count, size)
+}
+
+fn status() {
+    let index = load_index();
+    let (blob_count, blob_size) = dir_size(&cache_dir().join("blobs"));
+
+    let mut stale = 0;
+    for (path, entry) in &index {
+        match stamp(Path::new(path)) {
+            Ok(current) if current == entry.stamp => {}
+            _ => stale += 1,
+        }
+    }
+
+    let referenced: HashSet<&str> = index.values().map(|entry| entry.hash.as_str()).collect();
+
+    info!("Cache directory: {}", cache_dir().display());
+    info!("Indexed files:   {} ({} stale)", index.len(), stale);
+    info!(
+        "Stored blobs:    {} ({} unreferenced), {:.1} KB",
+        blob_count,
+        blob_count.saturating_sub(referenced.len()),
+        blob_size as f64 / 1024.0
+    );
+}
+
+fn clear() {
+    let dir = cache_dir();
+    if !dir.exists() {
+        info!("Cache is already empty");
+        return;
+    }
+    match fs::remove_dir_all(&dir) {
+        Ok(_) => info!("Cleared cache at {}", dir.display()),
+        Err(err) => {
+            eprintln!("Failed to clear cache {}: {}", dir.display(), err);
+            std::process::exit(1);
+        }
+    }
+}
+
+pub fn run(args: &[String]) {
+    match args.first().map(String::as_str) {
+        Some("status") if args.len() == 1 => status(),
+        Some("clear") if args.len() == 1 => clear(),
+        _ => {
+            eprintln!("Usage: fdllm cache <status|clear>");
+            std::process::exit(exit_codes::CONFIG);
+        }
+    }
+}
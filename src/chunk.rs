@@ -0,0 +1,293 @@
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::path::Path;
+
+use crate::{lang, tokens};
+
+// Lines per window and the step between window starts (the difference overlaps neighbours)
+const CHUNK_LINES: usize = 40;
+const CHUNK_STEP: usize = 30;
+
+// Tokens per window, and roughly how many of them the next window repeats
+const CHUNK_TOKENS: usize = 400;
+const OVERLAP_TOKENS: usize = 100;
+
+// Structural chunks shorter than this are joined with the next one; longer ones are split
+// along their inner items, or into windows when there are none
+const MIN_CHUNK_LINES: usize = 8;
+const MAX_CHUNK_LINES: usize = 80;
+
+// How a file is cut into the chunks that are embedded and searched
+#[derive(Deserialize, JsonSchema, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Chunker {
+    // Overlapping windows of 40 lines (default for everything but markdown)
+    Lines,
+    // Overlapping windows of ~400 tokens, cut at line ends
+    Tokens,
+    // One chunk per top-level item (function, type, impl block, class) with its doc comments
+    // and attributes; found from indentation and brackets, so it works for any language
+    Functions,
+    // One chunk per markdown heading and the text under it (default for markdown)
+    Sections,
+}
+
+// Per-language chunkers, e.g. { default = "functions", markdown = "sections", json = "tokens" }
+pub type Chunking = BTreeMap<String, Chunker>;
+
+impl Chunker {
+    // Stored with each indexed file, so changing a language's chunker re-chunks its files
+    pub fn name(self) -> &'static str {
+        match self {
+            Chunker::Lines => "lines",
+            Chunker::Tokens => "tokens",
+            Chunker::Functions => "functions",
+            Chunker::Sections => "sections",
+        }
+    }
+}
+
+// The file's language entry, then `default`, then the built-in choice
+pub fn chunker_for(path: &Path, chunking: Option<&Chunking>) -> Chunker {
+    let language = lang::language_for(path);
+    chunking
+        .and_then(|chunking| language.and_then(|language| chunking.get(language)).or_else(|| chunking.get("default")))
+        .copied()
+        .unwrap_or(match language {
+            Some("markdown") => Chunker::Sections,
+            _ => Chunker::Lines,
+        })
+}
+
+fn line_windows(lines: Range<usize>) -> Vec<Range<usize>> {
+    let mut windows = Vec::new();
+    let mut start = lines.start;
+    loop {
+        let end = (start + CHUNK_LINES).min(lines.end);
+        windows.push(start..end);
+        if end >= lines.end {
+            break;
+        }
+        start += CHUNK_STEP;
+    }
+    windows
+}
+
+fn token_windows(lines: &[&str]) -> Vec<Range<usize>> {
+    // The newline counts too
+    let counts: Vec<usize> = lines.iter().map(|line| tokens::count(line) + 1).collect();
+    let mut windows = Vec::new();
+    let mut start = 0;
+    while start < lines.len() {
+        let mut end = start;
+        let mut used = 0;
+        while end < lines.len() && (end == start || used + counts[end] <= CHUNK_TOKENS) {
+            used += counts[end];
+            end += 1;
+        }
+        windows.push(start..end);
+        if end == lines.len() {
+            break;
+        }
+        // Back up over the window's last lines for the overlap, always moving forward
+        let mut next = end;
+        let mut repeated = 0;
+        while next > start + 1 && repeated + counts[next - 1] <= OVERLAP_TOKENS {
+            next -= 1;
+            repeated += counts[next];
+        }
+        start = next;
+    }
+    if windows.is_empty() {
+        windows.push(0..0);
+    }
+    windows
+}
+
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+// Comments, attributes and decorators belong to the item below them
+fn is_preamble(trimmed: &str) -> bool {
+    ["//", "/*", "*", "#", "@", "--"].iter().any(|prefix| trimmed.starts_with(prefix))
+}
+
+// Lines that carry on the item above them: closing brackets, an opening brace on its own
+// line, and `else`-style clauses
+fn is_continuation(trimmed: &str) -> bool {
+    let word = trimmed.split(|c: char| !c.is_alphanumeric() && c != '_').next().unwrap_or("");
+    trimmed.starts_with(['}', ')', ']', '{'])
+        || ["else", "elif", "except", "finally", "catch", "end"].contains(&word)
+}
+
+// Splits `range` before each line at its outermost indentation that starts a new item
+fn items(lines: &[&str], range: Range<usize>) -> Vec<Range<usize>> {
+    let base = lines[range.clone()]
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| indent(line))
+        .min();
+    let Some(base) = base else {
+        return vec![range];
+    };
+
+    let mut starts = vec![range.start];
+    let mut previous: Option<&str> = None;
+    for index in range.clone() {
+        let line = lines[index];
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let glued = previous.is_some_and(|previous| indent(previous) == base && is_preamble(previous.trim_start()));
+        if previous.is_some() && indent(line) == base && !glued && !is_continuation(trimmed) {
+            starts.push(index);
+        }
+        previous = Some(line);
+    }
+    starts.dedup();
+    starts.push(range.end);
+    starts.windows(2).map(|pair| pair[0]..pair[1]).collect()
+}
+
+// Joins each chunk shorter than MIN_CHUNK_LINES with the ones after it
+fn join_short(chunks: Vec<Range<usize>>) -> Vec<Range<usize>> {
+    let mut joined: Vec<Range<usize>> = Vec::new();
+    for chunk in chunks {
+        match joined.last_mut() {
+            Some(last) if last.len() < MIN_CHUNK_LINES => last.end = chunk.end,
+            _ => joined.push(chunk),
+        }
+    }
+    joined
+}
+
+// An oversized item is cut along the items inside it (an impl block along its methods), its
+// first line going with the first of them and its closing line with the last
+fn split_item(lines: &[&str], item: Range<usize>) -> Vec<Range<usize>> {
+    if item.len() <= MAX_CHUNK_LINES {
+        return vec![item];
+    }
+    let Some(header) = item.clone().find(|index| !lines[*index].trim().is_empty()) else {
+        return vec![item];
+    };
+    let inner = |index: &usize| !lines[*index].trim().is_empty() && indent(lines[*index]) > indent(lines[header]);
+    let (Some(first), Some(last)) = (item.clone().find(inner), item.clone().rev().find(inner)) else {
+        return line_windows(item);
+    };
+
+    let mut parts = join_short(items(lines, first..last + 1));
+    if parts.len() < 2 {
+        return line_windows(item);
+    }
+    parts[0].start = item.start;
+    if let Some(part) = parts.last_mut() {
+        part.end = item.end;
+    }
+    parts.into_iter().flat_map(|part| split_item(lines, part)).collect()
+}
+
+fn function_chunks(lines: &[&str]) -> Vec<Range<usize>> {
+    join_short(items(lines, 0..lines.len()))
+        .into_iter()
+        .flat_map(|item| split_item(lines, item))
+        .collect()
+}
+
+// Headings outside fenced code blocks start a section; long sections become windows
+fn section_chunks(lines: &[&str]) -> Vec<Range<usize>> {
+    let mut starts = vec![0];
+    let mut fence: Option<&str> = None;
+    for (index, line) in lines.iter().enumerate() {
+        let trimmed = line.trim_start();
+        let marker = ["```", "~~~"].into_iter().find(|marker| trimmed.starts_with(marker));
+        match (fence, marker) {
+            (None, Some(marker)) => fence = Some(marker),
+            (Some(open), Some(marker)) if open == marker => fence = None,
+            (None, None) if indent(line) < 4 && trimmed.starts_with('#') => {
+                let text = trimmed.trim_start_matches('#');
+                if text.is_empty() || text.starts_with(' ') {
+                    starts.push(index);
+                }
+            }
+            _ => {}
+        }
+    }
+    starts.dedup();
+    starts.push(lines.len());
+    let sections: Vec<Range<usize>> = starts.windows(2).map(|pair| pair[0]..pair[1]).collect();
+    join_short(sections)
+        .into_iter()
+        .flat_map(|section| {
+            if section.len() > MAX_CHUNK_LINES {
+                line_windows(section)
+            } else {
+                vec![section]
+            }
+        })
+        .collect()
+}
+
+// 1-based, inclusive line ranges of the chunks `content` is embedded as
+pub fn chunk_ranges(chunker: Chunker, content: &str) -> Vec<(usize, usize)> {
+    let lines: Vec<&str> = content.lines().collect();
+    let chunks = match chunker {
+        Chunker::Lines => line_windows(0..lines.len()),
+        Chunker::Tokens => token_windows(&lines),
+        Chunker::Functions => function_chunks(&lines),
+        Chunker::Sections => section_chunks(&lines),
+    };
+    chunks
+        .into_iter()
+        .map(|chunk| {
+            // Blank lines between items stay out of the range
+            let mut end = chunk.end;
+            while end > chunk.start + 1 && lines[end - 1].trim().is_empty() {
+                end -= 1;
+            }
+            (chunk.start + 1, end.max(chunk.start + 1))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_the_chunker_per_language() {
+        let chunking = Chunking::from([("rust".to_string(), Chunker::Functions), ("default".to_string(), Chunker::Tokens)]);
+        assert!(chunker_for(Path::new("a.rs"), Some(&chunking)) == Chunker::Functions);
+        assert!(chunker_for(Path::new("a.py"), Some(&chunking)) == Chunker::Tokens);
+        assert!(chunker_for(Path::new("a.md"), None) == Chunker::Sections);
+        assert!(chunker_for(Path::new("a.py"), None) == Chunker::Lines);
+    }
+
+    #[test]
+    fn line_windows_overlap_and_cover_the_file() {
+        let content: String = (1..=100).map(|line| format!("line {}\n", line)).collect();
+        let ranges = chunk_ranges(Chunker::Lines, &content);
+        assert_eq!(ranges.first(), Some(&(1, CHUNK_LINES)));
+        assert_eq!(ranges.last().map(|range| range.1), Some(100));
+        for pair in ranges.windows(2) {
+            assert_eq!(pair[1].0, pair[0].0 + CHUNK_STEP);
+        }
+    }
+
+    #[test]
+    fn sections_start_at_headings() {
+        let section = |title: &str| format!("# {}\n{}", title, "text\n".repeat(MIN_CHUNK_LINES));
+        let content = format!("{}{}```\n# not a heading\n```\n", section("One"), section("Two"));
+        let size = MIN_CHUNK_LINES + 1;
+        assert_eq!(chunk_ranges(Chunker::Sections, &content), [(1, size), (size + 1, 2 * size + 3)]);
+    }
+
+    #[test]
+    fn short_sections_join_the_next() {
+        let content = "# One\ntext\n\n# Two\nmore\n";
+        assert_eq!(chunk_ranges(Chunker::Sections, content), [(1, 5)]);
+    }
+}
@@ -0,0 +1,360 @@
+use clap::{Args, CommandFactory, FromArgMatches, Parser, Subcommand};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::{clipboard, deadline, file_size, format, git, redact, send_keys, template};
+
+#[derive(Parser)]
+#[command(
+    name = "fdllm",
+    version,
+    about = "Bundle files, project trees and schemas into one paste for an LLM",
+    after_help = "A bare profile name works as well: `fdllm backend` is `fdllm copy backend`."
+)]
+pub struct Cli {
+    #[arg(long, help = "List the exit statuses fdllm uses and what each means")]
+    pub print_exit_codes: bool,
+    #[command(flatten)]
+    pub global: GlobalArgs,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+// Accepted anywhere on the command line, before or after the subcommand's own arguments
+#[derive(Args)]
+pub struct GlobalArgs {
+    #[arg(long, global = true, value_name = "FILE", help = "Use this config file instead of the default one")]
+    pub config: Option<PathBuf>,
+    #[arg(long, global = true, help = "Don't copy to the clipboard (other outputs still apply)")]
+    pub no_clipboard: bool,
+    #[arg(long, global = true, value_name = "BACKEND", value_parser = parse_clipboard_backend, help = "Reach the clipboard through auto, native, wl-copy or osc52 (the terminal, over SSH too)")]
+    pub clipboard_backend: Option<clipboard::ClipboardBackend>,
+    #[arg(short, long, global = true, help = "Report each file as it is added")]
+    pub verbose: bool,
+    #[arg(long, global = true, help = "Print stable machine-readable records on stdout")]
+    pub porcelain: bool,
+    #[arg(long, global = true, value_name = "DURATION", value_parser = parse_timeout, help = "Deliver whatever is collected by then, e.g. 30s, 2m or 500ms")]
+    pub timeout: Option<Duration>,
+    #[arg(long, global = true, help = "Keep config and state next to the executable")]
+    pub portable: bool,
+    #[arg(long, global = true, value_name = "PRESET", value_parser = parse_redaction, help = "Override the redaction preset: strict, standard or off")]
+    pub redaction: Option<redact::Redaction>,
+    #[arg(long, global = true, help = "Deliver the bundle even when `scan = \"block\"` finds likely secrets")]
+    pub allow_secrets: bool,
+    #[arg(long, global = true, value_name = "PATH", help = "Write the bundle to this file instead of the profile's output_path")]
+    pub output: Option<String>,
+    #[arg(long, global = true, conflicts_with_all = ["porcelain", "diff_output"], help = "Print the bundle on stdout instead of copying it (e.g. `fdllm backend --stdout | llm`)")]
+    pub stdout: bool,
+    #[arg(long, global = true, value_name = "TARGET", value_parser = parse_send_keys, conflicts_with = "diff_output", help = "Paste the bundle into a tmux pane instead of copying it, e.g. tmux:%3 or tmux:work:1.2")]
+    pub send_keys: Option<send_keys::SendKeys>,
+    #[arg(long, global = true, requires = "send_keys", help = "Paste the path of a file holding the bundle instead of the bundle")]
+    pub send_path: bool,
+    #[arg(long, global = true, help = "Make the bundle byte-identical for the same inputs: no timestamps, sorted files, the built-in tree, C locale")]
+    pub deterministic: bool,
+    #[arg(long, global = true, help = "Include files that .gitignore and friends would skip")]
+    pub no_gitignore: bool,
+    #[arg(long, global = true, conflicts_with_all = ["stdout", "diff_output"], help = "List each file with its size and token count, and the total, without copying or writing anything")]
+    pub dry_run: bool,
+    #[arg(long, global = true, help = "Describe what the run will collect before collecting it")]
+    pub explain: bool,
+    #[arg(long, global = true, help = "Print a diff against the existing output file instead of delivering")]
+    pub diff_output: bool,
+    #[arg(long, global = true, value_name = "TEXT", help = "Add this request after the files, e.g. --prompt \"Review this code for bugs\"")]
+    pub prompt: Option<String>,
+    #[arg(long, global = true, value_name = "FILE", help = "Lay the bundle out with this template instead of the profile's")]
+    pub template: Option<String>,
+    #[arg(long = "var", global = true, value_name = "KEY=VALUE", value_parser = parse_var, help = "Set a template variable (repeatable)")]
+    pub vars: Vec<(String, String)>,
+    #[arg(long, global = true, value_name = "FORMAT", value_parser = parse_format, help = "Lay the bundle out as plain, markdown, xml, aider or cursor")]
+    pub format: Option<format::Format>,
+    #[arg(long, global = true, value_name = "SELECTION", value_parser = parse_git_selection, help = "Keep only files that are changed, staged, or differ on this branch (`--git branch main`)")]
+    pub git: Option<git::Selection>,
+    #[arg(long, global = true, conflicts_with = "git", help = "Bundle the files in a merge conflict, each with its base, ours and theirs versions")]
+    pub conflicts: bool,
+    #[arg(long, global = true, value_name = "WHAT", help = "Add `git diff` as a section: unstaged, staged, or a ref to compare against")]
+    pub git_diff: Option<String>,
+    #[arg(long, global = true, help = "Fail on an unknown profile name without suggesting or asking")]
+    pub no_suggest: bool,
+    #[arg(long, global = true, help = "Reduce Rust files to item signatures and their /// docs, dropping bodies")]
+    pub docs_only: bool,
+    #[arg(long, global = true, help = "List binary files with a one-line note instead of skipping them")]
+    pub include_binary_as_note: bool,
+    #[arg(long = "file", global = true, value_name = "PATH", help = "Bundle this file too, or on its own without a profile (repeatable)")]
+    pub files: Vec<String>,
+    #[arg(long = "dir", global = true, value_name = "PATH", help = "Bundle the files below this directory too (repeatable)")]
+    pub dirs: Vec<String>,
+    #[arg(long, global = true, help = "Bundle the paths piped in, one per line (or NUL-separated), e.g. `git ls-files '*.rs' | fdllm --stdin`")]
+    pub stdin: bool,
+    #[arg(long, global = true, value_name = "PATH", help = "Add the project tree of this directory")]
+    pub tree: Option<String>,
+    #[arg(short = 'L', long, global = true, value_name = "DEPTH", requires = "tree", help = "Depth of the --tree")]
+    pub level: Option<u32>,
+    #[arg(long, global = true, value_name = "TOKENS", help = "Token budget for the whole bundle")]
+    pub max_tokens: Option<usize>,
+    #[arg(long, global = true, value_name = "SIZE", value_parser = parse_file_size, help = "Skip (or cut, per large_files) files larger than this, e.g. 512KB or 2MB")]
+    pub max_file_size: Option<u64>,
+}
+
+fn parse_timeout(value: &str) -> Result<Duration, String> {
+    deadline::parse_duration(value).ok_or_else(|| "expected a duration such as 30s, 2m or 500ms".to_string())
+}
+
+fn parse_redaction(value: &str) -> Result<redact::Redaction, String> {
+    redact::Redaction::parse(value).ok_or_else(|| "expected one of: strict, standard, off".to_string())
+}
+
+fn parse_git_selection(value: &str) -> Result<git::Selection, String> {
+    git::Selection::parse(value).ok_or_else(|| "expected changed, staged or `branch <base>`".to_string())
+}
+
+fn parse_file_size(value: &str) -> Result<u64, String> {
+    file_size::parse_size(value).ok_or_else(|| "expected a size such as 512KB or 2MB".to_string())
+}
+
+fn parse_clipboard_backend(value: &str) -> Result<clipboard::ClipboardBackend, String> {
+    clipboard::ClipboardBackend::parse(value).ok_or_else(|| "expected one of: auto, native, wl-copy, osc52".to_string())
+}
+
+fn parse_send_keys(value: &str) -> Result<send_keys::SendKeys, String> {
+    send_keys::SendKeys::parse(value).ok_or_else(|| "expected tmux:PANE, e.g. tmux:%3".to_string())
+}
+
+fn parse_var(value: &str) -> Result<(String, String), String> {
+    template::parse_var(value).ok_or_else(|| "expected KEY=VALUE".to_string())
+}
+
+fn parse_format(value: &str) -> Result<format::Format, String> {
+    format::Format::parse(value).ok_or_else(|| "expected one of: plain, markdown, xml, aider, cursor".to_string())
+}
+
+// Subcommands that parse their own arguments
+#[derive(Args)]
+pub struct Passthrough {
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    pub args: Vec<String>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    #[command(about = "Bundle one or more profiles (the top-level configuration without one)")]
+    Copy {
+        #[arg(help = "Profiles to bundle together, e.g. `fdllm copy frontend backend`, or an expression such as \"backend + shared - backend:tests\"")]
+        profiles: Vec<String>,
+        #[arg(long = "profiles", value_name = "NAMES", value_delimiter = ',', help = "More profiles to bundle, comma-separated")]
+        more_profiles: Vec<String>,
+    },
+    #[command(about = "List, show, add or remove profiles")]
+    Profiles {
+        #[command(subcommand)]
+        command: Option<ProfilesCommand>,
+    },
+    #[command(about = "Edit the config or print its JSON Schema")]
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+    #[command(about = "Write a starter config")]
+    Init,
+    #[command(about = "Walk the largest files of an over-budget profile, excluding, outlining or truncating each")]
+    Trim {
+        #[arg(help = "The profile to trim (the top-level configuration without one)")]
+        profile: Option<String>,
+        #[arg(long, help = "Save the decisions into the profile without asking")]
+        save: bool,
+    },
+    #[command(about = "Bundle the current cargo workspace")]
+    Cargo(Passthrough),
+    #[command(about = "Bundle the current node project")]
+    Node(Passthrough),
+    #[command(about = "Bundle the current python project")]
+    Python(Passthrough),
+    #[command(about = "Bundle infrastructure-as-code files")]
+    Iac(Passthrough),
+    #[command(about = "Bundle old and new versions of the files changed between two git refs")]
+    Compare(Passthrough),
+    #[command(about = "Run a command and bundle the files its errors point at")]
+    Errors(Passthrough),
+    #[command(about = "Run tests and bundle the failing ones with the code they exercise")]
+    Failing(Passthrough),
+    #[command(about = "Bundle a dependency's source from Cargo.lock")]
+    Dep(Passthrough),
+    #[command(about = "Bundle the project files behind the stack traces in a log")]
+    Triage(Passthrough),
+    #[command(about = "Inspect or clear the content cache")]
+    Cache(Passthrough),
+    #[command(about = "Open a `path:line` reference from a bundle in $EDITOR")]
+    Open(Passthrough),
+    #[command(about = "Pick the files most relevant to a question")]
+    Auto(Passthrough),
+    #[command(about = "Build the lexical (word and identifier) search index for a profile")]
+    Index(Passthrough),
+    #[command(about = "Search the lexical index for chunks sharing the query's words")]
+    Semsearch(Passthrough),
+    #[command(about = "Show token statistics from past runs")]
+    Stats(Passthrough),
+    #[command(about = "Show how often profiles run and which files they include most")]
+    Usage(Passthrough),
+    #[command(about = "Write a profile's files into a context pack")]
+    Pack(Passthrough),
+    #[command(about = "Extract a context pack and bundle it")]
+    Unpack(Passthrough),
+    #[command(about = "Leave paths out for this session")]
+    Exclude(Passthrough),
+    #[command(about = "Add paths for this session")]
+    Add(Passthrough),
+    #[command(about = "Show or reset session overrides")]
+    Session(Passthrough),
+    #[command(about = "Show which profiles include a path")]
+    Which(Passthrough),
+    #[command(about = "Show which profiles have files matching a pattern")]
+    GrepProfiles(Passthrough),
+    #[command(about = "Flag profiles with missing paths, stale directories or oversized bundles")]
+    Audit(Passthrough),
+    #[command(about = "Bundle text piped on stdin, or queue it as a snippet")]
+    Snip(Passthrough),
+    #[command(about = "Collect clipboard snippets for the next bundle")]
+    ClipWatch(Passthrough),
+}
+
+#[derive(Subcommand)]
+pub enum ProfilesCommand {
+    #[command(about = "List the configured profiles")]
+    List,
+    #[command(about = "Print a profile as written in the config")]
+    Show { name: String },
+    #[command(about = "Add a profile to the config")]
+    Add {
+        name: String,
+        #[arg(long, num_args = 1.., value_name = "PATH", help = "Files to bundle; relative paths are made absolute")]
+        files: Vec<String>,
+        #[arg(long, num_args = 1.., value_name = "DIR", help = "Directories to walk; relative paths are made absolute")]
+        dirs: Vec<String>,
+    },
+    #[command(about = "Remove a profile from the config")]
+    Remove { name: String },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommand {
+    #[command(about = "Open the config in $VISUAL or $EDITOR")]
+    Edit,
+    #[command(about = "Print a JSON Schema for config.toml")]
+    Schema,
+}
+
+// The global flags, long and short, and whether each takes a value (e.g. `--timeout 30s`, `-L 2`)
+fn global_flags() -> Vec<(String, bool)> {
+    Cli::command()
+        .get_arguments()
+        .filter(|arg| arg.is_global_set())
+        .flat_map(|arg| {
+            let takes_value = arg.get_action().takes_values();
+            let long = arg.get_long().map(|long| (format!("--{}", long), takes_value));
+            let short = arg.get_short().map(|short| (format!("-{}", short), takes_value));
+            long.into_iter().chain(short)
+        })
+        .collect()
+}
+
+// Keeps the old command line working under clap: a bare profile name becomes `copy <profile>`,
+// and global flags given after a subcommand's own arguments are moved in front of them
+pub fn normalize(mut args: Vec<String>) -> Vec<String> {
+    // `--git branch main` is one value to clap
+    if let Some(index) = args.iter().position(|arg| arg == "--git") {
+        if args.get(index + 1).map(String::as_str) == Some("branch") && index + 2 < args.len() {
+            let base = args.remove(index + 2);
+            args[index + 1] = format!("branch={}", base);
+        }
+    }
+
+    let globals = global_flags();
+    let takes_value = |arg: &str| globals.iter().any(|(flag, value)| flag == arg && *value);
+    let is_global = |arg: &str| globals.iter().any(|(flag, _)| flag == arg);
+
+    let mut position = 1;
+    while position < args.len() && args[position].starts_with('-') {
+        // `fdllm --profiles a,b` is `fdllm copy --profiles a,b`
+        if args[position] == "--profiles" || args[position].starts_with("--profiles=") {
+            args.insert(position, "copy".to_string());
+            return args;
+        }
+        position += if takes_value(&args[position]) { 2 } else { 1 };
+    }
+    let Some(first) = args.get(position) else {
+        return args;
+    };
+    if !is_subcommand(first) {
+        args.insert(position, "copy".to_string());
+        return args;
+    }
+
+    let mut rest = args.split_off(position + 1);
+    let mut lifted = Vec::new();
+    let mut index = 0;
+    // Everything after `--` belongs to the command being wrapped (`fdllm errors -- cargo test`)
+    while index < rest.len() && rest[index] != "--" {
+        if is_global(&rest[index]) {
+            let count = if takes_value(&rest[index]) { 2 } else { 1 };
+            lifted.extend(rest.drain(index..(index + count).min(rest.len())));
+        } else {
+            index += 1;
+        }
+    }
+    args.extend(lifted);
+    args.extend(rest);
+    args
+}
+
+// Such a name always runs the subcommand: a profile called `node` is only reached as `copy node`
+pub fn is_subcommand(name: &str) -> bool {
+    name == "help" || Cli::command().get_subcommands().any(|subcommand| subcommand.get_name() == name)
+}
+
+// The parsed command line and the subcommand's name, which runs are recorded under
+pub fn parse() -> (Cli, Option<String>) {
+    let matches = Cli::command().get_matches_from(normalize(std::env::args().collect()));
+    let subcommand = matches.subcommand_name().map(str::to_string);
+    match Cli::from_arg_matches(&matches) {
+        Ok(cli) => (cli, subcommand),
+        Err(err) => err.exit(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalized(args: &[&str]) -> Vec<String> {
+        normalize(args.iter().map(|arg| arg.to_string()).collect())
+    }
+
+    #[test]
+    fn inserts_copy_before_a_profile_name() {
+        assert_eq!(normalized(&["fdllm", "backend", "-v"]), ["fdllm", "copy", "backend", "-v"]);
+        assert_eq!(normalized(&["fdllm", "--timeout", "5s", "backend"]), ["fdllm", "--timeout", "5s", "copy", "backend"]);
+    }
+
+    #[test]
+    fn skips_values_of_short_global_flags() {
+        assert_eq!(
+            normalized(&["fdllm", "--file", "src/main.rs", "--tree", ".", "-L", "2"]),
+            ["fdllm", "--file", "src/main.rs", "--tree", ".", "-L", "2"]
+        );
+        assert_eq!(normalized(&["fdllm", "-L", "2", "src"]), ["fdllm", "-L", "2", "copy", "src"]);
+    }
+
+    #[test]
+    fn profile_names_can_shadow_subcommands() {
+        assert!(is_subcommand("node") && is_subcommand("init") && is_subcommand("help"));
+        assert!(!is_subcommand("backend"));
+        assert_eq!(normalized(&["fdllm", "node"]), ["fdllm", "node"]);
+    }
+
+    #[test]
+    fn lifts_global_flags_after_a_subcommand() {
+        assert_eq!(
+            normalized(&["fdllm", "cargo", "--all", "-L", "3", "-v", "--", "-L"]),
+            ["fdllm", "cargo", "-L", "3", "-v", "--all", "--", "-L"]
+        );
+    }
+}
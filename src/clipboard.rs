@@ -0,0 +1,123 @@
+use base64::Engine;
+use copypasta::{ClipboardContext, ClipboardProvider};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::env;
+use std::fs::OpenOptions;
+use std::io::{self, IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+// How the bundle reaches the clipboard
+#[derive(Deserialize, JsonSchema, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum ClipboardBackend {
+    // wl-copy on Wayland, then the native clipboard, then OSC 52 from a terminal
+    #[default]
+    Auto,
+    // X11, macOS or Windows clipboard through the system APIs
+    Native,
+    // `wl-copy` from wl-clipboard, for Wayland-only sessions
+    WlCopy,
+    // An escape sequence asking the terminal to set its clipboard; works over SSH and in tmux
+    Osc52,
+}
+
+impl ClipboardBackend {
+    pub fn parse(value: &str) -> Option<ClipboardBackend> {
+        match value {
+            "auto" => Some(ClipboardBackend::Auto),
+            "native" => Some(ClipboardBackend::Native),
+            "wl-copy" => Some(ClipboardBackend::WlCopy),
+            "osc52" => Some(ClipboardBackend::Osc52),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ClipboardBackend::Auto => "auto",
+            ClipboardBackend::Native => "native",
+            ClipboardBackend::WlCopy => "wl-copy",
+            ClipboardBackend::Osc52 => "osc52",
+        }
+    }
+}
+
+fn env_set(name: &str) -> bool {
+    env::var_os(name).is_some_and(|value| !value.is_empty())
+}
+
+fn native(content: &str) -> Result<(), String> {
+    let mut ctx = ClipboardContext::new().map_err(|err| format!("Failed to access clipboard: {}", err))?;
+    ctx.set_contents(content.to_string())
+        .map_err(|err| format!("Failed to copy to clipboard: {}", err))
+}
+
+fn wl_copy(content: &str) -> Result<(), String> {
+    let mut child = Command::new("wl-copy")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .map_err(|err| match err.kind() {
+            io::ErrorKind::NotFound => "wl-copy not found (install wl-clipboard)".to_string(),
+            _ => format!("Failed to run wl-copy: {}", err),
+        })?;
+    let written = child.stdin.take().expect("stdin is piped").write_all(content.as_bytes());
+    let status = child.wait().map_err(|err| format!("Failed to run wl-copy: {}", err))?;
+    match (written, status.success()) {
+        (Ok(()), true) => Ok(()),
+        (Err(err), _) => Err(format!("Failed to write to wl-copy: {}", err)),
+        (_, false) => Err(format!("wl-copy failed ({})", status)),
+    }
+}
+
+// The terminal can't report whether it honoured the request, so this only fails without one
+fn osc52(content: &str) -> Result<(), String> {
+    let payload = base64::engine::general_purpose::STANDARD.encode(content);
+    let mut sequence = format!("\x1b]52;c;{}\x07", payload);
+    // tmux passes it on to the outer terminal only when wrapped
+    if env_set("TMUX") {
+        sequence = format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"));
+    }
+    let written = match OpenOptions::new().write(true).open("/dev/tty") {
+        Ok(mut tty) => tty.write_all(sequence.as_bytes()).and_then(|_| tty.flush()),
+        Err(_) if io::stderr().is_terminal() => {
+            let mut stderr = io::stderr().lock();
+            stderr.write_all(sequence.as_bytes()).and_then(|_| stderr.flush())
+        }
+        Err(_) => return Err("OSC 52 needs a terminal".to_string()),
+    };
+    written.map_err(|err| format!("Failed to write to the terminal: {}", err))
+}
+
+// Copies `content` and returns the backend that took it; `auto` reports every failure
+pub fn copy(content: &str, backend: ClipboardBackend) -> Result<ClipboardBackend, String> {
+    let attempts = match backend {
+        ClipboardBackend::Auto => {
+            let mut attempts = Vec::new();
+            if env_set("WAYLAND_DISPLAY") {
+                attempts.push(ClipboardBackend::WlCopy);
+            }
+            // Over SSH the native clipboard, if any, is on the wrong machine
+            if !env_set("SSH_CONNECTION") && !env_set("SSH_TTY") {
+                attempts.push(ClipboardBackend::Native);
+            }
+            attempts.push(ClipboardBackend::Osc52);
+            attempts
+        }
+        backend => vec![backend],
+    };
+    let mut errors = Vec::new();
+    for attempt in attempts {
+        let result = match attempt {
+            ClipboardBackend::Native | ClipboardBackend::Auto => native(content),
+            ClipboardBackend::WlCopy => wl_copy(content),
+            ClipboardBackend::Osc52 => osc52(content),
+        };
+        match result {
+            Ok(()) => return Ok(attempt),
+            Err(err) => errors.push(err),
+        }
+    }
+    Err(errors.join("; "))
+}
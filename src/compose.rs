@@ -0,0 +1,189 @@
+use std::path::{Component, Path};
+
+// One operand of `fdllm copy "backend + shared - backend:tests"`
+#[derive(Debug, PartialEq)]
+pub struct Term {
+    pub exclude: bool,
+    pub profile: String,
+    // `backend:tests`: only the profile's files below a `tests` directory, or matching a glob
+    pub subset: Option<String>,
+}
+
+fn term(exclude: bool, word: &str) -> Result<Term, String> {
+    let (profile, subset) = match word.split_once(':') {
+        Some((profile, subset)) => (profile, Some(subset)),
+        None => (word, None),
+    };
+    if profile.is_empty() {
+        return Err(format!("'{}' names no profile", word));
+    }
+    if subset.is_some_and(str::is_empty) {
+        return Err(format!("'{}' names no subset after ':'", word));
+    }
+    Ok(Term {
+        exclude,
+        profile: profile.to_string(),
+        subset: subset.map(str::to_string),
+    })
+}
+
+enum Token {
+    Operator(char),
+    Word(String),
+}
+
+// `+` always joins, `-` only at the start of a word, so `frontend-v2` stays one name
+fn tokenize(arg: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    for c in arg.chars() {
+        if c.is_whitespace() || c == '+' || (c == '-' && word.is_empty()) {
+            if !word.is_empty() {
+                tokens.push(Token::Word(std::mem::take(&mut word)));
+            }
+            if !c.is_whitespace() {
+                tokens.push(Token::Operator(c));
+            }
+        } else {
+            word.push(c);
+        }
+    }
+    if !word.is_empty() {
+        tokens.push(Token::Word(word));
+    }
+    tokens
+}
+
+// Terms apply left to right; separate arguments with no operator between them are added together
+pub fn parse(args: &[String]) -> Result<Vec<Term>, String> {
+    let mut terms = Vec::new();
+    let mut operator = None;
+    for arg in args {
+        let mut first_word = true;
+        for token in tokenize(arg) {
+            match token {
+                Token::Operator(c) => {
+                    if let Some(previous) = operator {
+                        return Err(format!("'{}' follows '{}' with no profile between", c, previous));
+                    }
+                    operator = Some(c);
+                }
+                Token::Word(word) => {
+                    let exclude = match operator.take() {
+                        Some(operator) => operator == '-',
+                        None if first_word => false,
+                        None => return Err(format!("expected + or - before '{}'", word)),
+                    };
+                    terms.push(term(exclude, &word)?);
+                }
+            }
+            first_word = false;
+        }
+    }
+    if let Some(operator) = operator {
+        return Err(format!("'{}' at the end has no profile after it", operator));
+    }
+    match terms.first() {
+        Some(first) if first.exclude => Err(format!("nothing to take '{}' away from", first.profile)),
+        _ => Ok(terms),
+    }
+}
+
+// Whole profiles added together: merged the way `fdllm copy a b` always has been
+pub fn is_plain(terms: &[Term]) -> bool {
+    terms.iter().all(|term| !term.exclude && term.subset.is_none())
+}
+
+// `backend+shared-backend:tests`, which the run is recorded and shown under
+pub fn describe(terms: &[Term]) -> String {
+    let mut described = String::new();
+    for (index, term) in terms.iter().enumerate() {
+        if index > 0 || term.exclude {
+            described.push(if term.exclude { '-' } else { '+' });
+        }
+        described.push_str(&term.profile);
+        if let Some(subset) = &term.subset {
+            described.push(':');
+            described.push_str(subset);
+        }
+    }
+    described
+}
+
+// A plain subset such as `tests` or `src/db` matches consecutive directory names anywhere in the
+// path; one with glob characters is matched against every trailing part of it
+pub fn in_subset(path: &Path, subset: &str) -> bool {
+    let components: Vec<Component> = path.components().filter(|part| matches!(part, Component::Normal(_))).collect();
+    let pattern = subset.contains(['*', '?', '[']).then(|| glob::Pattern::new(subset).ok()).flatten();
+    let options = glob::MatchOptions {
+        require_literal_separator: true,
+        ..Default::default()
+    };
+    (0..components.len()).any(|start| {
+        let suffix: std::path::PathBuf = components[start..].iter().collect();
+        match &pattern {
+            Some(pattern) => pattern.matches_path_with(&suffix, options),
+            None => suffix.starts_with(subset.trim_matches('/')),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn exclude(profile: &str, subset: Option<&str>) -> Term {
+        Term {
+            exclude: true,
+            profile: profile.to_string(),
+            subset: subset.map(str::to_string),
+        }
+    }
+
+    fn include(profile: &str) -> Term {
+        Term {
+            exclude: false,
+            profile: profile.to_string(),
+            subset: None,
+        }
+    }
+
+    #[test]
+    fn parses_expressions() {
+        let terms = parse(&args(&["backend + shared - backend:tests"])).unwrap();
+        assert_eq!(terms, vec![include("backend"), include("shared"), exclude("backend", Some("tests"))]);
+        assert_eq!(describe(&terms), "backend+shared-backend:tests");
+        assert!(!is_plain(&terms));
+
+        assert_eq!(parse(&args(&["backend+shared-docs"])).unwrap()[1].profile, "shared-docs");
+        assert_eq!(parse(&args(&["backend", "-docs"])).unwrap()[1], exclude("docs", None));
+        assert_eq!(parse(&args(&["api", "web-v2"])).unwrap(), vec![include("api"), include("web-v2")]);
+        assert!(is_plain(&parse(&args(&["api", "web"])).unwrap()));
+    }
+
+    #[test]
+    fn rejects_malformed_expressions() {
+        assert!(parse(&args(&["backend +"])).is_err());
+        assert!(parse(&args(&["backend + - shared"])).is_err());
+        assert!(parse(&args(&["- backend"])).is_err());
+        assert!(parse(&args(&["backend shared"])).is_err());
+        assert!(parse(&args(&["backend:"])).is_err());
+        assert!(parse(&args(&[":tests"])).is_err());
+    }
+
+    #[test]
+    fn matches_subsets() {
+        let path = Path::new("/home/me/app/tests/db/schema_test.rs");
+        assert!(in_subset(path, "tests"));
+        assert!(in_subset(path, "tests/db"));
+        assert!(in_subset(path, "*_test.rs"));
+        assert!(in_subset(path, "tests/**/*.rs"));
+        assert!(!in_subset(path, "db/tests"));
+        assert!(!in_subset(path, "test"));
+        assert!(!in_subset(path, "tests/*.rs"));
+    }
+}
@@ -0,0 +1,120 @@
+use std::path::Path;
+
+// Documentation-only keys that can be dropped without changing the API contract
+const DROPPED_KEYS: [&str; 4] = ["description", "example", "examples", "externalDocs"];
+
+// Maps keyed by user-defined names (a property may well be called "description")
+const NAMED_MAPS: [&str; 11] = [
+    "properties",
+    "patternProperties",
+    "definitions",
+    "schemas",
+    "paths",
+    "responses",
+    "parameters",
+    "headers",
+    "securitySchemes",
+    "variables",
+    "callbacks",
+];
+
+fn condense_value(value: &mut serde_json::Value, named_map: bool) {
+    match value {
+        serde_json::Value::Object(map) => {
+            if !named_map {
+                map.retain(|key, _| !DROPPED_KEYS.contains(&key.as_str()) && !key.starts_with("x-"));
+            }
+            for (key, child) in map.iter_mut() {
+                condense_value(child, !named_map && NAMED_MAPS.contains(&key.as_str()));
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                condense_value(item, false);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn is_openapi(document: &serde_json::Value) -> bool {
+    document.get("openapi").is_some() || document.get("swagger").is_some()
+}
+
+fn condense_openapi_json(content: &str) -> Option<String> {
+    let mut document: serde_json::Value = serde_json::from_str(content).ok()?;
+    if !is_openapi(&document) {
+        return None;
+    }
+    condense_value(&mut document, false);
+    serde_json::to_string_pretty(&document).ok()
+}
+
+fn condense_openapi_yaml(content: &str) -> Option<String> {
+    let mut document: serde_json::Value = serde_yaml::from_str(content).ok()?;
+    if !is_openapi(&document) {
+        return None;
+    }
+    condense_value(&mut document, false);
+    serde_yaml::to_string(&document).ok()
+}
+
+// Drops comments and description strings (string literals that start a line)
+fn condense_graphql(content: &str) -> String {
+    let mut condensed = String::new();
+    let mut in_block_string = false;
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+
+        if in_block_string {
+            if trimmed.contains("\"\"\"") {
+                in_block_string = false;
+            }
+            continue;
+        }
+        if let Some(rest) = trimmed.strip_prefix("\"\"\"") {
+            in_block_string = !rest.contains("\"\"\"");
+            continue;
+        }
+        if trimmed.starts_with('"') || trimmed.starts_with('#') || trimmed.is_empty() {
+            continue;
+        }
+
+        condensed.push_str(line);
+        condensed.push('\n');
+        // Keep a blank line between top-level definitions for readability
+        if trimmed == "}" && !line.starts_with(char::is_whitespace) {
+            condensed.push('\n');
+        }
+    }
+
+    condensed
+}
+
+pub fn condense_schema(path: &Path, content: String) -> String {
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    let condensed = match extension.as_str() {
+        "json" => condense_openapi_json(&content),
+        "yaml" | "yml" => condense_openapi_yaml(&content),
+        "graphql" | "gql" => Some(condense_graphql(&content)),
+        _ => None,
+    };
+
+    match condensed {
+        Some(condensed) => {
+            info!(
+                "Condensed schema {} ({} -> {} bytes)",
+                path.display(),
+                content.len(),
+                condensed.len()
+            );
+            condensed
+        }
+        None => content,
+    }
+}
@@ -0,0 +1,145 @@
+use std::io::{self, Read};
+use std::process::{Command, Output, Stdio};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::tuning;
+
+// Set by --timeout; every long-running step checks it and winds down once it has passed
+static DEADLINE: OnceLock<(Instant, Duration)> = OnceLock::new();
+
+// What was cut short, for the partial-result report
+static SKIPPED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+// A blocked syscall (e.g. a hung network mount) can't check the deadline, so after this
+// grace period the watchdog reports and exits on its own
+const WATCHDOG_GRACE: Duration = Duration::from_secs(2);
+
+// Exit status used when the run was cut short, matching timeout(1)
+pub const TIMEOUT_EXIT_CODE: i32 = 124;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+// `30s`, `2m`, `500ms`, or plain seconds
+pub fn parse_duration(text: &str) -> Option<Duration> {
+    let text = text.trim();
+    let (number, unit) = match text.find(|c: char| !c.is_ascii_digit() && c != '.') {
+        Some(index) => text.split_at(index),
+        None => (text, "s"),
+    };
+    let value: f64 = number.parse().ok()?;
+    let seconds = match unit {
+        "ms" => value / 1000.0,
+        "s" => value,
+        "m" => value * 60.0,
+        "h" => value * 3600.0,
+        _ => return None,
+    };
+    if seconds > 0.0 {
+        Some(Duration::from_secs_f64(seconds))
+    } else {
+        None
+    }
+}
+
+pub fn start(limit: Duration) {
+    if DEADLINE.set((Instant::now() + limit, limit)).is_err() {
+        return;
+    }
+    thread::spawn(move || {
+        thread::sleep(limit + WATCHDOG_GRACE);
+        eprintln!(
+            "Timed out after {:.1}s and a step did not respond to cancellation; nothing was delivered{}",
+            limit.as_secs_f64(),
+            skipped_report()
+        );
+        std::process::exit(TIMEOUT_EXIT_CODE);
+    });
+}
+
+pub fn expired() -> bool {
+    DEADLINE.get().is_some_and(|(deadline, _)| Instant::now() >= *deadline)
+}
+
+pub fn limit() -> Option<Duration> {
+    DEADLINE.get().map(|(_, limit)| *limit)
+}
+
+pub fn skip(what: impl Into<String>) {
+    SKIPPED.lock().unwrap_or_else(|err| err.into_inner()).push(what.into());
+}
+
+// True once anything was dropped because of the deadline
+pub fn cut_short() -> bool {
+    !SKIPPED.lock().unwrap_or_else(|err| err.into_inner()).is_empty()
+}
+
+// One `  skipped: …` line for each, each after a newline
+pub fn skipped_report() -> String {
+    let skipped = SKIPPED.lock().unwrap_or_else(|err| err.into_inner());
+    skipped.iter().map(|what| format!("\n  skipped: {}", what)).collect()
+}
+
+fn timed_out(what: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::TimedOut, format!("{} skipped: --timeout reached", what))
+}
+
+// `read_to_string` that refuses to start once the deadline has passed
+pub fn read_to_string(path: &std::path::Path) -> io::Result<String> {
+    if expired() {
+        skip(format!("read {}", path.display()));
+        return Err(timed_out("read"));
+    }
+    tuning::read_to_string(path)
+}
+
+// `Command::output` that kills the child when the deadline passes
+pub fn output(command: &mut Command) -> io::Result<Output> {
+    let deadline = match DEADLINE.get() {
+        Some((deadline, _)) => *deadline,
+        None => return command.output(),
+    };
+    let program = command.get_program().to_string_lossy().to_string();
+    if Instant::now() >= deadline {
+        skip(format!("command {}", program));
+        return Err(timed_out("command"));
+    }
+
+    // stdin is left as the caller configured it (spawn inherits by default)
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // Drain both pipes on their own threads so a chatty child never blocks on a full pipe
+    let mut stdout = child.stdout.take().expect("piped stdout");
+    let mut stderr = child.stderr.take().expect("piped stderr");
+    let stdout_reader = thread::spawn(move || {
+        let mut buffer = Vec::new();
+        let _ = stdout.read_to_end(&mut buffer);
+        buffer
+    });
+    let stderr_reader = thread::spawn(move || {
+        let mut buffer = Vec::new();
+        let _ = stderr.read_to_end(&mut buffer);
+        buffer
+    });
+
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Output {
+                status,
+                stdout: stdout_reader.join().unwrap_or_default(),
+                stderr: stderr_reader.join().unwrap_or_default(),
+            });
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            skip(format!("command {} (killed)", program));
+            return Err(timed_out("command"));
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
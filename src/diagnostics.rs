@@ -0,0 +1,219 @@
+use regex::Regex;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::{deadline, exit_codes, Section};
+
+// A source position referenced by compiler output, a test failure or a stack trace
+#[derive(Clone, PartialEq)]
+pub struct Location {
+    pub path: PathBuf,
+    pub line: usize,
+}
+
+pub struct CommandOutput {
+    pub success: bool,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+pub fn run_command(command_line: &[String]) -> CommandOutput {
+    let (program, args) = match command_line.split_first() {
+        Some(split) => split,
+        None => {
+            eprintln!("No command given");
+            std::process::exit(exit_codes::CONFIG);
+        }
+    };
+
+    info!("Running: {}", command_line.join(" "));
+
+    let output = deadline::output(Command::new(program).args(args).stdin(Stdio::inherit()));
+
+    match output {
+        Ok(output) => CommandOutput {
+            success: output.status.success(),
+            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        },
+        Err(err) => {
+            eprintln!("Failed to run {}: {}", program, err);
+            std::process::exit(1);
+        }
+    }
+}
+
+// Only files inside the current project are worth bundling (not std or registry sources)
+pub fn resolve_project_file(raw_path: &str) -> Option<PathBuf> {
+    let raw_path = raw_path.trim().trim_start_matches("file://");
+    let path = PathBuf::from(raw_path);
+    if !path.is_file() {
+        return None;
+    }
+
+    let cwd = env::current_dir().ok()?.canonicalize().ok()?;
+    let canonical = path.canonicalize().ok()?;
+    let relative = canonical.strip_prefix(&cwd).ok()?;
+    Some(relative.to_path_buf())
+}
+
+pub fn push_location(locations: &mut Vec<Location>, raw_path: &str, line: &str) {
+    let line = match line.parse::<usize>() {
+        Ok(line) if line > 0 => line,
+        _ => return,
+    };
+    if let Some(path) = resolve_project_file(raw_path) {
+        let location = Location { path, line };
+        if !locations.contains(&location) {
+            locations.push(location);
+        }
+    }
+}
+
+// Recognises the location formats of rustc, gcc/clang/go/eslint, tsc and Python tracebacks
+pub fn find_locations(text: &str) -> Vec<Location> {
+    let patterns = [
+        // rustc: ` --> src/main.rs:10:5`
+        Regex::new(r"-->\s+([^\s:]+):(\d+)(?::\d+)?").unwrap(),
+        // Python: `File "app/models.py", line 42`
+        Regex::new(r#"File "([^"]+)", line (\d+)"#).unwrap(),
+        // tsc: `src/app.ts(12,5): error TS2322`
+        Regex::new(r"(?m)^([^\s():]+)\((\d+),\d+\)").unwrap(),
+        // gcc/clang/go/rustc short: `src/main.c:10:5: error: ...`
+        Regex::new(r"(?m)(?:^|\s)([^\s:()]+\.[A-Za-z0-9]+):(\d+)(?::\d+)?").unwrap(),
+    ];
+
+    let mut locations = Vec::new();
+    for pattern in &patterns {
+        for caps in pattern.captures_iter(text) {
+            push_location(&mut locations, &caps[1], &caps[2]);
+        }
+    }
+    locations
+}
+
+// Numbered excerpts around every referenced line, overlapping ranges merged per file
+pub fn excerpt_sections(locations: &[Location], context: usize) -> Vec<Section> {
+    let mut by_file: Vec<(PathBuf, Vec<usize>)> = Vec::new();
+    for location in locations {
+        match by_file.iter_mut().find(|(path, _)| path == &location.path) {
+            Some((_, lines)) => lines.push(location.line),
+            None => by_file.push((location.path.clone(), vec![location.line])),
+        }
+    }
+
+    let mut sections = Vec::new();
+    for (path, mut lines) in by_file {
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) => {
+                eprintln!("Failed to read file {}: {}", path.display(), err);
+                continue;
+            }
+        };
+        let source: Vec<&str> = content.lines().collect();
+        lines.sort_unstable();
+
+        let mut ranges: Vec<(usize, usize)> = Vec::new();
+        for line in lines {
+            let start = line.saturating_sub(context).max(1);
+            let end = (line + context).min(source.len());
+            match ranges.last_mut() {
+                Some(last) if start <= last.1 + 1 => last.1 = last.1.max(end),
+                _ => ranges.push((start, end)),
+            }
+        }
+
+        for (start, end) in ranges {
+            if start > end {
+                continue;
+            }
+            let body: String = (start..=end)
+                .map(|number| format!("{:>5} | {}\n", number, source[number - 1]))
+                .collect();
+            sections.push(Section {
+                title: format!("{} (lines {}-{})", path.display(), start, end),
+                body,
+            });
+        }
+    }
+
+    sections
+}
+
+// Directories never searched when mapping names back to project files
+const SEARCH_EXCLUDED: [&str; 9] = [
+    ".git",
+    "target",
+    "node_modules",
+    "dist",
+    "build",
+    ".venv",
+    "venv",
+    "__pycache__",
+    ".mypy_cache",
+];
+
+fn walk_project(dir: &Path, files: &mut Vec<PathBuf>) {
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            if SEARCH_EXCLUDED.contains(&name.as_str()) {
+                continue;
+            }
+            if path.is_dir() {
+                walk_project(&path, files);
+            } else if path.is_file() {
+                files.push(path);
+            }
+        }
+    }
+}
+
+// All files below the current directory, relative to it and sorted
+pub fn project_files() -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    walk_project(Path::new("."), &mut files);
+    let mut files: Vec<PathBuf> = files
+        .into_iter()
+        .map(|file| file.strip_prefix(".").map(Path::to_path_buf).unwrap_or(file))
+        .collect();
+    files.sort();
+    files
+}
+
+// Frames in production logs carry paths from another machine (/app/src/x.rs, com/foo/Bar.java);
+// map them onto the project file with the longest matching path suffix
+pub fn map_to_project(raw_path: &str, project: &[PathBuf]) -> Option<PathBuf> {
+    if let Some(path) = resolve_project_file(raw_path) {
+        return Some(path);
+    }
+
+    let frame: Vec<&str> = raw_path
+        .split(['/', '\\'])
+        .filter(|part| !part.is_empty() && *part != ".")
+        .collect();
+    let file_name = *frame.last()?;
+
+    project
+        .iter()
+        .filter(|path| path.file_name().map(|name| name == file_name).unwrap_or(false))
+        .map(|path| {
+            let parts: Vec<String> = path
+                .components()
+                .map(|part| part.as_os_str().to_string_lossy().to_string())
+                .collect();
+            let matched = parts
+                .iter()
+                .rev()
+                .zip(frame.iter().rev())
+                .take_while(|(part, frame_part)| part.as_str() == **frame_part)
+                .count();
+            (matched, path)
+        })
+        .max_by_key(|(matched, path)| (*matched, std::cmp::Reverse(path.components().count())))
+        .map(|(_, path)| path.clone())
+}
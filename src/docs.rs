@@ -0,0 +1,368 @@
+use std::path::Path;
+
+use crate::lang::language_for;
+
+// Follows strings and comments across lines, so braces inside them are not counted
+#[derive(Default)]
+struct Scanner {
+    block_comment: usize,
+    // Inside a string literal: `Some(None)` for "...", `Some(Some(hashes))` for a raw string
+    string: Option<Option<usize>>,
+}
+
+fn is_ident_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_'
+}
+
+impl Scanner {
+    fn in_block_comment(&self) -> bool {
+        self.block_comment > 0
+    }
+
+    // Offsets of the braces, parens, brackets and semicolons in `line` that are code
+    fn scan(&mut self, line: &str) -> Vec<(usize, u8)> {
+        let bytes = line.as_bytes();
+        let mut found = Vec::new();
+        let mut index = 0;
+        while index < bytes.len() {
+            if self.block_comment > 0 {
+                if bytes[index..].starts_with(b"*/") {
+                    self.block_comment -= 1;
+                    index += 2;
+                } else if bytes[index..].starts_with(b"/*") {
+                    self.block_comment += 1;
+                    index += 2;
+                } else {
+                    index += 1;
+                }
+                continue;
+            }
+            match self.string {
+                Some(None) => {
+                    match bytes[index] {
+                        b'\\' => index += 1,
+                        b'"' => self.string = None,
+                        _ => {}
+                    }
+                    index += 1;
+                    continue;
+                }
+                Some(Some(hashes)) => {
+                    if bytes[index] == b'"' && bytes[index + 1..].starts_with(&vec![b'#'; hashes]) {
+                        self.string = None;
+                        index += hashes;
+                    }
+                    index += 1;
+                    continue;
+                }
+                None => {}
+            }
+
+            let starts_word = index == 0 || !is_ident_byte(bytes[index - 1]);
+            match bytes[index] {
+                b'/' if bytes.get(index + 1) == Some(&b'/') => break,
+                b'/' if bytes.get(index + 1) == Some(&b'*') => {
+                    self.block_comment = 1;
+                    index += 1;
+                }
+                b'"' => self.string = Some(None),
+                b'r' | b'b' if starts_word => {
+                    // r"...", r#"..."#, br"..."
+                    let mut next = index + 1;
+                    if bytes[index] == b'b' && bytes.get(next) == Some(&b'r') {
+                        next += 1;
+                    } else if bytes[index] == b'b' {
+                        index += 1;
+                        continue;
+                    }
+                    let hashes = bytes[next..].iter().take_while(|byte| **byte == b'#').count();
+                    if bytes.get(next + hashes) == Some(&b'"') {
+                        self.string = Some(Some(hashes));
+                        index = next + hashes;
+                    }
+                }
+                b'\'' => {
+                    // A char literal ('{', '\'', '\u{7d}') rather than a lifetime
+                    let rest = &line[index + 1..];
+                    if let Some(escaped) = rest.strip_prefix('\\') {
+                        if let Some(end) = escaped[1.min(escaped.len())..].find('\'') {
+                            index += end + 3;
+                        }
+                    } else if let Some(c) = rest.chars().next() {
+                        if rest[c.len_utf8()..].starts_with('\'') {
+                            index += c.len_utf8() + 1;
+                        }
+                    }
+                }
+                b'{' | b'}' | b'(' | b')' | b'[' | b']' | b';' => found.push((index, bytes[index])),
+                _ => {}
+            }
+            index += 1;
+        }
+        found
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    // Functions and macro_rules: the body is dropped
+    Body,
+    // mod, impl, trait and extern blocks: each item inside is reduced in turn
+    Items,
+    // struct, enum and union definitions are the API itself and stay whole
+    Definition,
+    // use, const, static, type and anything else: kept as written
+    Other,
+}
+
+fn item_kind(line: &str) -> Kind {
+    let mut external = false;
+    for word in line.split_whitespace() {
+        match word {
+            "pub" | "async" | "unsafe" | "default" | "const" => {}
+            _ if word.starts_with("pub(") => {}
+            "extern" => external = true,
+            _ if external && word.starts_with('"') => {}
+            _ if external && word.starts_with('{') => return Kind::Items,
+            "fn" | "macro_rules!" => return Kind::Body,
+            "mod" | "impl" | "trait" => return Kind::Items,
+            _ if word.starts_with("impl<") || word.starts_with("trait<") => return Kind::Items,
+            "struct" | "enum" | "union" => return Kind::Definition,
+            _ => return Kind::Other,
+        }
+    }
+    Kind::Other
+}
+
+enum Frame {
+    Items,
+    Definition(usize),
+    Skipped(usize),
+}
+
+#[derive(Default)]
+struct Extractor {
+    scanner: Scanner,
+    frames: Vec<Frame>,
+    output: String,
+    // Doc comments and attributes waiting for the item they belong to
+    preamble: Vec<String>,
+    attribute_depth: i32,
+    doc_block: bool,
+    // The item being read, until its body opens or it ends
+    pending: Vec<String>,
+    kind: Option<Kind>,
+    parens: i32,
+    braces: usize,
+}
+
+fn is_plain_comment(trimmed: &str) -> bool {
+    trimmed.starts_with("//") && !trimmed.starts_with("///") && !trimmed.starts_with("//!")
+}
+
+impl Extractor {
+    fn push_line(&mut self, line: &str) {
+        self.output.push_str(line);
+        self.output.push('\n');
+    }
+
+    fn push_blank(&mut self) {
+        if !self.output.is_empty() && !self.output.ends_with("\n\n") {
+            self.output.push('\n');
+        }
+    }
+
+    // `#[cfg(test)]` items are tests, not API
+    fn hidden(&self) -> bool {
+        self.preamble.iter().any(|line| line.trim() == "#[cfg(test)]")
+    }
+
+    fn flush_preamble(&mut self) {
+        for line in std::mem::take(&mut self.preamble) {
+            self.push_line(&line);
+        }
+    }
+
+    fn line(&mut self, line: &str) {
+        match self.frames.last_mut() {
+            Some(Frame::Skipped(depth)) | Some(Frame::Definition(depth)) => {
+                let was_comment = self.scanner.in_block_comment();
+                let mut closed = false;
+                for (_, byte) in self.scanner.scan(line) {
+                    match byte {
+                        b'{' => *depth += 1,
+                        b'}' => {
+                            *depth -= 1;
+                            if *depth == 0 {
+                                closed = true;
+                                break;
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                let kept = matches!(self.frames.last(), Some(Frame::Definition(_)));
+                if kept && !was_comment && !is_plain_comment(line.trim_start()) {
+                    self.push_line(line);
+                }
+                if closed {
+                    self.frames.pop();
+                }
+            }
+            _ if !self.pending.is_empty() => self.item_line(line),
+            _ => self.start_line(line),
+        }
+    }
+
+    // A line at item level, outside any item
+    fn start_line(&mut self, line: &str) {
+        let trimmed = line.trim_start();
+        if self.scanner.in_block_comment() || trimmed.starts_with("/*") {
+            if !self.scanner.in_block_comment() {
+                self.doc_block = trimmed.starts_with("/**") || trimmed.starts_with("/*!");
+            }
+            self.scanner.scan(line);
+            if self.doc_block {
+                self.preamble.push(line.to_string());
+            }
+            return;
+        }
+        if self.attribute_depth > 0 || trimmed.starts_with("#[") || trimmed.starts_with("#![") {
+            for (_, byte) in self.scanner.scan(line) {
+                match byte {
+                    b'[' => self.attribute_depth += 1,
+                    b']' => self.attribute_depth -= 1,
+                    _ => {}
+                }
+            }
+            self.preamble.push(line.to_string());
+            return;
+        }
+        if trimmed.is_empty() {
+            if self.preamble.is_empty() {
+                self.push_blank();
+            }
+            return;
+        }
+        if trimmed.starts_with("//!") {
+            self.push_line(line);
+            return;
+        }
+        if trimmed.starts_with("///") {
+            self.preamble.push(line.to_string());
+            return;
+        }
+        if is_plain_comment(trimmed) {
+            return;
+        }
+        if trimmed.starts_with('}') {
+            self.scanner.scan(line);
+            self.flush_preamble();
+            self.push_line(line);
+            self.frames.pop();
+            return;
+        }
+
+        self.kind = Some(item_kind(trimmed));
+        self.parens = 0;
+        self.braces = 0;
+        self.item_line(line);
+    }
+
+    // A line of the item being read
+    fn item_line(&mut self, line: &str) {
+        let kind = self.kind.unwrap_or(Kind::Other);
+        let mut opened = None;
+        let mut ended = false;
+        for (offset, byte) in self.scanner.scan(line) {
+            match byte {
+                b'(' | b'[' => self.parens += 1,
+                b')' | b']' => self.parens -= 1,
+                b'{' if opened.is_none() && self.braces == 0 && self.parens == 0 && kind != Kind::Other => {
+                    opened = Some(offset);
+                    self.braces = 1;
+                }
+                b'{' => self.braces += 1,
+                b'}' => {
+                    self.braces = self.braces.saturating_sub(1);
+                    if kind == Kind::Other && self.braces == 0 && self.parens == 0 {
+                        ended = true;
+                    }
+                }
+                b';' if opened.is_none() && self.braces == 0 && self.parens == 0 => ended = true,
+                _ => {}
+            }
+        }
+
+        let hidden = self.hidden();
+        if opened.is_none() && !ended {
+            self.pending.push(line.to_string());
+            return;
+        }
+        let mut lines = std::mem::take(&mut self.pending);
+        if hidden {
+            self.preamble.clear();
+            if opened.is_some() && self.braces > 0 {
+                self.frames.push(Frame::Skipped(self.braces));
+            }
+            return;
+        }
+
+        self.flush_preamble();
+        match opened {
+            Some(offset) if kind == Kind::Body => {
+                for line in &lines {
+                    self.push_line(line);
+                }
+                let signature = &line[..offset];
+                if signature.trim().is_empty() {
+                    self.push_line(&format!("{}{{ ... }}", signature));
+                } else {
+                    self.push_line(&format!("{} {{ ... }}", signature.trim_end()));
+                }
+                if self.braces > 0 {
+                    self.frames.push(Frame::Skipped(self.braces));
+                }
+            }
+            Some(_) => {
+                lines.push(line.to_string());
+                for line in &lines {
+                    self.push_line(line);
+                }
+                if self.braces > 0 {
+                    self.frames.push(match kind {
+                        Kind::Items => Frame::Items,
+                        _ => Frame::Definition(self.braces),
+                    });
+                }
+            }
+            None => {
+                lines.push(line.to_string());
+                for line in &lines {
+                    self.push_line(line);
+                }
+            }
+        }
+    }
+
+    fn finish(mut self) -> String {
+        for line in std::mem::take(&mut self.pending) {
+            self.push_line(&line);
+        }
+        self.flush_preamble();
+        self.output
+    }
+}
+
+// `--docs-only`: Rust files keep item signatures, type definitions and their `///` docs, with
+// function bodies reduced to `{ ... }` and tests left out; other files pass through unchanged
+pub fn docs_only(path: &Path, content: String) -> String {
+    if language_for(path) != Some("rust") {
+        return content;
+    }
+    let mut extractor = Extractor::default();
+    for line in content.lines() {
+        extractor.line(line);
+    }
+    extractor.finish()
+}
@@ -0,0 +1,130 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+// Local, deterministic text vectors via feature hashing: no model download and no API, and the
+// same text always maps to the same vector, which keeps the index incremental. The vectors are
+// lexical, not semantic: chunks match on shared words, identifier parts and stems, so
+// `authenticate` finds `auth_token` but never `login`.
+
+// Stored with every index; bump when tokenization or hashing changes so old indexes are rebuilt
+pub const EMBEDDER: &str = "hashing-v2";
+
+// 2^18 buckets keep collisions rare while vectors stay sparse
+const DIMENSIONS: u64 = 1 << 18;
+
+// Sparse, L2-normalized vector as (bucket, weight) pairs sorted by bucket
+pub type Vector = Vec<(u32, f32)>;
+
+const STOPWORDS: [&str; 24] = [
+    "the", "and", "for", "with", "that", "this", "from", "are", "was", "not", "but", "all", "any",
+    "can", "has", "have", "into", "its", "our", "out", "you", "your", "let", "use",
+];
+
+// FNV-1a: stable across runs and platforms, unlike std's RandomState
+fn hash(text: &str) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in text.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+// `parseHttpRequest` and `parse_http_request` both become ["parse", "http", "request"]
+fn split_identifier(word: &str, tokens: &mut Vec<String>) {
+    let mut current = String::new();
+    let mut previous_lower = false;
+    for c in word.chars() {
+        if c == '_' || c == '-' {
+            if !current.is_empty() {
+                tokens.push(std::mem::take(&mut current));
+            }
+            previous_lower = false;
+            continue;
+        }
+        if c.is_uppercase() && previous_lower && !current.is_empty() {
+            tokens.push(std::mem::take(&mut current));
+        }
+        previous_lower = c.is_lowercase() || c.is_ascii_digit();
+        current.extend(c.to_lowercase());
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+}
+
+// Crude suffix stripping so `redact`, `redacted` and `redaction` share a feature
+fn stem(token: &str) -> &str {
+    for suffix in ["ations", "ation", "ions", "ion", "ing", "ed", "es", "s"] {
+        if let Some(stem) = token.strip_suffix(suffix) {
+            if stem.len() >= 4 {
+                return stem;
+            }
+        }
+    }
+    token
+}
+
+pub fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    for word in text.split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-')) {
+        if word.is_empty() {
+            continue;
+        }
+        split_identifier(word, &mut tokens);
+    }
+    tokens.retain(|token| token.chars().count() >= 2 && !STOPWORDS.contains(&token.as_str()));
+    tokens.iter().map(|token| stem(token).to_string()).collect()
+}
+
+pub fn embed(text: &str) -> Vector {
+    let tokens = tokenize(text);
+    let mut counts: HashMap<u32, f32> = HashMap::new();
+
+    let mut add = |feature: &str, weight: f32| {
+        let hashed = hash(feature);
+        let bucket = (hashed % DIMENSIONS) as u32;
+        // The sign bit spreads collisions around zero instead of piling them up
+        let sign = if hashed >> 63 == 0 { 1.0 } else { -1.0 };
+        *counts.entry(bucket).or_insert(0.0) += sign * weight;
+    };
+
+    for (index, token) in tokens.iter().enumerate() {
+        add(token, 1.0);
+        if let Some(next) = tokens.get(index + 1) {
+            add(&format!("{} {}", token, next), 0.5);
+        }
+    }
+
+    // Sublinear term frequency so a repeated identifier doesn't dominate a chunk
+    let mut vector: Vector = counts
+        .into_iter()
+        .filter(|(_, weight)| *weight != 0.0)
+        .map(|(bucket, weight)| (bucket, weight.signum() * (1.0 + weight.abs().ln())))
+        .collect();
+    let norm = vector.iter().map(|(_, weight)| weight * weight).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        for (_, weight) in &mut vector {
+            *weight /= norm;
+        }
+    }
+    vector.sort_by_key(|(bucket, _)| *bucket);
+    vector
+}
+
+// Cosine similarity of two normalized vectors
+pub fn similarity(a: &Vector, b: &Vector) -> f32 {
+    let (mut i, mut j, mut dot) = (0, 0, 0.0);
+    while i < a.len() && j < b.len() {
+        match a[i].0.cmp(&b[j].0) {
+            Ordering::Less => i += 1,
+            Ordering::Greater => j += 1,
+            Ordering::Equal => {
+                dot += a[i].1 * b[j].1;
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    dot
+}
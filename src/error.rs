@@ -0,0 +1,68 @@
+use std::fmt;
+
+use crate::{deadline, exit_codes};
+
+/// Why a bundle couldn't be built or delivered. The library returns it; the command line
+/// prints it and exits with [`Error::exit_code`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// The config file can't be read or parsed, one of its settings is invalid, or the request
+    /// is one a profile refuses
+    Config(String),
+    /// No profile has this name; `suggestions` are the close matches
+    UnknownProfile { name: String, suggestions: Vec<String> },
+    /// Nothing was collected: no files, trees or sections
+    Empty(String),
+    /// The bundle exceeded max_tokens with budget_strategy = "strict"
+    OverBudget(String),
+    /// An output or spill file couldn't be written
+    Output(String),
+    /// The clipboard couldn't be written
+    Clipboard(String),
+    /// `scan = "block"` found likely secrets; nothing was delivered
+    Secrets(String),
+    /// --timeout expired
+    TimedOut(String),
+    /// Anything else
+    Failure(String),
+}
+
+impl Error {
+    /// The status the command line exits with, one of `fdllm --print-exit-codes`
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Config(_) | Error::UnknownProfile { .. } => exit_codes::CONFIG,
+            Error::Empty(_) => exit_codes::EMPTY,
+            Error::OverBudget(_) => exit_codes::OVER_BUDGET,
+            Error::Output(_) => exit_codes::OUTPUT,
+            Error::Clipboard(_) => exit_codes::CLIPBOARD,
+            Error::Secrets(_) => exit_codes::SECRETS,
+            Error::TimedOut(_) => deadline::TIMEOUT_EXIT_CODE,
+            Error::Failure(_) => exit_codes::FAILURE,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Config(message)
+            | Error::Empty(message)
+            | Error::OverBudget(message)
+            | Error::Output(message)
+            | Error::Clipboard(message)
+            | Error::Secrets(message)
+            | Error::TimedOut(message)
+            | Error::Failure(message) => f.write_str(message),
+            Error::UnknownProfile { name, suggestions } => {
+                write!(f, "Profile '{}' not found in config", name)?;
+                if !suggestions.is_empty() {
+                    write!(f, "\nDid you mean: {}?", suggestions.join(", "))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
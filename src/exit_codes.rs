@@ -0,0 +1,37 @@
+use crate::deadline;
+
+// Stable exit statuses for scripts and editor plugins; new outcomes get new numbers, existing
+// ones never change meaning
+pub const SUCCESS: i32 = 0;
+pub const FAILURE: i32 = 1;
+pub const CONFIG: i32 = 2;
+pub const EMPTY: i32 = 3;
+pub const CLIPBOARD: i32 = 4;
+pub const OVER_BUDGET: i32 = 5;
+pub const OUTPUT: i32 = 6;
+pub const FINDINGS: i32 = 7;
+pub const SECRETS: i32 = 8;
+
+const TABLE: [(i32, &str, &str); 10] = [
+    (SUCCESS, "success", "the bundle was delivered to every output"),
+    (FAILURE, "failure", "any other error"),
+    (CONFIG, "config", "bad usage, or the config is missing, unreadable or names no such profile"),
+    (EMPTY, "empty", "nothing to copy or pack: no files, trees, sections or changes were collected"),
+    (CLIPBOARD, "clipboard", "the clipboard could not be written (with clipboard_fallback = \"error\")"),
+    (OVER_BUDGET, "over-budget", "the bundle exceeded max_tokens with budget_strategy = \"strict\""),
+    (OUTPUT, "output", "an output file could not be written"),
+    (FINDINGS, "findings", "`fdllm audit` flagged at least one profile"),
+    (SECRETS, "secrets", "`scan = \"block\"` found likely secrets and nothing was delivered (see --allow-secrets)"),
+    (deadline::TIMEOUT_EXIT_CODE, "timeout", "--timeout expired; whatever was collected was still delivered"),
+];
+
+// `fdllm --print-exit-codes`; with --porcelain, one `code N name` record per status
+pub fn print() {
+    for (code, name, description) in TABLE {
+        if crate::porcelain() {
+            println!("code {} {}", code, name);
+        } else {
+            println!("{:>3}  {:<12} {}", code, name, description);
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::{lang, tokens};
+
+// Data files rarely need more than a sample; prose sits between data and source
+const DATA_LIMIT: usize = 2_000;
+const PROSE_LIMIT: usize = 4_000;
+const SOURCE_LIMIT: usize = 8_000;
+
+// `true` for the built-in per-language limits, a number for every file, or a table such as
+// { default = 6000, json = 1500 } layered over the built-in limits
+#[derive(Deserialize, JsonSchema, Clone)]
+#[serde(untagged)]
+pub enum MaxTokensPerFile {
+    Defaults(bool),
+    All(usize),
+    Languages(BTreeMap<String, usize>),
+}
+
+fn default_limit(language: Option<&str>) -> usize {
+    match language {
+        Some("json" | "yaml" | "csv" | "text") => DATA_LIMIT,
+        Some("markdown") => PROSE_LIMIT,
+        _ => SOURCE_LIMIT,
+    }
+}
+
+impl MaxTokensPerFile {
+    pub fn limit_for(&self, path: &Path) -> Option<usize> {
+        let language = lang::language_for(path);
+        match self {
+            MaxTokensPerFile::Defaults(false) => None,
+            MaxTokensPerFile::Defaults(true) => Some(default_limit(language)),
+            MaxTokensPerFile::All(limit) => Some(*limit),
+            MaxTokensPerFile::Languages(limits) => language
+                .and_then(|language| limits.get(language))
+                .or_else(|| limits.get("default"))
+                .copied()
+                .or_else(|| Some(default_limit(language))),
+        }
+    }
+}
+
+// Like `limit_file_tokens`, counted with the real tokenizer: `truncate_paths` limits come from
+// `fdllm trim`, which measures files that way
+pub fn truncate_to_tokens(path: &Path, content: String, limit: usize) -> String {
+    if tokens::count(&content) <= limit {
+        return content;
+    }
+    let mut kept = String::new();
+    let mut used = 0;
+    let mut dropped = 0;
+    for line in content.lines() {
+        let length = tokens::count(line) + 1;
+        if dropped > 0 || used + length > limit {
+            dropped += 1;
+            continue;
+        }
+        used += length;
+        kept.push_str(line);
+        kept.push('\n');
+    }
+    let comment = lang::language_for(path).map(lang::line_comment).unwrap_or("//");
+    kept.push_str(&format!(
+        "{} [fdllm: truncated to ~{} tokens; {} more line(s)]\n",
+        comment, limit, dropped
+    ));
+    kept
+}
+
+// Keeps whole lines up to the file's token limit and notes how much was cut
+pub fn limit_file_tokens(path: &Path, content: String, setting: &MaxTokensPerFile) -> String {
+    let limit = match setting.limit_for(path) {
+        Some(limit) => limit,
+        None => return content,
+    };
+    if tokens::estimate(&content) <= limit {
+        return content;
+    }
+
+    let budget = limit * 4;
+    let mut kept = String::new();
+    let mut used = 0;
+    let mut dropped = 0;
+    for line in content.lines() {
+        let length = line.chars().count() + 1;
+        if dropped > 0 || used + length > budget {
+            dropped += 1;
+            continue;
+        }
+        used += length;
+        kept.push_str(line);
+        kept.push('\n');
+    }
+    if dropped == 0 {
+        return content;
+    }
+
+    let comment = lang::language_for(path).map(lang::line_comment).unwrap_or("//");
+    kept.push_str(&format!(
+        "{} [fdllm: truncated to ~{} tokens; {} more line(s)]\n",
+        comment, limit, dropped
+    ));
+    kept
+}
@@ -0,0 +1,185 @@
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::lang;
+
+// What to do with files larger than `max_file_size`
+#[derive(Deserialize, JsonSchema, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum LargeFiles {
+    // Leave the file out, unread, and list it in the summary
+    #[default]
+    Skip,
+    // Keep the first `max_file_size` bytes
+    Head,
+    // Keep the last `max_file_size` bytes, where logs have the recent entries
+    Tail,
+    // Keep half of it from each end
+    HeadTail,
+}
+
+impl LargeFiles {
+    pub fn name(self) -> &'static str {
+        match self {
+            LargeFiles::Skip => "skip",
+            LargeFiles::Head => "head",
+            LargeFiles::Tail => "tail",
+            LargeFiles::HeadTail => "head-tail",
+        }
+    }
+}
+
+// `max_file_size`: a number of bytes, or a size such as "512KB" or "2MB"
+#[derive(Deserialize, JsonSchema, Clone)]
+#[serde(untagged)]
+pub enum FileSize {
+    Bytes(u64),
+    Text(String),
+}
+
+// "2MB", "512 KB", "1.5mb" or a plain number of bytes; K/M/G are powers of 1024
+pub fn parse_size(value: &str) -> Option<u64> {
+    let value = value.trim().to_lowercase();
+    let split = value.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let multiplier: u64 = match unit.trim().trim_end_matches("ib").trim_end_matches('b') {
+        "" => 1,
+        "k" => 1 << 10,
+        "m" => 1 << 20,
+        "g" => 1 << 30,
+        _ => return None,
+    };
+    let number: f64 = number.parse().ok()?;
+    Some((number * multiplier as f64) as u64).filter(|bytes| *bytes > 0)
+}
+
+impl FileSize {
+    pub fn bytes(&self) -> Option<u64> {
+        match self {
+            FileSize::Bytes(bytes) => Some(*bytes).filter(|bytes| *bytes > 0),
+            FileSize::Text(text) => parse_size(text),
+        }
+    }
+}
+
+pub struct Limit {
+    pub bytes: u64,
+    pub strategy: LargeFiles,
+}
+
+pub fn describe(bytes: u64) -> String {
+    if bytes < 1 << 10 {
+        format!("{} bytes", bytes)
+    } else if bytes < 1 << 20 {
+        format!("{} KB", bytes.div_ceil(1024))
+    } else {
+        format!("{:.1} MB", bytes as f64 / (1u64 << 20) as f64)
+    }
+}
+
+// A cut can land inside a character: an incomplete one at the end is dropped, anything else
+// that isn't UTF-8 is an error like a full read's, so binary detection still applies
+fn utf8(mut bytes: Vec<u8>) -> io::Result<String> {
+    match std::str::from_utf8(&bytes) {
+        Ok(_) => {}
+        Err(err) if err.error_len().is_none() => bytes.truncate(err.valid_up_to()),
+        Err(err) => return Err(io::Error::new(io::ErrorKind::InvalidData, err)),
+    }
+    Ok(String::from_utf8(bytes).expect("validated above"))
+}
+
+// Only the head and/or tail of a file over the limit is read, cut to whole lines, with a marker
+// where the rest was
+pub fn read_truncated(path: &Path, size: u64, limit: &Limit) -> io::Result<String> {
+    let (head_bytes, tail_bytes) = match limit.strategy {
+        LargeFiles::Skip | LargeFiles::Head => (limit.bytes, 0),
+        LargeFiles::Tail => (0, limit.bytes),
+        LargeFiles::HeadTail => (limit.bytes / 2, limit.bytes - limit.bytes / 2),
+    };
+    let mut file = File::open(path)?;
+    let mut head = vec![0; head_bytes as usize];
+    file.read_exact(&mut head)?;
+    let mut tail = vec![0; tail_bytes as usize];
+    file.seek(SeekFrom::End(-(tail_bytes as i64)))?;
+    file.read_exact(&mut tail)?;
+
+    if let Some(end) = head.iter().rposition(|byte| *byte == b'\n') {
+        head.truncate(end + 1);
+    }
+    match tail.iter().position(|byte| *byte == b'\n') {
+        Some(start) if start + 1 < tail.len() => {
+            tail.drain(..=start);
+        }
+        // One long line: start at a character boundary instead
+        _ => {
+            let continuation = tail.iter().take_while(|byte| **byte & 0xC0 == 0x80).count();
+            tail.drain(..continuation);
+        }
+    }
+    let (head, tail) = (utf8(head)?, utf8(tail)?);
+
+    let comment = lang::language_for(path).map(lang::line_comment).unwrap_or("//");
+    let shown = (head.len() + tail.len()) as u64;
+    let marker = match limit.strategy {
+        LargeFiles::Skip | LargeFiles::Head => format!("showing the first {} of {}", describe(shown), describe(size)),
+        LargeFiles::Tail => format!("showing the last {} of {}", describe(shown), describe(size)),
+        LargeFiles::HeadTail => format!("{} of {} omitted here", describe(size - shown), describe(size)),
+    };
+    let mut content = head;
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(&format!("{} [fdllm: truncated: {}]\n", comment, marker));
+    content.push_str(&tail);
+    Ok(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sizes() {
+        assert_eq!(parse_size("512"), Some(512));
+        assert_eq!(parse_size("2MB"), Some(2 << 20));
+        assert_eq!(parse_size("512 KB"), Some(512 << 10));
+        assert_eq!(parse_size("1.5mb"), Some(3 << 19));
+        assert_eq!(parse_size("1GiB"), Some(1 << 30));
+        assert_eq!(parse_size("0"), None);
+        assert_eq!(parse_size("2 parsecs"), None);
+        assert_eq!(parse_size(""), None);
+    }
+
+    #[test]
+    fn describes_sizes() {
+        assert_eq!(describe(100), "100 bytes");
+        assert_eq!(describe(1024), "1 KB");
+        assert_eq!(describe(1500), "2 KB");
+        assert_eq!(describe(3 << 19), "1.5 MB");
+    }
+
+    #[test]
+    fn truncates_to_whole_lines_with_a_marker() {
+        let path = std::env::temp_dir().join(format!("fdllm-file-size-{}.py", std::process::id()));
+        let content: String = (0..100).map(|line| format!("line {}\n", line)).collect();
+        std::fs::write(&path, &content).unwrap();
+        let size = content.len() as u64;
+
+        let head = read_truncated(&path, size, &Limit { bytes: 20, strategy: LargeFiles::Head }).unwrap();
+        assert!(head.starts_with("line 0\nline 1\n"));
+        assert!(head.ends_with("# [fdllm: truncated: showing the first 14 bytes of 790 bytes]\n"));
+
+        let tail = read_truncated(&path, size, &Limit { bytes: 20, strategy: LargeFiles::Tail }).unwrap();
+        assert!(tail.ends_with("line 98\nline 99\n"));
+        assert!(!tail.contains("line 0\n"));
+
+        let both = read_truncated(&path, size, &Limit { bytes: 40, strategy: LargeFiles::HeadTail }).unwrap();
+        assert!(both.starts_with("line 0\n"));
+        assert!(both.contains("omitted here]\n"));
+        assert!(both.ends_with("line 99\n"));
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,168 @@
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::path::Path;
+
+use crate::lang;
+
+// How the bundle is laid out: files, and for markdown the tree and sections too
+#[derive(Deserialize, JsonSchema, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    // `# NOTE: path:` followed by the raw content
+    #[default]
+    Plain,
+    // A `## path` heading over a fenced block tagged with the language
+    Markdown,
+    // The path on its own line, then a fenced block: what aider reads and writes for whole files
+    Aider,
+    // ```lang:path fences, which Cursor's chat treats as code it can apply to that file
+    Cursor,
+    // `<document path="...">` blocks and a `<tree>` element, as Claude prompts recommend
+    Xml,
+}
+
+impl Format {
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Plain => "plain",
+            Format::Markdown => "markdown",
+            Format::Aider => "aider",
+            Format::Cursor => "cursor",
+            Format::Xml => "xml",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Format> {
+        match name {
+            "plain" => Some(Format::Plain),
+            "markdown" => Some(Format::Markdown),
+            "aider" => Some(Format::Aider),
+            "cursor" => Some(Format::Cursor),
+            "xml" => Some(Format::Xml),
+            _ => None,
+        }
+    }
+}
+
+// A fence longer than any run of backticks inside the content
+fn fence(content: &str) -> String {
+    let longest = content.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+// Editors apply changes to paths relative to where they run, not to absolute ones
+fn relative_path(header: &str) -> String {
+    let path = Path::new(header);
+    std::env::current_dir()
+        .ok()
+        .and_then(|cwd| path.strip_prefix(cwd).ok().map(|relative| relative.to_string_lossy().to_string()))
+        .unwrap_or_else(|| header.to_string())
+}
+
+fn escape_attribute(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+// Content stays as written (escaping every `<` in code costs tokens and readability); only a
+// closing tag that would end the element early is neutralised
+fn xml_element(tag: &str, attributes: &str, body: &str) -> String {
+    let closing = format!("</{}", tag);
+    let body = body.replace(&closing, &format!("&lt;/{}", tag));
+    format!("<{}{}>\n{}{}</{}>\n\n", tag, attributes, body, line_end(&body), tag)
+}
+
+fn line_end(content: &str) -> &'static str {
+    if content.is_empty() || content.ends_with('\n') {
+        ""
+    } else {
+        "\n"
+    }
+}
+
+// Sections, snippets and instructions; editor formats only change how files look
+pub fn section_block(format: Format, title: &str, body: &str) -> String {
+    match format {
+        Format::Markdown => {
+            let fence = fence(body);
+            format!("## {}\n\n{}\n{}{}{}\n\n", title, fence, body, line_end(body), fence)
+        }
+        Format::Xml => xml_element("section", &format!(" title=\"{}\"", escape_attribute(title)), body),
+        _ => format!("# NOTE: {}:\n{}\n", title, body),
+    }
+}
+
+// A `header` or --prompt: text written to the model, so it goes in as it is
+pub fn prompt_block(format: Format, body: &str) -> String {
+    match format {
+        Format::Xml => xml_element("prompt", "", body),
+        _ => format!("{}{}\n", body, line_end(body)),
+    }
+}
+
+// Closing guidance from the `footer` template
+pub fn instructions_block(format: Format, body: &str) -> String {
+    match format {
+        Format::Xml => xml_element("instructions", "", body),
+        _ => section_block(format, "Instructions", body),
+    }
+}
+
+pub fn tree_block(format: Format, title: &str, tree: &str) -> String {
+    match format {
+        Format::Markdown => {
+            let fence = fence(tree);
+            format!("## Project Tree: {}\n\n{}\n{}{}{}\n\n", title, fence, tree, line_end(tree), fence)
+        }
+        Format::Xml => xml_element("tree", &format!(" root=\"{}\"", escape_attribute(title)), tree),
+        _ => format!("# NOTE: Project Tree: {}\n{}\n", title, tree),
+    }
+}
+
+pub fn file_block(format: Format, header: &str, path: &Path, content: &str) -> String {
+    match format {
+        Format::Plain => return format!("# NOTE: {}:\n{}\n", header, content),
+        Format::Xml => return xml_element("document", &format!(" path=\"{}\"", escape_attribute(header)), content),
+        _ => {}
+    }
+
+    let fence = fence(content);
+    let language = lang::language_for(path);
+    let newline = line_end(content);
+    if format == Format::Markdown {
+        return format!(
+            "## {}\n\n{}{}\n{}{}{}\n\n",
+            header,
+            fence,
+            language.unwrap_or(""),
+            content,
+            newline,
+            fence
+        );
+    }
+
+    let header = relative_path(header);
+    match format {
+        Format::Aider => format!(
+            "{}\n{}{}\n{}{}{}\n\n",
+            header,
+            fence,
+            language.unwrap_or(""),
+            content,
+            newline,
+            fence
+        ),
+        _ => format!(
+            "{}{}:{}\n{}{}{}\n\n",
+            fence,
+            language.unwrap_or("text"),
+            header,
+            content,
+            newline,
+            fence
+        ),
+    }
+}
@@ -0,0 +1,268 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::deadline;
+use crate::roots::{self, Root};
+use crate::Section;
+
+// `git:HEAD~3:src/main.rs` -> ("HEAD~3", "src/main.rs"); an index stage of a conflicted file,
+// `git::2:src/main.rs`, -> (":2", "src/main.rs")
+pub fn parse_revision_entry(entry: &str) -> Option<(&str, &str)> {
+    let rest = entry.strip_prefix("git:")?;
+    // A stage keeps its leading `:`, which is how git spells it
+    let split = match rest.strip_prefix(':') {
+        Some(staged) => matches!(staged.get(..2), Some("1:" | "2:" | "3:")).then_some(2)?,
+        None => rest.find(':')?,
+    };
+    let (rev, path) = (&rest[..split], &rest[split + 1..]);
+    if rev.is_empty() || path.is_empty() {
+        return None;
+    }
+    Some((rev, path))
+}
+
+pub fn run_git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = deadline::output(Command::new("git").arg("-C").arg(dir).args(args))
+        .map_err(|err| format!("failed to run git: {}", err))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+// The file may not exist in the working tree any more, so look for the closest existing parent
+fn existing_ancestor(path: &Path) -> PathBuf {
+    let mut dir = path.parent();
+    while let Some(candidate) = dir {
+        if candidate.is_dir() {
+            return candidate.to_path_buf();
+        }
+        dir = candidate.parent();
+    }
+    PathBuf::from(".")
+}
+
+// Content of `path` at `rev`. Relative paths are taken from `base_dir` (the project, or the
+// working directory); absolute and root-labeled paths find their own repository.
+pub fn show_file(rev: &str, path: &str, roots_list: &[Root], base_dir: &Path) -> Result<String, String> {
+    let resolved = roots::resolve(path, roots_list);
+    let (repo_dir, spec) = if resolved.is_absolute() {
+        let search_dir = existing_ancestor(&resolved);
+        let top_level = run_git(&search_dir, &["rev-parse", "--show-toplevel"])?;
+        let top_level = PathBuf::from(top_level.trim());
+        // Compare canonical forms so symlinked checkouts still strip cleanly
+        let canonical_top = top_level.canonicalize().unwrap_or(top_level.clone());
+        let canonical_dir = search_dir.canonicalize().unwrap_or(search_dir.clone());
+        let below = canonical_dir.strip_prefix(&canonical_top).unwrap_or(Path::new(""));
+        let relative = below.join(resolved.strip_prefix(&search_dir).unwrap_or(&resolved));
+        (top_level, format!("{}:{}", rev, relative.to_string_lossy()))
+    } else {
+        // `./` makes git resolve the path relative to -C instead of the repository root
+        (base_dir.to_path_buf(), format!("{}:./{}", rev, path.trim_start_matches("./")))
+    };
+    run_git(&repo_dir, &["show", &spec])
+}
+
+// `--git changed|staged|branch <base>`: which files of the work in progress a run keeps
+#[derive(Clone)]
+pub enum Selection {
+    // Modified, staged or untracked in the working tree
+    Changed,
+    // In the index
+    Staged,
+    // Different from where the branch left `base`, uncommitted work included
+    Branch(String),
+}
+
+impl Selection {
+    pub fn parse(value: &str) -> Option<Selection> {
+        match value.split_once('=') {
+            Some(("branch", base)) if !base.is_empty() => Some(Selection::Branch(base.to_string())),
+            None if value == "changed" => Some(Selection::Changed),
+            None if value == "staged" => Some(Selection::Staged),
+            _ => None,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Selection::Changed => "files changed in the working tree".to_string(),
+            Selection::Staged => "staged files".to_string(),
+            Selection::Branch(base) => format!("files changed since the branch left {}", base),
+        }
+    }
+}
+
+fn null_separated(output: &str) -> impl Iterator<Item = &str> {
+    output.split('\0').filter(|path| !path.is_empty())
+}
+
+// Existing files the selection covers in the repository containing `dir`, as absolute paths
+pub fn selected_files(dir: &Path, selection: &Selection) -> Result<Vec<PathBuf>, String> {
+    let top_level = PathBuf::from(run_git(dir, &["rev-parse", "--show-toplevel"])?.trim());
+    // Deleted files have nothing to bundle
+    let mut listed = match selection {
+        Selection::Changed => run_git(&top_level, &["diff", "--name-only", "-z", "--diff-filter=d", "HEAD"])?,
+        Selection::Staged => run_git(&top_level, &["diff", "--name-only", "-z", "--diff-filter=d", "--cached"])?,
+        Selection::Branch(base) => {
+            let merge_base = run_git(&top_level, &["merge-base", base, "HEAD"])?;
+            run_git(&top_level, &["diff", "--name-only", "-z", "--diff-filter=d", merge_base.trim()])?
+        }
+    };
+    if !matches!(selection, Selection::Staged) {
+        listed.push('\0');
+        listed.push_str(&run_git(&top_level, &["ls-files", "-z", "--others", "--exclude-standard"])?);
+    }
+
+    let mut files: Vec<PathBuf> = null_separated(&listed)
+        .map(|path| top_level.join(path))
+        .filter(|path| path.is_file())
+        .collect();
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+// `git_diff = "unstaged"`, "staged", or a ref the working tree is compared against
+pub fn diff_section(dir: &Path, against: &str, context: Option<u32>) -> Option<Section> {
+    let context = context.map(|lines| format!("-U{}", lines));
+    let mut args = vec!["diff", "--no-color", "--no-ext-diff"];
+    args.extend(context.as_deref());
+    let title = match against {
+        "unstaged" => "Git Diff (unstaged)".to_string(),
+        "staged" => {
+            args.push("--cached");
+            "Git Diff (staged)".to_string()
+        }
+        rev => {
+            args.push(rev);
+            format!("Git Diff (working tree against {})", rev)
+        }
+    };
+
+    match run_git(dir, &args) {
+        Ok(diff) if diff.trim().is_empty() => {
+            info!("Git diff: no {} changes", against);
+            None
+        }
+        Ok(diff) => Some(Section { title, body: diff }),
+        Err(err) => {
+            eprintln!("Failed to run git diff {}: {}", against, err);
+            None
+        }
+    }
+}
+
+// A file git couldn't merge, and the index stages it has: 1 the common base, 2 ours, 3 theirs
+// (delete/modify and add/add conflicts lack one or the other)
+pub struct Conflict {
+    pub path: PathBuf,
+    pub stages: Vec<u8>,
+}
+
+// Conflicted files in the repository containing `dir`, as absolute paths
+pub fn conflicts(dir: &Path) -> Result<Vec<Conflict>, String> {
+    let top_level = PathBuf::from(run_git(dir, &["rev-parse", "--show-toplevel"])?.trim());
+    // `MODE SHA STAGE\tPATH` for each stage
+    let listed = run_git(&top_level, &["ls-files", "--unmerged", "-z"])?;
+    let mut conflicts: Vec<Conflict> = Vec::new();
+    for record in null_separated(&listed) {
+        let Some((info, path)) = record.split_once('\t') else {
+            continue;
+        };
+        let Some(stage) = info.rsplit(' ').next().and_then(|stage| stage.parse().ok()) else {
+            continue;
+        };
+        let path = top_level.join(path);
+        match conflicts.last_mut() {
+            Some(last) if last.path == path => last.stages.push(stage),
+            _ => conflicts.push(Conflict { path, stages: vec![stage] }),
+        }
+    }
+    Ok(conflicts)
+}
+
+impl Conflict {
+    // Relative to `base_dir` when below it, as `git::STAGE:path` entries are read from there
+    fn display(&self, base_dir: &Path) -> String {
+        let canonical_base = base_dir.canonicalize().unwrap_or(base_dir.to_path_buf());
+        // The file itself may be gone (deleted on one side), its directory rarely is
+        let path = match self.path.parent().and_then(|parent| parent.canonicalize().ok()) {
+            Some(parent) => parent.join(self.path.file_name().unwrap_or_default()),
+            None => self.path.clone(),
+        };
+        path.strip_prefix(&canonical_base).unwrap_or(&path).to_string_lossy().to_string()
+    }
+
+    // The versions git has, then the working copy with its conflict markers
+    pub fn entries(&self, base_dir: &Path) -> Vec<String> {
+        let path = self.display(base_dir);
+        let mut entries: Vec<String> = self.stages.iter().map(|stage| format!("git::{}:{}", stage, path)).collect();
+        if self.path.is_file() {
+            entries.push(path);
+        }
+        entries
+    }
+}
+
+// What the conflicts came from, and what "ours" and "theirs" are in it; in a rebase they are
+// the other way round from a merge
+fn operation(dir: &Path) -> (&'static str, &'static str, &'static str) {
+    let exists = |name: &str| {
+        run_git(dir, &["rev-parse", "--git-path", name]).is_ok_and(|path| dir.join(path.trim()).exists())
+    };
+    if exists("rebase-merge") || exists("rebase-apply") {
+        ("a rebase", "the branch being rebased onto", "the commit being replayed")
+    } else if exists("MERGE_HEAD") {
+        ("a merge", "the current branch", "the branch being merged in")
+    } else if exists("CHERRY_PICK_HEAD") {
+        ("a cherry-pick", "the current branch", "the commit being picked")
+    } else if exists("REVERT_HEAD") {
+        ("a revert", "the current branch", "the commit being reverted, undone")
+    } else {
+        ("an operation git keeps no record of, such as `git stash pop`", "the current branch", "the stash or patch being applied")
+    }
+}
+
+// Tells the reader which block is which, ahead of the files
+pub fn conflicts_section(dir: &Path, conflicts: &[Conflict]) -> Section {
+    let (operation, ours, theirs) = operation(dir);
+    let mut body = format!(
+        "{} file(s) conflict in {}. Each is given as up to three versions from the index, then the working copy with conflict markers:\n",
+        conflicts.len(),
+        operation
+    );
+    body.push_str("  git::1:PATH  the common ancestor (base)\n");
+    body.push_str(&format!("  git::2:PATH  ours: {}\n", ours));
+    body.push_str(&format!("  git::3:PATH  theirs: {}\n", theirs));
+    for conflict in conflicts {
+        let missing = match (conflict.stages.contains(&2), conflict.stages.contains(&3)) {
+            (true, false) => " (deleted by theirs)",
+            (false, true) => " (deleted by ours)",
+            _ if !conflict.stages.contains(&1) => " (added on both sides)",
+            _ => "",
+        };
+        body.push_str(&format!("- {}{}\n", conflict.display(dir), missing));
+    }
+    Section {
+        title: "Merge Conflicts".to_string(),
+        body,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_revision_and_stage_entries() {
+        assert_eq!(parse_revision_entry("git:HEAD~3:src/main.rs"), Some(("HEAD~3", "src/main.rs")));
+        assert_eq!(parse_revision_entry("git::2:src/main.rs"), Some((":2", "src/main.rs")));
+        assert_eq!(parse_revision_entry("git::3:/abs/a:b.rs"), Some((":3", "/abs/a:b.rs")));
+        assert_eq!(parse_revision_entry("git::4:src/main.rs"), None);
+        assert_eq!(parse_revision_entry("git::src/main.rs"), None);
+        assert_eq!(parse_revision_entry("git:HEAD:"), None);
+        assert_eq!(parse_revision_entry("src/main.rs"), None);
+    }
+}
@@ -0,0 +1,190 @@
+use serde::Deserialize;
+use std::process::Command;
+
+use crate::deadline;
+use crate::Section;
+
+// `gh:owner/repo#123` in `files`, or on the command line: an issue or pull request, fetched
+// with its comments into a section of its own
+#[derive(Debug, PartialEq)]
+pub struct Reference<'a> {
+    pub owner: &'a str,
+    pub repo: &'a str,
+    pub number: u64,
+}
+
+pub fn parse_reference(entry: &str) -> Option<Reference<'_>> {
+    let rest = entry.strip_prefix("gh:")?;
+    let (repository, number) = rest.split_once('#')?;
+    let (owner, repo) = repository.split_once('/')?;
+    if owner.is_empty() || repo.is_empty() || repo.contains('/') {
+        return None;
+    }
+    Some(Reference {
+        owner,
+        repo,
+        number: number.parse().ok()?,
+    })
+}
+
+pub fn is_reference(entry: &str) -> bool {
+    entry.starts_with("gh:")
+}
+
+// Files to read, and the issues to fetch instead; a `gh:` entry that doesn't parse stays with
+// the references so it is reported rather than looked for on disk
+pub fn split_entries(entries: Option<&Vec<String>>) -> (Vec<String>, Vec<String>) {
+    entries.into_iter().flatten().cloned().partition(|entry| !is_reference(entry))
+}
+
+#[derive(Deserialize)]
+struct User {
+    login: String,
+}
+
+#[derive(Deserialize)]
+struct Issue {
+    title: String,
+    body: Option<String>,
+    state: String,
+    user: User,
+    html_url: String,
+    created_at: String,
+    // Only present on pull requests, which the issues API serves too
+    pull_request: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct Comment {
+    body: Option<String>,
+    user: User,
+    created_at: String,
+}
+
+// Through the GitHub CLI, which holds the token (in the system keyring after `gh auth login`,
+// or from GH_TOKEN) and knows Enterprise hosts
+fn api(path: &str) -> Result<String, String> {
+    let output = deadline::output(Command::new("gh").args(["api", "--paginate", path])).map_err(|err| match err.kind() {
+        std::io::ErrorKind::NotFound => "gh not found; install the GitHub CLI and run `gh auth login`".to_string(),
+        _ => format!("failed to run gh: {}", err),
+    })?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+// `--paginate` prints each page's array after the last, with nothing between them
+fn parse_comments(json: &str) -> Result<Vec<Comment>, serde_json::Error> {
+    let mut comments = Vec::new();
+    for page in serde_json::Deserializer::from_str(json).into_iter::<Vec<Comment>>() {
+        comments.extend(page?);
+    }
+    Ok(comments)
+}
+
+fn date(timestamp: &str) -> &str {
+    timestamp.get(..10).unwrap_or(timestamp)
+}
+
+fn text<'a>(body: Option<&'a str>, missing: &'a str) -> &'a str {
+    body.map(str::trim).filter(|body| !body.is_empty()).unwrap_or(missing)
+}
+
+fn render(reference: &Reference, issue: &Issue, comments: &[Comment]) -> Section {
+    let kind = if issue.pull_request.is_some() { "pull request" } else { "issue" };
+    let mut body = format!(
+        "{}\n{}, opened by @{} on {}\n\n{}\n",
+        issue.html_url,
+        issue.state,
+        issue.user.login,
+        date(&issue.created_at),
+        text(issue.body.as_deref(), "(no description)")
+    );
+    for comment in comments {
+        body.push_str(&format!(
+            "\nComment by @{} on {}:\n{}\n",
+            comment.user.login,
+            date(&comment.created_at),
+            text(comment.body.as_deref(), "(empty)")
+        ));
+    }
+    Section {
+        title: format!(
+            "GitHub {} {}/{}#{}: {}",
+            kind, reference.owner, reference.repo, reference.number, issue.title
+        ),
+        body,
+    }
+}
+
+// Like the database schema, a reference that can't be fetched is reported and left out
+pub fn issue_section(entry: &str) -> Option<Section> {
+    let Some(reference) = parse_reference(entry) else {
+        eprintln!("Invalid GitHub reference '{}': expected gh:owner/repo#123", entry);
+        return None;
+    };
+    let path = format!("repos/{}/{}/issues/{}", reference.owner, reference.repo, reference.number);
+    let fetched = api(&path).and_then(|json| {
+        let issue: Issue = serde_json::from_str(&json).map_err(|err| format!("unexpected response: {}", err))?;
+        let comments = api(&format!("{}/comments", path))?;
+        let comments = parse_comments(&comments).map_err(|err| format!("unexpected response: {}", err))?;
+        Ok((issue, comments))
+    });
+    match fetched {
+        Ok((issue, comments)) => {
+            info!("Included {} with {} comment(s)", entry, comments.len());
+            Some(render(&reference, &issue, &comments))
+        }
+        Err(err) => {
+            eprintln!("Failed to fetch {}: {}", entry, err);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_references() {
+        assert_eq!(
+            parse_reference("gh:rust-lang/cargo#123"),
+            Some(Reference {
+                owner: "rust-lang",
+                repo: "cargo",
+                number: 123
+            })
+        );
+        assert_eq!(parse_reference("gh:rust-lang/cargo"), None);
+        assert_eq!(parse_reference("gh:cargo#123"), None);
+        assert_eq!(parse_reference("gh:a/b/c#1"), None);
+        assert_eq!(parse_reference("gh:a/b#x"), None);
+        assert_eq!(parse_reference("src/main.rs"), None);
+    }
+
+    #[test]
+    fn renders_issues_with_comments() {
+        let issue: Issue = serde_json::from_str(
+            r#"{"title": "Crash on empty input", "body": "It panics.\r\n", "state": "open",
+                "user": {"login": "alice"}, "html_url": "https://github.com/acme/api/pull/7",
+                "created_at": "2024-05-01T10:00:00Z", "pull_request": {"url": "..."}}"#,
+        )
+        .unwrap();
+        let comments = parse_comments(
+            r#"[{"body": "Same here", "user": {"login": "bob"}, "created_at": "2024-05-02T09:00:00Z"}][{"body": null, "user": {"login": "carol"}, "created_at": "2024-05-03T09:00:00Z"}]"#,
+        )
+        .unwrap();
+        let reference = parse_reference("gh:acme/api#7").unwrap();
+        let section = render(&reference, &issue, &comments);
+        assert_eq!(section.title, "GitHub pull request acme/api#7: Crash on empty input");
+        assert_eq!(
+            section.body,
+            "https://github.com/acme/api/pull/7\nopen, opened by @alice on 2024-05-01\n\nIt panics.\n\
+             \nComment by @bob on 2024-05-02:\nSame here\n\
+             \nComment by @carol on 2024-05-03:\n(empty)\n"
+        );
+    }
+}
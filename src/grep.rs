@@ -0,0 +1,130 @@
+use regex::RegexBuilder;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+
+use crate::{
+    active_profile, command_line_options, exit_codes, get_config_path, git, load_config, porcelain, porcelain_record,
+    profile_files, roots, special, ActiveProfile, Error, QUIET,
+};
+
+// Matching lines shown per profile; the rest are counted
+const SHOWN_LINES: usize = 5;
+
+struct Hit {
+    path: PathBuf,
+    line: usize,
+    text: String,
+}
+
+// Every working-tree file the profile would bundle, searched line by line; a file shared by
+// several profiles is read once
+fn search(
+    profile: &ActiveProfile,
+    pattern: &regex::Regex,
+    seen: &mut HashMap<PathBuf, Vec<(usize, String)>>,
+) -> Vec<Hit> {
+    let mut hits = Vec::new();
+    for entry in profile_files(profile) {
+        if git::parse_revision_entry(&entry).is_some() {
+            continue;
+        }
+        let path = roots::resolve(&entry, &profile.roots);
+        if !special::safe_to_read(&path) {
+            continue;
+        }
+        let key = fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+        let lines = seen.entry(key).or_insert_with(|| {
+            // Unreadable and non-UTF-8 files have nothing to match
+            fs::read_to_string(&path)
+                .unwrap_or_default()
+                .lines()
+                .enumerate()
+                .filter(|(_, line)| pattern.is_match(line))
+                .map(|(index, line)| (index + 1, line.trim().to_string()))
+                .collect()
+        });
+        hits.extend(lines.iter().map(|(line, text)| Hit {
+            path: path.clone(),
+            line: *line,
+            text: text.clone(),
+        }));
+    }
+    hits
+}
+
+pub fn run(args: &[String]) -> Result<(), Error> {
+    let usage = "Usage: fdllm grep-profiles <pattern> [-i] [-F]";
+    let mut pattern = None;
+    let mut ignore_case = false;
+    let mut fixed = false;
+    for arg in args {
+        match arg.as_str() {
+            "-i" | "--ignore-case" => ignore_case = true,
+            "-F" | "--fixed-strings" => fixed = true,
+            _ if pattern.is_none() && (!arg.starts_with('-') || arg.len() == 1) => pattern = Some(arg.as_str()),
+            _ => {
+                eprintln!("{}", usage);
+                std::process::exit(exit_codes::CONFIG);
+            }
+        }
+    }
+    let Some(pattern) = pattern else {
+        eprintln!("{}", usage);
+        std::process::exit(exit_codes::CONFIG);
+    };
+    let source = if fixed { regex::escape(pattern) } else { pattern.to_string() };
+    let regex = match RegexBuilder::new(&source).case_insensitive(ignore_case).build() {
+        Ok(regex) => regex,
+        Err(err) => {
+            eprintln!("Invalid pattern '{}': {}", pattern, err);
+            std::process::exit(exit_codes::CONFIG);
+        }
+    };
+
+    let config = load_config(&get_config_path()?)?;
+    let mut names: Vec<&String> = config.profiles.iter().flat_map(|profiles| profiles.keys()).collect();
+    names.sort();
+
+    // Every profile is walked, so their own progress messages are silenced
+    QUIET.store(true, Ordering::Relaxed);
+    let mut seen = HashMap::new();
+    let mut matches = Vec::new();
+    let default_hits = search(&active_profile(&config, None, command_line_options())?, &regex, &mut seen);
+    if !default_hits.is_empty() {
+        matches.push(("(default)".to_string(), default_hits));
+    }
+    for name in names {
+        let hits = search(&active_profile(&config, Some(name), command_line_options())?, &regex, &mut seen);
+        if !hits.is_empty() {
+            matches.push((name.clone(), hits));
+        }
+    }
+    QUIET.store(false, Ordering::Relaxed);
+
+    if porcelain() {
+        for (name, hits) in &matches {
+            for hit in hits {
+                porcelain_record("match", format!("{} {} {}", name, hit.line, hit.path.display()));
+            }
+        }
+        return Ok(());
+    }
+    if matches.is_empty() {
+        println!("No profile has a match for {}", pattern);
+        return Ok(());
+    }
+    for (name, hits) in &matches {
+        let mut files: Vec<&PathBuf> = hits.iter().map(|hit| &hit.path).collect();
+        files.dedup();
+        println!("{}: {} match(es) in {} file(s)", name, hits.len(), files.len());
+        for hit in hits.iter().take(SHOWN_LINES) {
+            println!("  {}:{}: {}", hit.path.display(), hit.line, hit.text);
+        }
+        if hits.len() > SHOWN_LINES {
+            println!("  ... {} more", hits.len() - SHOWN_LINES);
+        }
+    }
+    Ok(())
+}
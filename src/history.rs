@@ -0,0 +1,309 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::{exit_codes, get_state_dir, tokens};
+use crate::provenance::utc_timestamp;
+
+// Profile name (or mode) and start time of the current run, set once by main
+static RUN: OnceLock<(String, Instant)> = OnceLock::new();
+
+// Set by `usage_metrics = true`: runs also record which files they included
+static TRACK_PATHS: AtomicBool = AtomicBool::new(false);
+
+// A profile not bundled for this long is reported as stale by `fdllm usage`
+const STALE_DAYS: i64 = 30;
+
+// Average bundle size above which `fdllm usage` flags a profile as large
+const LARGE_TOKENS: usize = 100_000;
+
+#[derive(Serialize, Deserialize)]
+struct Run {
+    timestamp: String,
+    profile: String,
+    tokens: usize,
+    files: usize,
+    bytes: usize,
+    duration_ms: u64,
+    // Empty unless usage metrics are enabled (and for runs recorded before they existed)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    paths: Vec<String>,
+    // The bundle counted under each of tokens::ENCODERS (missing for older runs)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    encodings: BTreeMap<String, usize>,
+}
+
+fn history_path() -> PathBuf {
+    get_state_dir().join("history.jsonl")
+}
+
+pub fn begin(profile: &str) {
+    let _ = RUN.set((profile.to_string(), Instant::now()));
+}
+
+// The profile (or mode) this run bundles, once `begin` has named it
+pub fn profile() -> Option<&'static str> {
+    RUN.get().map(|(profile, _)| profile.as_str())
+}
+
+pub fn track_paths() {
+    TRACK_PATHS.store(true, Ordering::Relaxed);
+}
+
+// Appends one line per successful bundle; runs that never called `begin` are not recorded
+pub fn record(tokens: usize, encodings: BTreeMap<String, usize>, paths: &[String], bytes: usize) {
+    let Some((profile, started)) = RUN.get() else {
+        return;
+    };
+
+    let run = Run {
+        timestamp: utc_timestamp(SystemTime::now()),
+        profile: profile.clone(),
+        tokens,
+        files: paths.len(),
+        bytes,
+        duration_ms: started.elapsed().as_millis() as u64,
+        paths: if TRACK_PATHS.load(Ordering::Relaxed) { paths.to_vec() } else { Vec::new() },
+        encodings,
+    };
+
+    let path = history_path();
+    let line = serde_json::to_string(&run).expect("Failed to serialize run stats");
+    let result = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| writeln!(file, "{}", line));
+    if let Err(err) = result {
+        eprintln!("Failed to record run stats in {}: {}", path.display(), err);
+    }
+}
+
+fn load_runs() -> Vec<Run> {
+    let content = fs::read_to_string(history_path()).unwrap_or_default();
+    // A torn last line (interrupted write) should not hide the rest of the history
+    content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+fn percent_change(from: usize, to: usize) -> String {
+    if from == 0 {
+        return "n/a".to_string();
+    }
+    let change = (to as f64 - from as f64) / from as f64 * 100.0;
+    format!("{:+.1}%", change)
+}
+
+fn print_history(runs: &[&Run]) {
+    info!(
+        "{:<22} {:<16} {:>9} {:>8} {:>6} {:>9}",
+        "timestamp", "profile", "tokens", "change", "files", "duration"
+    );
+    // Changes are relative to the same profile's previous run
+    let mut previous: HashMap<&str, usize> = HashMap::new();
+    for run in runs {
+        let change = match previous.insert(&run.profile, run.tokens) {
+            Some(previous) => format!("{:+}", run.tokens as i64 - previous as i64),
+            None => String::new(),
+        };
+        info!(
+            "{:<22} {:<16} {:>9} {:>8} {:>6} {:>7}ms",
+            run.timestamp, run.profile, run.tokens, change, run.files, run.duration_ms
+        );
+    }
+
+    let mut profiles: Vec<&str> = previous.into_keys().collect();
+    profiles.sort();
+    for profile in profiles {
+        let profile_runs: Vec<&&Run> = runs.iter().filter(|run| run.profile == profile).collect();
+        if let (Some(first), Some(last)) = (profile_runs.first(), profile_runs.last()) {
+            if profile_runs.len() > 1 {
+                info!(
+                    "\n{}: context size {} since {} ({} -> {} tokens over {} runs)",
+                    profile,
+                    percent_change(first.tokens, last.tokens),
+                    first.timestamp,
+                    first.tokens,
+                    last.tokens,
+                    profile_runs.len()
+                );
+            }
+        }
+    }
+}
+
+// Latest run per profile
+fn print_summary(runs: &[&Run]) {
+    let mut latest: Vec<(&Run, usize)> = Vec::new();
+    for run in runs {
+        match latest.iter_mut().find(|(seen, _)| seen.profile == run.profile) {
+            Some(entry) => *entry = (run, entry.1 + 1),
+            None => latest.push((run, 1)),
+        }
+    }
+    latest.sort_by(|a, b| a.0.profile.cmp(&b.0.profile));
+
+    let encoders: String = tokens::ENCODERS.iter().map(|name| format!(" {:>9}", name)).collect();
+    info!(
+        "{:<16} {:>5}{} {:>6} {:>10}  {}",
+        "profile", "runs", encoders, "files", "bytes", "last run"
+    );
+    for (run, count) in latest {
+        // Runs recorded before per-encoder counts only have the o200k total
+        let counts: String = tokens::ENCODERS
+            .iter()
+            .enumerate()
+            .map(|(index, name)| match run.encodings.get(*name) {
+                Some(count) => format!(" {:>9}", count),
+                None if index == 0 => format!(" {:>9}", run.tokens),
+                None => format!(" {:>9}", "-"),
+            })
+            .collect();
+        info!(
+            "{:<16} {:>5}{} {:>6} {:>10}  {}",
+            run.profile, count, counts, run.files, run.bytes, run.timestamp
+        );
+    }
+    info!("(~ approximate: Claude at ~3.5 characters per token, Llama 3 with cl100k_base)");
+}
+
+pub fn stats(args: &[String]) {
+    let mut profile = None;
+    let mut show_history = false;
+    for arg in args {
+        match arg.as_str() {
+            "--history" => show_history = true,
+            _ if !arg.starts_with('-') && profile.is_none() => profile = Some(arg.as_str()),
+            _ => {
+                eprintln!("Usage: fdllm stats [profile] [--history]");
+                std::process::exit(exit_codes::CONFIG);
+            }
+        }
+    }
+
+    let all_runs = load_runs();
+    let runs: Vec<&Run> = all_runs
+        .iter()
+        .filter(|run| profile.is_none_or(|profile| run.profile == profile))
+        .collect();
+
+    if runs.is_empty() {
+        match profile {
+            Some(profile) => info!("No recorded runs for '{}'", profile),
+            None => info!("No recorded runs yet"),
+        }
+        return;
+    }
+
+    if show_history {
+        print_history(&runs);
+    } else {
+        print_summary(&runs);
+    }
+}
+
+// Days since 1970-01-01 for the date part of a recorded RFC 3339 timestamp
+fn days_since_epoch(timestamp: &str) -> Option<i64> {
+    let mut parts = timestamp.get(..10)?.split('-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: i64 = parts.next()?.parse().ok()?;
+    let day: i64 = parts.next()?.parse().ok()?;
+
+    // Inverse of the civil-date conversion in provenance::utc_timestamp
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    Some(era * 146_097 + doe - 719_468)
+}
+
+pub fn usage(args: &[String]) {
+    let mut top = 10;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--top" => match iter.next().and_then(|value| value.parse().ok()) {
+                Some(value) if value > 0 => top = value,
+                _ => {
+                    eprintln!("--top expects a positive number");
+                    std::process::exit(exit_codes::CONFIG);
+                }
+            },
+            _ => {
+                eprintln!("Usage: fdllm usage [--top N]");
+                std::process::exit(exit_codes::CONFIG);
+            }
+        }
+    }
+
+    let runs = load_runs();
+    if runs.is_empty() {
+        info!("No recorded runs yet");
+        return;
+    }
+
+    let today = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| (elapsed.as_secs() / 86_400) as i64)
+        .unwrap_or(0);
+
+    let mut profiles: Vec<&str> = runs.iter().map(|run| run.profile.as_str()).collect();
+    profiles.sort();
+    profiles.dedup();
+
+    info!(
+        "{:<16} {:>5} {:>10} {:>10} {:>9}  {}",
+        "profile", "runs", "avg tokens", "max tokens", "last run", "notes"
+    );
+    for profile in profiles {
+        let profile_runs: Vec<&Run> = runs.iter().filter(|run| run.profile == profile).collect();
+        let average = profile_runs.iter().map(|run| run.tokens).sum::<usize>() / profile_runs.len();
+        let max = profile_runs.iter().map(|run| run.tokens).max().unwrap_or(0);
+        let age = profile_runs
+            .last()
+            .and_then(|run| days_since_epoch(&run.timestamp))
+            .map(|day| today - day);
+
+        let mut notes = Vec::new();
+        if age.is_some_and(|age| age > STALE_DAYS) {
+            notes.push("stale");
+        }
+        if average > LARGE_TOKENS {
+            notes.push("large");
+        }
+        info!(
+            "{:<16} {:>5} {:>10} {:>10} {:>9}  {}",
+            profile,
+            profile_runs.len(),
+            average,
+            max,
+            age.map(|age| format!("{}d ago", age)).unwrap_or_default(),
+            notes.join(", ")
+        );
+    }
+
+    let mut included: HashMap<&str, usize> = HashMap::new();
+    for run in &runs {
+        for path in &run.paths {
+            *included.entry(path).or_insert(0) += 1;
+        }
+    }
+    if included.is_empty() {
+        info!("\nSet `usage_metrics = true` in the config to also track the most-included files");
+        return;
+    }
+    let mut included: Vec<(&str, usize)> = included.into_iter().collect();
+    included.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    info!("\nMost-included files:");
+    for (path, count) in included.into_iter().take(top) {
+        info!("  {:>5}  {}", count, path);
+    }
+}
@@ -0,0 +1,155 @@
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::path::Path;
+
+use crate::lang::{language_for, line_comment};
+
+// `strip_imports = true` for every supported language, or a list such as ["rust", "python"]
+#[derive(Deserialize, JsonSchema, Clone)]
+#[serde(untagged)]
+pub enum StripImports {
+    All(bool),
+    Languages(Vec<String>),
+}
+
+impl StripImports {
+    fn applies_to(&self, language: &str) -> bool {
+        match self {
+            StripImports::All(enabled) => *enabled,
+            StripImports::Languages(languages) => languages.iter().any(|name| name == language),
+        }
+    }
+}
+
+// Number of lines the import statement starting at `lines[start]` spans, if it is one
+fn import_len(language: &str, lines: &[&str], start: usize) -> Option<usize> {
+    let line = lines[start];
+    let trimmed = line.trim_start();
+
+    let ends_when = |done: &dyn Fn(&str) -> bool| -> usize {
+        let mut end = start;
+        while end < lines.len() && !done(lines[end]) {
+            end += 1;
+        }
+        (end - start + 1).min(lines.len() - start)
+    };
+
+    match language {
+        "rust" => {
+            // Top-level only; `pub use` re-exports are part of the API surface and stay
+            if line.starts_with("use ") || line.starts_with("extern crate ") {
+                Some(ends_when(&|line: &str| line.trim_end().ends_with(';')))
+            } else {
+                None
+            }
+        }
+        "python" => {
+            // Only module-level imports; nested ones are usually meaningful
+            if line.starts_with("import ") || (line.starts_with("from ") && line.contains(" import")) {
+                if line.contains('(') && !line.contains(')') {
+                    Some(ends_when(&|line: &str| line.contains(')')))
+                } else {
+                    Some(ends_when(&|line: &str| !line.trim_end().ends_with('\\')))
+                }
+            } else {
+                None
+            }
+        }
+        "javascript" | "typescript" => {
+            if trimmed.starts_with("import ") || trimmed.starts_with("import{") {
+                // `import x from "y"`, `import "y"`, or a multi-line `import {\n a,\n} from "y"`
+                Some(ends_when(&|line: &str| {
+                    let line = line.trim_end().trim_end_matches(';');
+                    line.ends_with('"') || line.ends_with('\'')
+                }))
+            } else {
+                None
+            }
+        }
+        "go" => {
+            if trimmed.starts_with("import (") {
+                Some(ends_when(&|line: &str| line.trim() == ")"))
+            } else if trimmed.starts_with("import ") {
+                Some(1)
+            } else {
+                None
+            }
+        }
+        "c" | "cpp" => {
+            if trimmed.starts_with("#include") {
+                Some(1)
+            } else {
+                None
+            }
+        }
+        "java" | "kotlin" | "scala" | "swift" => {
+            if trimmed.starts_with("import ") {
+                Some(1)
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+fn strip(language: &str, content: &str) -> (String, usize) {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut output: Vec<String> = Vec::new();
+    let mut stripped = 0;
+    let mut marker_index = None;
+    let mut index = 0;
+
+    while index < lines.len() {
+        match import_len(language, &lines, index) {
+            Some(len) => {
+                stripped += len;
+                index += len;
+                if marker_index.is_none() {
+                    marker_index = Some(output.len());
+                    output.push(String::new());
+                }
+                // Blank lines between import groups go with them
+                while index < lines.len()
+                    && lines[index].trim().is_empty()
+                    && index + 1 < lines.len()
+                    && import_len(language, &lines, index + 1).is_some()
+                {
+                    index += 1;
+                }
+            }
+            None => {
+                output.push(lines[index].to_string());
+                index += 1;
+            }
+        }
+    }
+
+    if let Some(marker_index) = marker_index {
+        output[marker_index] = format!(
+            "{} [fdllm: {} import line(s) stripped]",
+            line_comment(language),
+            stripped
+        );
+    }
+
+    let mut result = output.join("\n");
+    if content.ends_with('\n') {
+        result.push('\n');
+    }
+    (result, stripped)
+}
+
+pub fn strip_imports(path: &Path, content: String, setting: &StripImports) -> String {
+    let language = match language_for(path) {
+        Some(language) if setting.applies_to(language) => language,
+        _ => return content,
+    };
+
+    let (stripped_content, stripped) = strip(language, &content);
+    if stripped == 0 {
+        content
+    } else {
+        stripped_content
+    }
+}
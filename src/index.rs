@@ -0,0 +1,348 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::cache::{self, Stamp};
+use crate::chunk::{self, Chunker};
+use crate::embed::{self, Vector, EMBEDDER};
+use crate::{
+    active_profile, command_line_options, copy_bundle, exit_codes, get_config_path, get_state_dir, git, lang,
+    load_config, profile_files, roots, unicode_paths, ActiveProfile, Bundle, Error, Section,
+};
+
+#[derive(Serialize, Deserialize)]
+pub struct Chunk {
+    // 1-based, inclusive
+    pub start_line: usize,
+    pub end_line: usize,
+    pub vector: Vector,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct IndexedFile {
+    stamp: Stamp,
+    // Chunker name; files indexed before chunkers were configurable have none
+    #[serde(default)]
+    chunker: String,
+    pub chunks: Vec<Chunk>,
+}
+
+// One index per profile, keyed by canonical file path
+#[derive(Serialize, Deserialize, Default)]
+pub struct Index {
+    embedder: String,
+    pub files: BTreeMap<String, IndexedFile>,
+}
+
+fn index_path(name: &str) -> PathBuf {
+    let file_name: String = name
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    get_state_dir().join("index").join(format!("{}.json", file_name))
+}
+
+pub fn load(name: &str) -> Option<Index> {
+    let content = fs::read_to_string(index_path(name)).ok()?;
+    let index: Index = serde_json::from_str(&content).ok()?;
+    if index.embedder == EMBEDDER {
+        Some(index)
+    } else {
+        None
+    }
+}
+
+fn save(name: &str, index: &Index) {
+    let path = index_path(name);
+    // Written whole and renamed so an interrupted run leaves the previous index intact
+    let staging = path.with_extension("json.tmp");
+    let result = fs::create_dir_all(path.parent().unwrap())
+        .and_then(|_| fs::write(&staging, serde_json::to_string(index).unwrap_or_default()))
+        .and_then(|_| fs::rename(&staging, &path));
+    if let Err(err) = result {
+        eprintln!("Failed to write index {}: {}", path.display(), err);
+        std::process::exit(1);
+    }
+}
+
+fn embed_file(content: &str, chunker: Chunker) -> Vec<Chunk> {
+    let lines: Vec<&str> = content.lines().collect();
+    chunk::chunk_ranges(chunker, content)
+        .into_iter()
+        .map(|(start_line, end_line)| {
+            let end = end_line.min(lines.len());
+            let text = lines[start_line - 1..end].join("\n");
+            Chunk {
+                start_line,
+                end_line,
+                vector: embed::embed(&text),
+            }
+        })
+        .filter(|chunk| !chunk.vector.is_empty())
+        .collect()
+}
+
+// Canonical paths of everything the profile would bundle from the working tree
+pub fn profile_paths(profile_name: Option<&str>) -> Result<Vec<PathBuf>, Error> {
+    let config = load_config(&get_config_path()?)?;
+    Ok(paths_of(&active_profile(&config, profile_name, command_line_options())?))
+}
+
+fn paths_of(profile: &ActiveProfile) -> Vec<PathBuf> {
+    let mut seen = HashSet::new();
+    profile_files(profile)
+        .iter()
+        .filter(|entry| git::parse_revision_entry(entry).is_none())
+        .map(|entry| roots::resolve(entry, &profile.roots))
+        .filter(|path| path.is_file())
+        .filter_map(|path| fs::canonicalize(path).ok())
+        .filter(|path| seen.insert(unicode_paths::key(path)))
+        .collect()
+}
+
+pub fn run(args: &[String]) -> Result<(), Error> {
+    let mut profile_name = None;
+    let mut rebuild = false;
+    for arg in args {
+        match arg.as_str() {
+            "--rebuild" => rebuild = true,
+            _ if !arg.starts_with('-') && profile_name.is_none() => profile_name = Some(arg.as_str()),
+            _ => {
+                eprintln!("Usage: fdllm index [profile] [--rebuild]");
+                std::process::exit(exit_codes::CONFIG);
+            }
+        }
+    }
+
+    let name = profile_name.unwrap_or("default");
+    let config = load_config(&get_config_path()?)?;
+    let profile = active_profile(&config, profile_name, command_line_options())?;
+    lang::set_overrides(profile.language_overrides.clone());
+    let paths = paths_of(&profile);
+
+    let mut index = if rebuild { None } else { load(name) }.unwrap_or_else(|| Index {
+        embedder: EMBEDDER.to_string(),
+        ..Default::default()
+    });
+
+    let (mut added, mut updated, mut unchanged) = (0, 0, 0);
+    let mut current = HashSet::new();
+    for path in &paths {
+        let key = path.to_string_lossy().to_string();
+        current.insert(key.clone());
+
+        let stamp = match cache::stamp(path) {
+            Ok(stamp) => stamp,
+            Err(err) => {
+                eprintln!("Failed to stat {}: {}", path.display(), err);
+                continue;
+            }
+        };
+        let chunker = chunk::chunker_for(path, profile.chunking);
+        if index
+            .files
+            .get(&key)
+            .is_some_and(|file| file.stamp == stamp && file.chunker == chunker.name())
+        {
+            unchanged += 1;
+            continue;
+        }
+
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) => {
+                eprintln!("Skipping {}: {}", path.display(), err);
+                continue;
+            }
+        };
+        let indexed = IndexedFile {
+            stamp,
+            chunker: chunker.name().to_string(),
+            chunks: embed_file(&content, chunker),
+        };
+        if index.files.insert(key, indexed).is_some() {
+            updated += 1;
+        } else {
+            added += 1;
+        }
+    }
+
+    let before = index.files.len();
+    index.files.retain(|key, _| current.contains(key));
+    let removed = before - index.files.len();
+
+    save(name, &index);
+
+    let chunks: usize = index.files.values().map(|file| file.chunks.len()).sum();
+    info!(
+        "Indexed {} files ({} chunks) for '{}': {} new, {} updated, {} removed, {} unchanged",
+        index.files.len(),
+        chunks,
+        name,
+        added,
+        updated,
+        removed,
+        unchanged
+    );
+    info!("Index stored at {}", index_path(name).display());
+    Ok(())
+}
+
+pub struct Hit {
+    pub path: PathBuf,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub score: f32,
+}
+
+// Best chunks first; a chunk overlapping a better one from the same file is dropped
+pub fn search(index: &Index, query: &str, top: usize) -> Vec<Hit> {
+    let query = &embed::embed(query);
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    let mut scored: Vec<Hit> = index
+        .files
+        .iter()
+        .flat_map(|(path, file)| {
+            file.chunks.iter().map(move |chunk| Hit {
+                path: PathBuf::from(path),
+                start_line: chunk.start_line,
+                end_line: chunk.end_line,
+                score: embed::similarity(query, &chunk.vector),
+            })
+        })
+        .filter(|hit| hit.score > 0.0)
+        .collect();
+    scored.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+    let mut hits: Vec<Hit> = Vec::new();
+    for hit in scored {
+        let overlaps = hits.iter().any(|kept| {
+            kept.path == hit.path && hit.start_line <= kept.end_line && kept.start_line <= hit.end_line
+        });
+        if !overlaps {
+            hits.push(hit);
+        }
+        if hits.len() == top {
+            break;
+        }
+    }
+    hits
+}
+
+// Relative to the working directory when the file lives below it
+fn display_path(path: &Path) -> String {
+    env::current_dir()
+        .ok()
+        .and_then(|cwd| fs::canonicalize(cwd).ok())
+        .and_then(|cwd| path.strip_prefix(cwd).ok().map(Path::to_path_buf))
+        .unwrap_or_else(|| path.to_path_buf())
+        .to_string_lossy()
+        .to_string()
+}
+
+fn excerpt(path: &Path, start_line: usize, end_line: usize) -> Option<String> {
+    let content = fs::read_to_string(path).ok()?;
+    let lines: Vec<&str> = content
+        .lines()
+        .skip(start_line - 1)
+        .take(end_line + 1 - start_line)
+        .collect();
+    Some(lines.join("\n"))
+}
+
+pub fn semsearch(args: &[String]) -> Result<(), Error> {
+    let usage = "Usage: fdllm semsearch <query> [profile] [--top N] [--copy]";
+    let mut positional = Vec::new();
+    let mut top = 10;
+    let mut copy = false;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--copy" => copy = true,
+            "--top" => match iter.next().and_then(|value| value.parse().ok()) {
+                Some(value) if value > 0 => top = value,
+                _ => {
+                    eprintln!("--top expects a positive number");
+                    std::process::exit(exit_codes::CONFIG);
+                }
+            },
+            _ if !arg.starts_with('-') && positional.len() < 2 => positional.push(arg.as_str()),
+            _ => {
+                eprintln!("{}", usage);
+                std::process::exit(exit_codes::CONFIG);
+            }
+        }
+    }
+
+    let (query, profile_name) = match positional.as_slice() {
+        [query] => (*query, None),
+        [query, profile] => (*query, Some(*profile)),
+        _ => {
+            eprintln!("{}", usage);
+            std::process::exit(exit_codes::CONFIG);
+        }
+    };
+
+    let name = profile_name.unwrap_or("default");
+    let index = match load(name) {
+        Some(index) => index,
+        None => {
+            eprintln!("No index for '{}'; run `fdllm index {}` first", name, profile_name.unwrap_or(""));
+            std::process::exit(1);
+        }
+    };
+
+    let stale = index
+        .files
+        .iter()
+        .filter(|(path, file)| cache::stamp(Path::new(path)).ok().as_ref() != Some(&file.stamp))
+        .count();
+    if stale > 0 {
+        eprintln!("Index is stale for {} file(s); run `fdllm index` to refresh", stale);
+    }
+
+    let hits = search(&index, query, top);
+    if hits.is_empty() {
+        info!("No matches for \"{}\"", query);
+        return Ok(());
+    }
+
+    for hit in &hits {
+        info!(
+            "{:.3}  {}:{}-{}",
+            hit.score,
+            display_path(&hit.path),
+            hit.start_line,
+            hit.end_line
+        );
+    }
+
+    if copy {
+        // Titles use the `path (lines a-b)` form that `fdllm open` understands
+        let sections: Vec<Section> = hits
+            .iter()
+            .filter_map(|hit| {
+                Some(Section {
+                    title: format!(
+                        "{} (lines {}-{})",
+                        display_path(&hit.path),
+                        hit.start_line,
+                        hit.end_line
+                    ),
+                    body: excerpt(&hit.path, hit.start_line, hit.end_line)?,
+                })
+            })
+            .collect();
+        copy_bundle(Bundle {
+            sections,
+            ..Default::default()
+        })?;
+    }
+    Ok(())
+}
@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+
+// Keys naming a profile's layers rather than settings; each profile keeps only its own
+const LAYER_KEYS: [&str; 2] = ["extends", "include_profiles"];
+
+// `extends` first, then `include_profiles` in order
+fn layer_names(profile: &toml::Table) -> Vec<String> {
+    let mut names = Vec::new();
+    if let Some(toml::Value::String(name)) = profile.get("extends") {
+        names.push(name.clone());
+    }
+    if let Some(toml::Value::Array(list)) = profile.get("include_profiles") {
+        names.extend(list.iter().filter_map(toml::Value::as_str).map(str::to_string));
+    }
+    names
+}
+
+// Lists are combined (inherited entries first, duplicates dropped), tables merged key by key,
+// anything else replaced
+fn layer(base: &mut toml::Value, over: toml::Value) {
+    match (base, over) {
+        (toml::Value::Array(base), toml::Value::Array(over)) => {
+            for value in over {
+                if !base.contains(&value) {
+                    base.push(value);
+                }
+            }
+        }
+        (toml::Value::Table(base), toml::Value::Table(over)) => {
+            for (key, value) in over {
+                match base.get_mut(&key) {
+                    Some(existing) => layer(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, over) => *base = over,
+    }
+}
+
+fn resolve_profile(
+    name: &str,
+    profiles: &toml::Table,
+    resolved: &mut HashMap<String, toml::Table>,
+    chain: &mut Vec<String>,
+) -> Result<toml::Table, String> {
+    if let Some(profile) = resolved.get(name) {
+        return Ok(profile.clone());
+    }
+    if chain.iter().any(|seen| seen == name) {
+        chain.push(name.to_string());
+        return Err(format!("profile inheritance cycle: {}", chain.join(" -> ")));
+    }
+    let Some(toml::Value::Table(profile)) = profiles.get(name) else {
+        return Err(format!("profile '{}' inherits from unknown profile '{}'", chain.join(" -> "), name));
+    };
+
+    chain.push(name.to_string());
+    let mut layered = toml::Value::Table(toml::Table::new());
+    for base_name in layer_names(profile) {
+        let mut base = resolve_profile(&base_name, profiles, resolved, chain)?;
+        for key in LAYER_KEYS {
+            base.remove(key);
+        }
+        layer(&mut layered, toml::Value::Table(base));
+    }
+    layer(&mut layered, toml::Value::Table(profile.clone()));
+    chain.pop();
+
+    let toml::Value::Table(layered) = layered else {
+        unreachable!("profiles are layered onto a table");
+    };
+    resolved.insert(name.to_string(), layered.clone());
+    Ok(layered)
+}
+
+// Profiles with `extends = "base"` or `include_profiles = ["common", "backend"]` are expanded
+// into the settings they inherit, with their own applied last
+pub fn resolve(config: &mut toml::Table) -> Result<(), String> {
+    let Some(toml::Value::Table(profiles)) = config.get("profiles") else {
+        return Ok(());
+    };
+    let mut resolved = HashMap::new();
+    let names: Vec<String> = profiles.keys().cloned().collect();
+    for name in &names {
+        resolve_profile(name, profiles, &mut resolved, &mut Vec::new())?;
+    }
+    let profiles = resolved.into_iter().map(|(name, profile)| (name, toml::Value::Table(profile)));
+    config.insert("profiles".to_string(), toml::Value::Table(profiles.collect()));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolved(text: &str) -> Result<toml::Table, String> {
+        let mut table: toml::Table = text.parse().unwrap();
+        resolve(&mut table).map(|_| table)
+    }
+
+    #[test]
+    fn layers_lists_tables_and_scalars() {
+        let table = resolved(
+            r#"
+            [profiles.base]
+            files = ["a", "b"]
+            format = "plain"
+            roots = { api = "~/api" }
+            [profiles.extra]
+            files = ["c"]
+            [profiles.child]
+            extends = "base"
+            include_profiles = ["extra"]
+            files = ["b", "d"]
+            format = "markdown"
+            roots = { web = "~/web" }
+            "#,
+        )
+        .unwrap();
+        let child = &table["profiles"]["child"];
+        let files: Vec<&str> = child["files"].as_array().unwrap().iter().map(|file| file.as_str().unwrap()).collect();
+        assert_eq!(files, ["a", "b", "c", "d"]);
+        assert_eq!(child["format"].as_str(), Some("markdown"));
+        assert_eq!(child["roots"].as_table().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn rejects_cycles_and_unknown_parents() {
+        let cycle = resolved("[profiles.a]\nextends = \"b\"\n[profiles.b]\nextends = \"a\"\n").unwrap_err();
+        assert!(cycle.contains("a -> b -> a"), "{}", cycle);
+        assert!(resolved("[profiles.a]\nextends = \"nowhere\"\n").is_err());
+    }
+}
@@ -0,0 +1,84 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::OnceLock;
+
+// `language_overrides`, set once from the active profile; modes keep the built-in table
+static OVERRIDES: OnceLock<BTreeMap<String, String>> = OnceLock::new();
+
+pub fn set_overrides(overrides: BTreeMap<String, String>) {
+    let _ = OVERRIDES.set(overrides);
+}
+
+// An exact file name wins, then the longest matching suffix (".inc", ".blade.php")
+fn overridden(name: &str) -> Option<&'static str> {
+    let overrides = OVERRIDES.get()?;
+    if let Some(language) = overrides.get(name) {
+        return Some(language);
+    }
+    let name = name.to_lowercase();
+    overrides
+        .iter()
+        .filter(|(key, _)| key.starts_with('.') && name.ends_with(&key.to_lowercase()))
+        .max_by_key(|(key, _)| key.len())
+        .map(|(_, language)| language.as_str())
+}
+
+// Whether `language_overrides` names this file, which includes it even without an extension
+pub fn is_overridden(path: &Path) -> bool {
+    path.file_name().is_some_and(|name| overridden(&name.to_string_lossy()).is_some())
+}
+
+// Language of a file from its extension (or well-known file name)
+pub fn language_for(path: &Path) -> Option<&'static str> {
+    let name = path.file_name()?.to_string_lossy();
+    if let Some(language) = overridden(&name) {
+        return Some(language);
+    }
+    match name.as_ref() {
+        "Makefile" | "makefile" | "GNUmakefile" => return Some("make"),
+        "Dockerfile" => return Some("dockerfile"),
+        "CMakeLists.txt" => return Some("cmake"),
+        _ => {}
+    }
+
+    let extension = path.extension()?.to_string_lossy().to_lowercase();
+    let language = match extension.as_str() {
+        "rs" => "rust",
+        "py" | "pyi" => "python",
+        "js" | "mjs" | "cjs" | "jsx" => "javascript",
+        "ts" | "mts" | "cts" | "tsx" => "typescript",
+        "go" => "go",
+        "c" | "h" => "c",
+        "cpp" | "cc" | "cxx" | "hpp" | "hh" | "hxx" => "cpp",
+        "java" => "java",
+        "kt" | "kts" => "kotlin",
+        "scala" => "scala",
+        "swift" => "swift",
+        "rb" => "ruby",
+        "lua" => "lua",
+        "nu" => "nu",
+        "sh" | "bash" | "zsh" => "bash",
+        "toml" => "toml",
+        "json" => "json",
+        "yaml" | "yml" => "yaml",
+        "md" => "markdown",
+        "sql" => "sql",
+        "graphql" | "gql" => "graphql",
+        "tf" | "tfvars" | "hcl" => "hcl",
+        "html" | "htm" => "html",
+        "css" => "css",
+        "csv" => "csv",
+        "txt" | "log" => "text",
+        _ => return None,
+    };
+    Some(language)
+}
+
+// Prefix for a single-line comment, used for markers inserted into file content
+pub fn line_comment(language: &str) -> &'static str {
+    match language {
+        "python" | "ruby" | "bash" | "toml" | "yaml" | "make" | "dockerfile" | "cmake" | "nu" | "hcl" => "#",
+        "lua" | "sql" => "--",
+        _ => "//",
+    }
+}
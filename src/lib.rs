@@ -0,0 +1,3250 @@
+use ignore::WalkBuilder;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+// Set by --porcelain: stdout is reserved for stable machine-readable records
+static PORCELAIN: AtomicBool = AtomicBool::new(false);
+
+fn porcelain() -> bool {
+    PORCELAIN.load(Ordering::Relaxed)
+}
+
+// Set when stdout carries data (e.g. --diff-output), so messages must stay out of it
+static STDOUT_DATA: AtomicBool = AtomicBool::new(false);
+
+fn stdout_reserved() -> bool {
+    porcelain() || STDOUT_DATA.load(Ordering::Relaxed)
+}
+
+// Set by --deterministic: bundles are stamped with the epoch, files sorted, trees drawn built-in
+static DETERMINISTIC: AtomicBool = AtomicBool::new(false);
+
+fn deterministic() -> bool {
+    DETERMINISTIC.load(Ordering::Relaxed)
+}
+
+// Set by --no-gitignore: directory walks include files that ignore rules would skip
+static NO_GITIGNORE: AtomicBool = AtomicBool::new(false);
+
+// Set by --max-tokens: the token budget for every bundle this run, profile or mode
+static MAX_TOKENS: OnceLock<usize> = OnceLock::new();
+
+// Set by --max-file-size: files over it are skipped or cut in every bundle, profile or mode
+static MAX_FILE_SIZE: OnceLock<u64> = OnceLock::new();
+
+// Set by --no-clipboard: bundles go only to the file outputs
+static NO_CLIPBOARD: AtomicBool = AtomicBool::new(false);
+
+// Set by --stdout: the bundle is printed instead of copied, so messages go to stderr
+static STDOUT_BUNDLE: AtomicBool = AtomicBool::new(false);
+
+// Set by --redaction: the preset of every bundle this run, profile or mode
+static REDACTION: OnceLock<redact::Redaction> = OnceLock::new();
+
+// Set by --output: replaces the output file of every bundle this run, profile or mode
+static OUTPUT_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+// Set by --format: the layout of every bundle this run, profile or mode
+static FORMAT: OnceLock<format::Format> = OnceLock::new();
+
+// Set by --clipboard-backend: how every bundle reaches the clipboard
+static CLIPBOARD_BACKEND: OnceLock<clipboard::ClipboardBackend> = OnceLock::new();
+
+// Set by --send-keys: the bundle, or with --send-path its file's path, is pasted into a tmux pane
+static SEND_KEYS: OnceLock<send_keys::SendKeys> = OnceLock::new();
+
+// Set by --prompt: the request every bundle this run carries, after the files and before the footer
+static PROMPT: OnceLock<String> = OnceLock::new();
+
+// Set by --template: lays out every bundle this run, profile or mode
+static TEMPLATE: OnceLock<PathBuf> = OnceLock::new();
+
+// Set by --var: template values on top of every profile's `template_vars`
+static TEMPLATE_VARS: OnceLock<Vec<(String, String)>> = OnceLock::new();
+
+// Set by --docs-only: Rust files are reduced to signatures and doc comments in every bundle
+static DOCS_ONLY: AtomicBool = AtomicBool::new(false);
+
+// Set by --include-binary-as-note: binary files get a one-line note instead of being left out
+static BINARY_AS_NOTE: AtomicBool = AtomicBool::new(false);
+
+// Set by --git: profile runs keep only the work in progress
+static GIT_SELECTION: OnceLock<git::Selection> = OnceLock::new();
+
+// Set by --conflicts: profile runs bundle the conflicted files, each with the versions git merges
+static CONFLICTS: AtomicBool = AtomicBool::new(false);
+
+// Set by --git-diff: the changes added as a section, replacing the profile's `git_diff`
+static GIT_DIFF: OnceLock<String> = OnceLock::new();
+
+// Set by --no-suggest: an unknown profile name is a plain error, for scripts
+static NO_SUGGEST: AtomicBool = AtomicBool::new(false);
+
+// Set by --allow-secrets: `scan = "block"` reports what it finds but delivers anyway
+static ALLOW_SECRETS: AtomicBool = AtomicBool::new(false);
+
+// Set by --dry-run: report what a bundle would contain instead of delivering it
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+// Set by --verbose: report each file as it is added
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+
+// Set by --config: the config file to use instead of the one in the app directory
+static CONFIG_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+// Set while a command evaluates many profiles at once (e.g. `fdllm which`)
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+// Human-readable progress messages; moved to stderr when stdout is reserved
+macro_rules! info {
+    ($($arg:tt)*) => {
+        if $crate::QUIET.load(std::sync::atomic::Ordering::Relaxed) {
+        } else if $crate::stdout_reserved() {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
+        }
+    };
+}
+
+// Extra detail for --verbose, routed like `info!`
+macro_rules! verbose {
+    ($($arg:tt)*) => {
+        if $crate::VERBOSE.load(std::sync::atomic::Ordering::Relaxed) {
+            info!($($arg)*);
+        }
+    };
+}
+
+// One `key value` line per record, only printed in --porcelain mode
+fn porcelain_record(key: &str, value: impl std::fmt::Display) {
+    if porcelain() {
+        println!("{} {}", key, value);
+    }
+}
+
+mod audit;
+mod auto;
+mod binary;
+mod blobs;
+mod budget;
+mod build_output;
+mod cache;
+mod chunk;
+mod cli;
+mod clipboard;
+mod compose;
+mod condense;
+mod database;
+mod deadline;
+mod exit_codes;
+mod diagnostics;
+mod docs;
+mod embed;
+mod error;
+mod file_budget;
+mod file_size;
+mod format;
+mod git;
+mod github;
+mod grep;
+mod history;
+mod imports;
+mod index;
+mod inherit;
+mod lang;
+mod long_lines;
+mod modes;
+mod open;
+mod output;
+mod pack;
+mod project_config;
+mod project_docs;
+mod profiles;
+mod prune;
+mod provenance;
+mod redact;
+mod roots;
+mod scan;
+mod send_keys;
+mod session;
+mod snippets;
+mod special;
+mod spill;
+mod stdin_paths;
+mod suggest;
+mod template;
+mod tokens;
+mod tree;
+mod trim;
+mod tuning;
+mod unicode_paths;
+mod which;
+
+#[derive(Deserialize, JsonSchema, Default)]
+pub struct Config {
+    // Top-level settings are the defaults every profile starts from; each is described on
+    // `Profile`, and only the ones a profile can't set are described here
+
+    // Legacy fields for backward compatibility
+    files: Option<Vec<String>>,
+    directories: Option<Vec<String>>,
+    project: Option<Project>,
+    database: Option<Database>,
+    git_diff: Option<String>,
+    git_diff_context: Option<u32>,
+    condense_schemas: Option<bool>,
+    cache: Option<bool>,
+    strip_imports: Option<imports::StripImports>,
+    redaction: Option<redact::Redaction>,
+    redaction_rules: Option<Vec<redact::CustomRule>>,
+    scan: Option<scan::Scan>,
+    elide_blobs: Option<blobs::ElideBlobs>,
+    max_line_length: Option<usize>,
+    long_lines: Option<long_lines::LongLines>,
+    max_tokens_per_file: Option<file_budget::MaxTokensPerFile>,
+    max_file_size: Option<file_size::FileSize>,
+    large_files: Option<file_size::LargeFiles>,
+    max_memory: Option<file_size::FileSize>,
+    max_tokens: Option<usize>,
+    budget_strategy: Option<budget::BudgetStrategy>,
+    format: Option<format::Format>,
+    output_path: Option<String>,
+    clipboard: Option<bool>,
+    clipboard_fallback: Option<output::ClipboardFallback>,
+    clipboard_backend: Option<clipboard::ClipboardBackend>,
+    provenance: Option<bool>,
+    signing_key: Option<String>,
+    roots: Option<BTreeMap<String, String>>,
+    rewrite_paths: Option<Vec<roots::PathRewrite>>,
+    exclude_paths: Option<Vec<String>>,
+    include_extensions: Option<Vec<String>>,
+    exclude_extensions: Option<Vec<String>>,
+    include_docs: Option<bool>,
+    threads: Option<usize>,
+    io_buffer_size: Option<usize>,
+    follow_mounts: Option<bool>,
+    gitignore: Option<bool>,
+    prune_report: Option<usize>,
+    header: Option<String>,
+    footer: Option<String>,
+    template: Option<String>,
+    template_vars: Option<BTreeMap<String, String>>,
+    chunking: Option<chunk::Chunking>,
+    language_overrides: Option<BTreeMap<String, String>>,
+    // Record which files each run included, for `fdllm usage` (kept in the state dir only)
+    usage_metrics: Option<bool>,
+    // Context windows of the models bundles go to, e.g. { "gpt-4o" = 128000, claude = 200000 };
+    // `fdllm audit` flags profiles too big for all of them
+    models: Option<BTreeMap<String, usize>>,
+    // In a project .fdllm.toml: `false` ignores the global config instead of merging over it
+    inherit_global: Option<bool>,
+    // New profiles field
+    profiles: Option<HashMap<String, Profile>>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct Profile {
+    files: Option<Vec<String>>,
+    directories: Option<Vec<String>>,
+    project: Option<Project>,
+    database: Option<Database>,
+    // Add `git diff` as a section: "unstaged", "staged", or a ref such as "main"; context lines
+    // default to git's own
+    git_diff: Option<String>,
+    git_diff_context: Option<u32>,
+    condense_schemas: Option<bool>,
+    cache: Option<bool>,
+    // `true` for every supported language, or a list such as ["rust", "python"]
+    strip_imports: Option<imports::StripImports>,
+    // Redaction preset for file contents: "strict", "standard" (default) or "off"; secrets
+    // (private keys, cloud and API tokens, JWTs, `.env` values, URL credentials) are redacted
+    // unless it is "off", and "strict" also takes passwords, emails, IPs, UUIDs and database URLs
+    redaction: Option<redact::Redaction>,
+    // Extra patterns, e.g. [{ name = "ticket", pattern = "TICKET-[0-9]+" }]; the top-level
+    // ones apply to every profile, before the profile's own
+    redaction_rules: Option<Vec<redact::CustomRule>>,
+    // Look again once redaction has run, for known key formats and random-looking strings:
+    // "warn" lists them, "block" also refuses to deliver unless --allow-secrets is given
+    scan: Option<scan::Scan>,
+    // Replace long base64/hex runs (certificates, fixtures, inline images) with a size marker:
+    // `true` for runs of 256+ characters, or a minimum length
+    elide_blobs: Option<blobs::ElideBlobs>,
+    // Cap pathological lines (data blobs, inline base64); `long_lines` is "truncate" (default) or "wrap"
+    max_line_length: Option<usize>,
+    long_lines: Option<long_lines::LongLines>,
+    // `true` for per-language defaults, a number for every file, or a per-language table
+    max_tokens_per_file: Option<file_budget::MaxTokensPerFile>,
+    // Files over this size (bytes, or "512KB", "2MB") are skipped unread, or cut as `large_files`
+    // says: "skip" (default), "head", "tail" or "head-tail"
+    max_file_size: Option<file_size::FileSize>,
+    large_files: Option<file_size::LargeFiles>,
+    // Past this size ("256MB") the bundle is assembled in a temp file instead of memory, and goes
+    // to output_path or stdout rather than the clipboard. It bounds the assembled bundle, not
+    // fdllm as a whole: every file is still read (and counted) before assembly starts
+    max_memory: Option<file_size::FileSize>,
+    // Token budget for the whole bundle; `budget_strategy` is "warn" (default), "largest-first"
+    // "drop-last" or "strict" (deliver nothing, exit 5)
+    max_tokens: Option<usize>,
+    budget_strategy: Option<budget::BudgetStrategy>,
+    // How the bundle is laid out: "plain" (`# NOTE: path:`, default), "markdown" (fenced blocks),
+    // "xml" (`<document>` elements), or "aider" / "cursor" for file blocks those editors can apply
+    format: Option<format::Format>,
+    // Also write the bundle to this file; `clipboard = false` makes it the only output.
+    // `{{profile}}`, `{{date}}` and `{{time}}` are filled in, e.g. "~/llm-bundles/{{profile}}-{{date}}.md"
+    output_path: Option<String>,
+    clipboard: Option<bool>,
+    // When the clipboard is unavailable: "file" (a temp file, default), "stdout" or "error"
+    clipboard_fallback: Option<output::ClipboardFallback>,
+    // "auto" (default), "native", "wl-copy" or "osc52" (the terminal's clipboard, over SSH too)
+    clipboard_backend: Option<clipboard::ClipboardBackend>,
+    // Append a provenance footer; a signing key (ed25519 seed file) also signs the bundle
+    provenance: Option<bool>,
+    signing_key: Option<String>,
+    // Labeled roots, e.g. `[roots]` with `api = "~/app/api"`: `files` and `directories` entries
+    // may then be written `label:path` (`api:src/main.rs`), and headers and trees show
+    // `label/relative/path`; a profile's table replaces the top-level one
+    roots: Option<BTreeMap<String, String>>,
+    // Path prefixes replaced in headers and tree titles, e.g. [{ from = "/home/alice/work/", to = "" }]
+    rewrite_paths: Option<Vec<roots::PathRewrite>>,
+    // Globs relative to each directory, e.g. "vendor/**", skipped while walking
+    exclude_paths: Option<Vec<String>>,
+    // Globs matched against each file's path as bundled: `outline_paths` reduces Rust files to
+    // signatures and docs as --docs-only does, `truncate_paths` cuts files to a number of
+    // tokens, e.g. { "src/generated/**" = 2000 }; `fdllm trim` writes both
+    outline_paths: Option<Vec<String>>,
+    truncate_paths: Option<BTreeMap<String, usize>>,
+    // Extensions added to or removed from the built-in list, e.g. [".sql", ".proto"]
+    include_extensions: Option<Vec<String>>,
+    exclude_extensions: Option<Vec<String>>,
+    // Put README*, ARCHITECTURE*, CONTRIBUTING* and docs/ index pages from the project root
+    // (`project.path`, else the current directory) first in the bundle
+    include_docs: Option<bool>,
+    // File reader threads (default one per core), read buffer in bytes (default 8192), and
+    // whether walks cross into other filesystems (default true); for NFS/SMB or small machines
+    threads: Option<usize>,
+    io_buffer_size: Option<usize>,
+    follow_mounts: Option<bool>,
+    // Honour .gitignore, .ignore and global git excludes while walking (default true)
+    gitignore: Option<bool>,
+    // How many of the heaviest subdirectories to report after a run (default 3, 0 turns it off)
+    prune_report: Option<usize>,
+    // Text put before everything else, such as the task or a system prompt; `{{profile}}` is
+    // filled in
+    header: Option<String>,
+    // Instructions appended after all files; `{{profile}}` and `{{file_count}}` are filled in
+    footer: Option<String>,
+    // A file laying out the whole bundle: `{{header}}`, `{{tree}}`, `{{sections}}`, `{{files}}`,
+    // `{{file_count}}`, `{{profile}}`, `{{footer}}`, `{{prompt}}` and the `template_vars`, with
+    // `{{#if name}}...{{/if}}`
+    template: Option<String>,
+    // Values for the template, e.g. { task = "Find the bug" }; --var adds to them
+    template_vars: Option<BTreeMap<String, String>>,
+    // How `fdllm index` cuts files into chunks, per language: "lines", "tokens", "functions" or
+    // "sections", e.g. { default = "functions", json = "tokens" }; markdown defaults to "sections"
+    chunking: Option<chunk::Chunking>,
+    // Languages for files the built-in table gets wrong or misses, by file name or suffix, e.g.
+    // { ".inc" = "php", "Justfile" = "make" }; sets fence tags and language-aware transforms
+    language_overrides: Option<BTreeMap<String, String>>,
+    // Profiles this one is layered on: `extends` first, then `include_profiles` in order, its own
+    // settings last; lists are combined, tables merged and other settings replaced
+    extends: Option<String>,
+    include_profiles: Option<Vec<String>>,
+    // Team policy: CLI flags may not add files or weaken redaction for this profile
+    locked: Option<bool>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct Project {
+    path: String,
+    tree_level: Option<u32>,
+    // "native" (default) or "eza"
+    tree: Option<tree::TreeRenderer>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct Database {
+    // Name of the environment variable holding the connection string
+    url_env: Option<String>,
+    // postgres, mysql or sqlite; inferred from the connection string when omitted
+    engine: Option<String>,
+}
+
+fn env_path(name: &str) -> Option<PathBuf> {
+    env::var_os(name).filter(|value| !value.is_empty()).map(PathBuf::from)
+}
+
+// $HOME on Unix
+#[cfg(not(windows))]
+fn home_dir() -> Option<PathBuf> {
+    env_path("HOME")
+}
+
+// %USERPROFILE% on Windows; HOME only counts when a shell like Git Bash set it
+#[cfg(windows)]
+fn home_dir() -> Option<PathBuf> {
+    env_path("USERPROFILE")
+        .or_else(|| {
+            let drive = env::var_os("HOMEDRIVE")?;
+            let path = env::var_os("HOMEPATH")?;
+            Some(PathBuf::from(drive).join(path))
+        })
+        .or_else(|| env_path("HOME"))
+}
+
+// Where the config lives by the platform's conventions, without the `fdllm` directory itself
+#[cfg(windows)]
+fn platform_config_dir() -> Option<PathBuf> {
+    env_path("APPDATA")
+}
+
+#[cfg(target_os = "macos")]
+fn platform_config_dir() -> Option<PathBuf> {
+    home_dir().map(|home| home.join("Library").join("Application Support"))
+}
+
+// $XDG_CONFIG_HOME, or ~/.config; a relative XDG_CONFIG_HOME is ignored, as the spec says
+#[cfg(not(any(windows, target_os = "macos")))]
+fn platform_config_dir() -> Option<PathBuf> {
+    env_path("XDG_CONFIG_HOME")
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| home_dir().map(|home| home.join(".config")))
+}
+
+fn expand_tilde(path: &str) -> PathBuf {
+    // Only a leading `~` means home; `~` elsewhere in a path is an ordinary character
+    let rest = if path == "~" {
+        Some("")
+    } else {
+        path.strip_prefix("~/").or_else(|| path.strip_prefix("~\\"))
+    };
+    match (rest, home_dir()) {
+        (Some(rest), Some(home_dir)) => home_dir.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+// Set by --portable: config and state live next to the executable
+static PORTABLE_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+const PORTABLE_DIR_NAME: &str = "fdllm-data";
+
+// Resolved once per run, since finding it may migrate the legacy directory
+static APP_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+// --portable, then $FDLLM_HOME, then the platform's config directory: %APPDATA%\fdllm,
+// ~/Library/Application Support/fdllm or ~/.config/fdllm
+fn get_app_dir() -> Result<PathBuf, Error> {
+    if let Some(dir) = PORTABLE_DIR.get() {
+        return Ok(dir.clone());
+    }
+    if let Some(dir) = env::var_os("FDLLM_HOME").filter(|dir| !dir.is_empty()) {
+        return Ok(expand_tilde(&dir.to_string_lossy()));
+    }
+    if let Some(dir) = APP_DIR.get() {
+        return Ok(dir.clone());
+    }
+    let config_dir = platform_config_dir().ok_or_else(|| {
+        Error::Failure("Could not determine the config directory; set FDLLM_HOME or use --portable".to_string())
+    })?;
+    Ok(APP_DIR.get_or_init(|| migrate_legacy_dir(config_dir.join("fdllm"))).clone())
+}
+
+// Older versions kept everything in ~/fdllm. It is moved to `dir` as a whole; if that fails
+// (another filesystem, say) only config.toml is copied and the old directory is left alone.
+// Returns the directory to use, the old one when even the copy failed.
+fn migrate_legacy_dir(dir: PathBuf) -> PathBuf {
+    let Some(legacy) = home_dir().map(|home| home.join("fdllm")) else {
+        return dir;
+    };
+    let legacy_config = legacy.join("config.toml");
+    if legacy == dir || !legacy_config.is_file() || dir.join("config.toml").exists() {
+        return dir;
+    }
+    if let Some(parent) = dir.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if !dir.exists() && fs::rename(&legacy, &dir).is_ok() {
+        info!("Moved {} to {}, the new config location", legacy.display(), dir.display());
+        return dir;
+    }
+    match fs::create_dir_all(&dir).and_then(|_| fs::copy(&legacy_config, dir.join("config.toml"))) {
+        Ok(_) => {
+            info!(
+                "Copied {} to {}, the new config location; {} can be removed",
+                legacy_config.display(),
+                dir.display(),
+                legacy.display()
+            );
+            dir
+        }
+        Err(err) => {
+            eprintln!(
+                "Failed to move {} to {}: {}; still using {}",
+                legacy_config.display(),
+                dir.display(),
+                err,
+                legacy.display()
+            );
+            legacy
+        }
+    }
+}
+
+fn enable_portable_mode() -> Result<(), Error> {
+    let exe = env::current_exe().and_then(|exe| exe.canonicalize());
+    let dir = match exe.as_ref().ok().and_then(|exe| exe.parent()) {
+        Some(dir) => dir.join(PORTABLE_DIR_NAME),
+        None => return Err(Error::Failure("--portable: could not locate the fdllm executable".to_string())),
+    };
+    let _ = PORTABLE_DIR.set(dir);
+    Ok(())
+}
+
+// Runtime data (collected snippets, caches) lives next to the config, or in the temp dir when
+// there is no config directory; a state directory that can't be created shows up as failed
+// writes to its files
+fn get_state_dir() -> PathBuf {
+    let state_dir = get_app_dir().unwrap_or_else(|_| env::temp_dir().join("fdllm")).join("state");
+    let _ = fs::create_dir_all(&state_dir);
+    state_dir
+}
+
+// Written on first run and by `fdllm init`
+const DEFAULT_CONFIG: &str = r#"# Default configuration (used when no profile is specified)
+files = ["~/Desktop/my_test_file.txt"]
+directories = ["~/example_dir"]
+
+[project]
+path = ""
+tree_level = 3
+
+# Example profile configurations
+[profiles.project1]
+files = ["~/project1/main.rs"]
+directories = ["~/project1/src"]
+
+[profiles.project1.project]
+path = "~/project1"
+tree_level = 2
+
+[profiles.project2]
+files = ["~/project2/app.js"]
+directories = ["~/project2/lib"]
+
+[profiles.project2.project]
+path = "~/project2"
+tree_level = 3
+"#;
+
+fn default_config_path() -> Result<PathBuf, Error> {
+    match CONFIG_PATH.get() {
+        Some(path) => Ok(path.clone()),
+        None => Ok(get_app_dir()?.join("config.toml")),
+    }
+}
+
+fn get_config_path() -> Result<PathBuf, Error> {
+    if let Some(path) = CONFIG_PATH.get() {
+        if !path.is_file() {
+            return Err(Error::Config(format!("Config file not found: {}", path.display())));
+        }
+        verbose!("Config: {}", path.display());
+        return Ok(path.clone());
+    }
+
+    let config_file = default_config_path()?;
+    // A project config is enough on its own; the global one is only created when neither exists
+    if !config_file.exists() && project_config_path().is_none() {
+        fs::create_dir_all(get_app_dir()?)
+            .and_then(|_| fs::write(&config_file, DEFAULT_CONFIG))
+            .map_err(|err| Error::Output(format!("Failed to write {}: {}", config_file.display(), err)))?;
+        info!("Default config.toml created at {}", config_file.display());
+    }
+    verbose!("Config: {}", config_file.display());
+
+    Ok(config_file)
+}
+
+// The config in effect without writing the starter one: defaults when there is none
+fn config_if_any() -> Result<Config, Error> {
+    if CONFIG_PATH.get().is_none() && !default_config_path()?.exists() && project_config_path().is_none() {
+        return Ok(Config::default());
+    }
+    load_config(&get_config_path()?)
+}
+
+// `fdllm init` writes the starter config explicitly, never over an existing one
+fn init_config() -> Result<(), Error> {
+    let config_file = default_config_path()?;
+    if config_file.exists() {
+        return Err(Error::Config(format!(
+            "Config already exists at {}; change it with `fdllm config edit`",
+            config_file.display()
+        )));
+    }
+    config_file
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::write(&config_file, DEFAULT_CONFIG))
+        .map_err(|err| Error::Output(format!("Failed to write {}: {}", config_file.display(), err)))?;
+    info!("Starter config written to {}", config_file.display());
+    Ok(())
+}
+
+// A `.fdllm.toml` (or `fdllm.toml`) in the current directory or a parent, unless --config
+// names the one file to use
+fn project_config_path() -> Option<&'static PathBuf> {
+    static PROJECT_CONFIG: OnceLock<Option<PathBuf>> = OnceLock::new();
+    PROJECT_CONFIG
+        .get_or_init(|| if CONFIG_PATH.get().is_some() { None } else { project_config::discover() })
+        .as_ref()
+}
+
+// The parsed config and its source, so a project config can be merged at the TOML level
+fn read_config(config_path: &Path) -> Result<(Config, toml::Table), Error> {
+    let config_content = fs::read_to_string(config_path)
+        .map_err(|err| Error::Config(format!("Failed to read config file {}: {}", config_path.display(), err)))?;
+    
+    match toml::from_str(&config_content) {
+        Ok(config) => Ok((config, config_content.parse().expect("a valid config is a valid table"))),
+        Err(err) => Err(parse_error(config_path, err)),
+    }
+}
+
+fn parse_error(config_path: &Path, err: toml::de::Error) -> Error {
+    Error::Config(format!("Failed to parse config file: {}\nError: {}", config_path.display(), err))
+}
+
+// Expands `extends` and `include_profiles`; a project config is expanded after the merge, so
+// its profiles can build on global ones
+fn inherit_profiles(mut table: toml::Table, config_path: &Path) -> Result<Config, Error> {
+    inherit::resolve(&mut table).map_err(|err| Error::Config(format!("Invalid config {}: {}", config_path.display(), err)))?;
+    let config: Config = toml::Value::Table(table)
+        .try_into()
+        .map_err(|err| parse_error(config_path, err))?;
+    if !QUIET.load(Ordering::Relaxed) {
+        for name in config.profile_names().into_iter().filter(|name| cli::is_subcommand(name)) {
+            eprintln!(
+                "Warning: profile '{}' has the name of the `fdllm {}` subcommand; bundle it with `fdllm copy {}`",
+                name, name, name
+            );
+        }
+    }
+    Ok(config)
+}
+
+fn load_config(config_path: &Path) -> Result<Config, Error> {
+    let Some(project_path) = project_config_path() else {
+        return inherit_profiles(read_config(config_path)?.1, config_path);
+    };
+    verbose!("Project config: {}", project_path.display());
+    let (project, mut local) = read_config(project_path)?;
+    if let Some(base) = project_path.parent() {
+        project_config::rebase(&mut local, base);
+    }
+    if project.inherit_global == Some(false) || !config_path.exists() {
+        return inherit_profiles(local, project_path);
+    }
+    inherit_profiles(project_config::merge(read_config(config_path)?.1, local), project_path)
+}
+
+fn config_command(command: cli::ConfigCommand) -> Result<(), Error> {
+    match command {
+        // A JSON Schema for config.toml (for taplo, VS Code, Neovim)
+        cli::ConfigCommand::Schema => {
+            let schema = schemars::schema_for!(Config);
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&schema).expect("Failed to serialize config schema")
+            );
+        }
+        cli::ConfigCommand::Edit => {
+            let config_path = get_config_path()?;
+            match open::editor_command(&config_path, None).status() {
+                Ok(status) if status.success() => {}
+                Ok(status) => return Err(Error::Failure(format!("Editor exited with {}", status))),
+                Err(err) => return Err(Error::Failure(format!("Failed to start the editor: {}", err))),
+            }
+            // Catch a typo now rather than on the next bundle
+            let content = fs::read_to_string(&config_path).unwrap_or_default();
+            if let Err(err) = toml::from_str::<Config>(&content) {
+                return Err(Error::Config(format!("{} no longer parses:\n{}", config_path.display(), err)));
+            }
+            let mut table: toml::Table = content.parse().expect("a valid config is a valid table");
+            inherit::resolve(&mut table).map_err(|err| Error::Config(format!("Invalid config {}: {}", config_path.display(), err)))?;
+        }
+    }
+    Ok(())
+}
+
+fn collect_files_from_directory(dir_path: &Path) -> Vec<PathBuf> {
+    collect_files_excluding(dir_path, &[])
+}
+
+// File extensions or names to exclude
+const EXCLUDED_NAMES: [&str; 4] = [".DS_Store", ".git", ".gitignore", "target"];
+
+// Add your needed extensions
+const VALID_EXTENSIONS: [&str; 22] = [
+    ".rs", ".toml", ".json", ".yaml", ".yml", ".md", ".txt", 
+    ".c", ".h", ".cpp", ".hpp", ".js", ".ts", ".py", ".go", ".sh",
+    ".csv", ".log", ".lua", ".nu", ".graphql", ".gql"
+];
+
+// The extensions a directory walk keeps: VALID_EXTENSIONS plus `include_extensions`, minus
+// `exclude_extensions` (both accept "sql" or ".sql")
+struct ExtensionFilter {
+    extensions: Vec<String>,
+}
+
+fn normalize_extension(extension: &str) -> String {
+    format!(".{}", extension.trim().trim_start_matches('.'))
+}
+
+impl ExtensionFilter {
+    fn new(include: Option<&Vec<String>>, exclude: Option<&Vec<String>>) -> ExtensionFilter {
+        let excluded: Vec<String> = exclude.into_iter().flatten().map(|extension| normalize_extension(extension)).collect();
+        let mut extensions: Vec<String> = VALID_EXTENSIONS.iter().map(|extension| extension.to_string()).collect();
+        for extension in include.into_iter().flatten() {
+            let extension = normalize_extension(extension);
+            if !extensions.contains(&extension) {
+                extensions.push(extension);
+            }
+        }
+        extensions.retain(|extension| !excluded.contains(extension));
+        ExtensionFilter { extensions }
+    }
+
+    fn merge(&mut self, other: ExtensionFilter) {
+        for extension in other.extensions {
+            if !self.extensions.contains(&extension) {
+                self.extensions.push(extension);
+            }
+        }
+    }
+
+    fn allows(&self, path: &Path) -> bool {
+        path.extension()
+            .is_some_and(|extension| self.extensions.contains(&format!(".{}", extension.to_string_lossy())))
+    }
+}
+
+fn collect_files_excluding(dir_path: &Path, extra_excluded: &[&str]) -> Vec<PathBuf> {
+    let extensions = ExtensionFilter::new(None, None);
+    walk_directory(dir_path, extra_excluded, &[], !NO_GITIGNORE.load(Ordering::Relaxed), &extensions)
+}
+
+// A profile directory, minus its `exclude_paths` and limited to its extensions
+fn collect_profile_directory(dir_path: &Path, profile: &ActiveProfile) -> Vec<PathBuf> {
+    walk_directory(dir_path, &[], &profile.exclude_paths, profile.gitignore, &profile.extensions)
+}
+
+// `vendor/**` excludes the `vendor` directory itself, so it isn't walked at all
+fn path_excluded(relative: &Path, is_dir: bool, patterns: &[glob::Pattern]) -> bool {
+    patterns.iter().any(|pattern| {
+        pattern.matches_path(relative)
+            || (is_dir
+                && pattern
+                    .as_str()
+                    .strip_suffix("/**")
+                    .and_then(|prefix| glob::Pattern::new(prefix).ok())
+                    .is_some_and(|prefix| prefix.matches_path(relative)))
+    })
+}
+
+// Directories the walk could not list; the bundle gets a placeholder for each
+static DENIED_DIRECTORIES: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+fn take_denied_directories() -> Vec<PathBuf> {
+    std::mem::take(&mut *DENIED_DIRECTORIES.lock().unwrap_or_else(|err| err.into_inner()))
+}
+
+// Stands in for a file or directory that couldn't be read, so the gap is visible to the reader
+const PERMISSION_DENIED_PLACEHOLDER: &str = "[fdllm: skipped: permission denied]";
+
+fn walk_directory(
+    dir_path: &Path,
+    extra_excluded: &[&str],
+    exclude_paths: &[glob::Pattern],
+    gitignore: bool,
+    extensions: &ExtensionFilter,
+) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    if special::is_pseudo(dir_path) {
+        eprintln!("Skipping {}: pseudo filesystem, its files can block on read", dir_path.display());
+        return files;
+    }
+    
+    let base = dir_path.to_path_buf();
+    let extra_excluded: Vec<String> = extra_excluded.iter().map(|name| name.to_string()).collect();
+    let exclude_paths = exclude_paths.to_vec();
+    let mut walker = WalkBuilder::new(dir_path);
+    // .gitignore, .ignore and global git excludes apply even outside a git checkout; dotfiles
+    // are kept (EXCLUDED_NAMES handles the usual suspects) and symlinks are followed as before
+    walker
+        .standard_filters(gitignore)
+        .hidden(false)
+        .require_git(false)
+        .follow_links(true)
+        .same_file_system(!tuning::follow_mounts())
+        // Sorted by normalized name so every platform walks in the same order
+        .sort_by_file_name(|a, b| unicode_paths::nfc(&a.to_string_lossy()).cmp(&unicode_paths::nfc(&b.to_string_lossy())))
+        .filter_entry(move |entry| {
+            if entry.depth() == 0 {
+                return true;
+            }
+            // Skip excluded files/directories
+            let file_name = entry.file_name().to_string_lossy();
+            if EXCLUDED_NAMES.iter().any(|&excluded| file_name.contains(excluded))
+                || extra_excluded.iter().any(|excluded| *excluded == file_name)
+            {
+                return false;
+            }
+            let relative = entry.path().strip_prefix(&base).unwrap_or(entry.path());
+            !path_excluded(relative, entry.path().is_dir(), &exclude_paths)
+        });
+    
+    for result in walker.build() {
+        if deadline::expired() {
+            deadline::skip(format!("rest of {}", dir_path.display()));
+            break;
+        }
+        let entry = match result {
+            Ok(entry) => entry,
+            Err(err) => {
+                if err.io_error().map(std::io::Error::kind) == Some(std::io::ErrorKind::PermissionDenied) {
+                    let denied = denied_path(&err).unwrap_or(dir_path).to_path_buf();
+                    eprintln!("Permission denied: {}", denied.display());
+                    DENIED_DIRECTORIES.lock().unwrap_or_else(|err| err.into_inner()).push(denied);
+                }
+                continue;
+            }
+        };
+        let path = entry.path();
+        if let Some(kind) = special::kind(path) {
+            eprintln!("Skipping {}: {}", kind, path.display());
+            continue;
+        }
+        if !path.is_file() {
+            continue;
+        }
+        // Check if the file has a valid extension; a `language_overrides` entry (`Justfile`)
+        // counts as one
+        if lang::is_overridden(path) {
+            files.push(path.to_path_buf());
+        } else if path.extension().is_some() {
+            if extensions.allows(path) {
+                files.push(path.to_path_buf());
+            } else {
+                // Debug print to help understand what's being filtered
+                info!("Skipping file with unsupported extension: {}", path.display());
+            }
+        }
+    }
+    
+    // Debug print to help understand what files were found
+    info!("Found {} files in directory: {}", files.len(), dir_path.display());
+    
+    files
+}
+
+// The path an `ignore` walk error is about, if it carries one
+fn denied_path(err: &ignore::Error) -> Option<&Path> {
+    match err {
+        ignore::Error::WithPath { path, .. } => Some(path),
+        ignore::Error::WithDepth { err, .. } | ignore::Error::WithLineNumber { err, .. } => denied_path(err),
+        _ => None,
+    }
+}
+
+// A profile (or the top-level default configuration) with config-wide fallbacks applied
+struct ActiveProfile<'a> {
+    files: Vec<String>,
+    directories: Vec<String>,
+    project: Option<&'a Project>,
+    // Trees of the other profiles in a multi-profile run
+    more_projects: Vec<&'a Project>,
+    database: Option<&'a Database>,
+    // `gh:owner/repo#123` entries of `files`, fetched as sections rather than read
+    issues: Vec<String>,
+    git_diff: Option<&'a str>,
+    git_diff_context: Option<u32>,
+    condense_schemas: bool,
+    use_cache: bool,
+    strip_imports: Option<&'a imports::StripImports>,
+    redaction: redact::Redaction,
+    redaction_rules: Vec<redact::CustomRule>,
+    scan: Option<scan::Scan>,
+    elide_blobs: Option<blobs::ElideBlobs>,
+    max_line_length: Option<usize>,
+    long_lines: long_lines::LongLines,
+    max_tokens_per_file: Option<&'a file_budget::MaxTokensPerFile>,
+    max_file_size: Option<u64>,
+    large_files: file_size::LargeFiles,
+    max_memory: Option<u64>,
+    max_tokens: Option<usize>,
+    budget_strategy: budget::BudgetStrategy,
+    format: format::Format,
+    output_path: Option<&'a str>,
+    clipboard: bool,
+    clipboard_fallback: output::ClipboardFallback,
+    clipboard_backend: clipboard::ClipboardBackend,
+    provenance: bool,
+    signing_key: Option<&'a str>,
+    roots: Vec<roots::Root>,
+    rewrite_paths: &'a [roots::PathRewrite],
+    exclude_paths: Vec<glob::Pattern>,
+    outline_paths: Vec<glob::Pattern>,
+    truncate_paths: Vec<(glob::Pattern, usize)>,
+    extensions: ExtensionFilter,
+    include_docs: bool,
+    tuning: tuning::Tuning,
+    gitignore: bool,
+    prune_report: usize,
+    header: Option<&'a str>,
+    footer: Option<&'a str>,
+    template: Option<&'a str>,
+    // The config's values with the profile's on top
+    template_vars: BTreeMap<String, String>,
+    chunking: Option<&'a chunk::Chunking>,
+    // The config's overrides with the profile's on top
+    language_overrides: BTreeMap<String, String>,
+    // Profiles the active one inherits from, for --explain
+    layers: Vec<&'a str>,
+    locked: bool,
+}
+
+fn compile_paths<'p>(key: &str, patterns: impl IntoIterator<Item = &'p String>) -> Result<Vec<glob::Pattern>, Error> {
+    patterns
+        .into_iter()
+        .map(|pattern| {
+            glob::Pattern::new(pattern).map_err(|err| Error::Config(format!("Invalid {} pattern '{}': {}", key, pattern, err)))
+        })
+        .collect()
+}
+
+fn compile_excludes(patterns: Option<&Vec<String>>) -> Result<Vec<glob::Pattern>, Error> {
+    compile_paths("exclude_paths", patterns.into_iter().flatten())
+}
+
+fn profile_names(profiles: &HashMap<String, Profile>) -> Vec<&str> {
+    let mut names: Vec<&str> = profiles.keys().map(String::as_str).collect();
+    names.sort();
+    names
+}
+
+// An unknown profile name, with someone at the terminal: offer the closest names (or all of
+// them) to pick from instead of failing; closing the picker fails as the unknown name would
+fn resolve_profile_name(config: &Config, profile_name: Option<String>) -> Result<Option<String>, Error> {
+    let (Some(name), Some(profiles)) = (&profile_name, &config.profiles) else {
+        return Ok(profile_name);
+    };
+    if profiles.contains_key(name) || profiles.is_empty() || NO_SUGGEST.load(Ordering::Relaxed) || porcelain() {
+        return Ok(profile_name);
+    }
+    if !suggest::interactive() {
+        return Ok(profile_name);
+    }
+
+    let names = profile_names(profiles);
+    let matches = suggest::close_matches(name, &names);
+    let choices = if matches.is_empty() { names } else { matches };
+    match suggest::pick(&format!("Profile '{}' not found in config; pick one:", name), &choices) {
+        Some(choice) => Ok(Some(choice)),
+        None => Err(Error::UnknownProfile {
+            name: name.clone(),
+            suggestions: Vec::new(),
+        }),
+    }
+}
+
+fn size_setting(key: &str, setting: Option<&file_size::FileSize>) -> Result<Option<u64>, Error> {
+    let Some(setting) = setting else {
+        return Ok(None);
+    };
+    match setting.bytes() {
+        Some(bytes) => Ok(Some(bytes)),
+        None => Err(Error::Config(format!(
+            "Invalid {}: expected a number of bytes or a size such as \"2MB\"",
+            key
+        ))),
+    }
+}
+
+fn redaction_rules(sources: &[Option<&Vec<redact::CustomRule>>]) -> Result<Vec<redact::CustomRule>, Error> {
+    let rules: Vec<redact::CustomRule> = sources.iter().flatten().flat_map(|rules| rules.iter().cloned()).collect();
+    redact::compile_custom(&rules).map_err(|err| Error::Config(format!("Invalid {}", err)))?;
+    Ok(rules)
+}
+
+fn active_profile<'a>(config: &'a Config, profile_name: Option<&str>, options: &'a Options) -> Result<ActiveProfile<'a>, Error> {
+    // Use the specified profile if it exists
+    if let Some(profile_name) = profile_name {
+        let Some(profiles) = &config.profiles else {
+            return Err(Error::Config("No profiles defined in config".to_string()));
+        };
+        let Some(profile) = profiles.get(profile_name) else {
+            let suggestions = match NO_SUGGEST.load(Ordering::Relaxed) {
+                true => Vec::new(),
+                false => suggest::close_matches(profile_name, &profile_names(profiles)),
+            };
+            return Err(Error::UnknownProfile {
+                name: profile_name.to_string(),
+                suggestions: suggestions.into_iter().map(str::to_string).collect(),
+            });
+        };
+        info!("Using profile: {}", profile_name);
+        let (files, issues) = github::split_entries(profile.files.as_ref());
+        Ok(ActiveProfile {
+            files,
+            directories: profile.directories.clone().unwrap_or_default(),
+            project: profile.project.as_ref(),
+            more_projects: Vec::new(),
+            database: profile.database.as_ref(),
+            issues,
+            git_diff: options
+                .git_diff
+                .as_deref()
+                .or(profile.git_diff.as_deref())
+                .or(config.git_diff.as_deref()),
+            git_diff_context: profile.git_diff_context.or(config.git_diff_context),
+            condense_schemas: profile.condense_schemas.or(config.condense_schemas).unwrap_or(false),
+            use_cache: profile.cache.or(config.cache).unwrap_or(false),
+            strip_imports: profile.strip_imports.as_ref().or(config.strip_imports.as_ref()),
+            redaction: profile.redaction.or(config.redaction).unwrap_or(redact::Redaction::Standard),
+            redaction_rules: redaction_rules(&[config.redaction_rules.as_ref(), profile.redaction_rules.as_ref()])?,
+            scan: profile.scan.or(config.scan),
+            elide_blobs: profile.elide_blobs.or(config.elide_blobs),
+            max_line_length: profile.max_line_length.or(config.max_line_length),
+            long_lines: profile
+                .long_lines
+                .or(config.long_lines)
+                .unwrap_or(long_lines::LongLines::Truncate),
+            max_tokens_per_file: profile.max_tokens_per_file.as_ref().or(config.max_tokens_per_file.as_ref()),
+            max_file_size: match options.max_file_size {
+                Some(bytes) => Some(bytes),
+                None => size_setting("max_file_size", profile.max_file_size.as_ref().or(config.max_file_size.as_ref()))?,
+            },
+            large_files: profile.large_files.or(config.large_files).unwrap_or_default(),
+            max_memory: size_setting("max_memory", profile.max_memory.as_ref().or(config.max_memory.as_ref()))?,
+            max_tokens: options.max_tokens.or(profile.max_tokens).or(config.max_tokens),
+            budget_strategy: profile.budget_strategy.or(config.budget_strategy).unwrap_or_default(),
+            format: options.format.or(profile.format).or(config.format).unwrap_or_default(),
+            output_path: profile.output_path.as_deref().or(config.output_path.as_deref()),
+            clipboard: profile.clipboard.or(config.clipboard).unwrap_or(true),
+            clipboard_fallback: profile.clipboard_fallback.or(config.clipboard_fallback).unwrap_or_default(),
+            clipboard_backend: options
+                .clipboard_backend
+                .or(profile.clipboard_backend)
+                .or(config.clipboard_backend)
+                .unwrap_or_default(),
+            provenance: profile.provenance.or(config.provenance).unwrap_or(false),
+            signing_key: profile.signing_key.as_deref().or(config.signing_key.as_deref()),
+            roots: roots::load_roots(profile.roots.as_ref().or(config.roots.as_ref())),
+            rewrite_paths: profile
+                .rewrite_paths
+                .as_deref()
+                .or(config.rewrite_paths.as_deref())
+                .unwrap_or(&[]),
+            exclude_paths: compile_excludes(profile.exclude_paths.as_ref().or(config.exclude_paths.as_ref()))?,
+            outline_paths: compile_paths("outline_paths", profile.outline_paths.iter().flatten())?,
+            truncate_paths: compile_paths("truncate_paths", profile.truncate_paths.iter().flatten().map(|(pattern, _)| pattern))?
+                .into_iter()
+                .zip(profile.truncate_paths.iter().flatten().map(|(_, limit)| *limit))
+                .collect(),
+            extensions: ExtensionFilter::new(
+                profile.include_extensions.as_ref().or(config.include_extensions.as_ref()),
+                profile.exclude_extensions.as_ref().or(config.exclude_extensions.as_ref()),
+            ),
+            include_docs: profile.include_docs.or(config.include_docs).unwrap_or(false),
+            tuning: tuning::Tuning {
+                threads: profile.threads.or(config.threads),
+                io_buffer_size: profile.io_buffer_size.or(config.io_buffer_size),
+                follow_mounts: profile.follow_mounts.or(config.follow_mounts),
+            },
+            gitignore: profile.gitignore.or(config.gitignore).unwrap_or(true) && !options.no_gitignore,
+            prune_report: profile.prune_report.or(config.prune_report).unwrap_or(prune::DEFAULT_TOP),
+            header: profile.header.as_deref().or(config.header.as_deref()),
+            footer: profile.footer.as_deref().or(config.footer.as_deref()),
+            template: profile.template.as_deref().or(config.template.as_deref()),
+            template_vars: config
+                .template_vars
+                .iter()
+                .chain(&profile.template_vars)
+                .flatten()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+            chunking: profile.chunking.as_ref().or(config.chunking.as_ref()),
+            language_overrides: config
+                .language_overrides
+                .iter()
+                .chain(&profile.language_overrides)
+                .flatten()
+                .map(|(key, language)| (key.clone(), language.clone()))
+                .collect(),
+            layers: profile
+                .extends
+                .iter()
+                .chain(profile.include_profiles.iter().flatten())
+                .map(String::as_str)
+                .collect(),
+            locked: profile.locked.unwrap_or(false),
+        })
+    } else {
+        // Use default config (for backward compatibility)
+        info!("Using default configuration");
+        let (files, issues) = github::split_entries(config.files.as_ref());
+        Ok(ActiveProfile {
+            files,
+            directories: config.directories.clone().unwrap_or_default(),
+            project: config.project.as_ref(),
+            more_projects: Vec::new(),
+            database: config.database.as_ref(),
+            issues,
+            git_diff: options.git_diff.as_deref().or(config.git_diff.as_deref()),
+            git_diff_context: config.git_diff_context,
+            condense_schemas: config.condense_schemas.unwrap_or(false),
+            use_cache: config.cache.unwrap_or(false),
+            strip_imports: config.strip_imports.as_ref(),
+            redaction: config.redaction.unwrap_or(redact::Redaction::Standard),
+            redaction_rules: redaction_rules(&[config.redaction_rules.as_ref()])?,
+            scan: config.scan,
+            elide_blobs: config.elide_blobs,
+            max_line_length: config.max_line_length,
+            long_lines: config.long_lines.unwrap_or(long_lines::LongLines::Truncate),
+            max_tokens_per_file: config.max_tokens_per_file.as_ref(),
+            max_file_size: match options.max_file_size {
+                Some(bytes) => Some(bytes),
+                None => size_setting("max_file_size", config.max_file_size.as_ref())?,
+            },
+            large_files: config.large_files.unwrap_or_default(),
+            max_memory: size_setting("max_memory", config.max_memory.as_ref())?,
+            max_tokens: options.max_tokens.or(config.max_tokens),
+            budget_strategy: config.budget_strategy.unwrap_or_default(),
+            format: options.format.or(config.format).unwrap_or_default(),
+            output_path: config.output_path.as_deref(),
+            clipboard: config.clipboard.unwrap_or(true),
+            clipboard_fallback: config.clipboard_fallback.unwrap_or_default(),
+            clipboard_backend: options.clipboard_backend.or(config.clipboard_backend).unwrap_or_default(),
+            provenance: config.provenance.unwrap_or(false),
+            signing_key: config.signing_key.as_deref(),
+            roots: roots::load_roots(config.roots.as_ref()),
+            rewrite_paths: config.rewrite_paths.as_deref().unwrap_or(&[]),
+            exclude_paths: compile_excludes(config.exclude_paths.as_ref())?,
+            outline_paths: Vec::new(),
+            truncate_paths: Vec::new(),
+            extensions: ExtensionFilter::new(config.include_extensions.as_ref(), config.exclude_extensions.as_ref()),
+            include_docs: config.include_docs.unwrap_or(false),
+            tuning: tuning::Tuning {
+                threads: config.threads,
+                io_buffer_size: config.io_buffer_size,
+                follow_mounts: config.follow_mounts,
+            },
+            gitignore: config.gitignore.unwrap_or(true) && !options.no_gitignore,
+            prune_report: config.prune_report.unwrap_or(prune::DEFAULT_TOP),
+            header: config.header.as_deref(),
+            footer: config.footer.as_deref(),
+            template: config.template.as_deref(),
+            template_vars: config.template_vars.clone().unwrap_or_default(),
+            chunking: config.chunking.as_ref(),
+            language_overrides: config.language_overrides.clone().unwrap_or_default(),
+            layers: Vec::new(),
+            locked: false,
+        })
+    }
+}
+
+// `fdllm copy frontend backend`: the first profile's settings, with the others' files,
+// directories, roots, excludes, extensions and project trees added; redaction, the secret scan
+// and locking follow the strictest of them
+fn merge_profiles<'a>(profile: &mut ActiveProfile<'a>, others: Vec<ActiveProfile<'a>>) {
+    for other in others {
+        for file in other.files {
+            if !profile.files.contains(&file) {
+                profile.files.push(file);
+            }
+        }
+        for dir in other.directories {
+            if !profile.directories.contains(&dir) {
+                profile.directories.push(dir);
+            }
+        }
+        for issue in other.issues {
+            if !profile.issues.contains(&issue) {
+                profile.issues.push(issue);
+            }
+        }
+        for root in other.roots {
+            if !profile.roots.iter().any(|existing| existing.label == root.label) {
+                profile.roots.push(root);
+            }
+        }
+        for pattern in other.exclude_paths {
+            if !profile.exclude_paths.contains(&pattern) {
+                profile.exclude_paths.push(pattern);
+            }
+        }
+        for pattern in other.outline_paths {
+            if !profile.outline_paths.contains(&pattern) {
+                profile.outline_paths.push(pattern);
+            }
+        }
+        for (pattern, limit) in other.truncate_paths {
+            if !profile.truncate_paths.iter().any(|(existing, _)| *existing == pattern) {
+                profile.truncate_paths.push((pattern, limit));
+            }
+        }
+        profile.extensions.merge(other.extensions);
+        for project in other.project.into_iter().chain(other.more_projects) {
+            let listed = profile
+                .project
+                .iter()
+                .chain(&profile.more_projects)
+                .any(|existing| existing.path == project.path);
+            if profile.project.is_none() {
+                profile.project = Some(project);
+            } else if !listed {
+                profile.more_projects.push(project);
+            }
+        }
+        for rule in other.redaction_rules {
+            if !profile.redaction_rules.iter().any(|existing| existing.name == rule.name) {
+                profile.redaction_rules.push(rule);
+            }
+        }
+        profile.redaction = profile.redaction.max(other.redaction);
+        profile.scan = profile.scan.max(other.scan);
+        profile.locked |= other.locked;
+        profile.include_docs |= other.include_docs;
+        profile.gitignore &= other.gitignore;
+        profile.language_overrides.extend(other.language_overrides);
+        // The first profile's header, footer, template and values win
+        profile.header = profile.header.or(other.header);
+        profile.footer = profile.footer.or(other.footer);
+        profile.template = profile.template.or(other.template);
+        for (key, value) in other.template_vars {
+            profile.template_vars.entry(key).or_insert(value);
+        }
+    }
+}
+
+// Each term's files added to or taken from the list in turn, told apart by canonical path
+fn composed_files(config: &Config, terms: &[compose::Term], options: &Options) -> Result<Vec<String>, Error> {
+    let mut files: Vec<(String, PathBuf)> = Vec::new();
+    for term in terms {
+        let profile = active_profile(config, Some(&term.profile), options)?;
+        for entry in profile_files(&profile) {
+            let path = match git::parse_revision_entry(&entry) {
+                Some((_, path)) => PathBuf::from(path),
+                None => roots::resolve(&entry, &profile.roots),
+            };
+            if term.subset.as_deref().is_some_and(|subset| !compose::in_subset(&path, subset)) {
+                continue;
+            }
+            let key = match git::parse_revision_entry(&entry) {
+                Some(_) => PathBuf::from(&entry),
+                None => fs::canonicalize(&path).unwrap_or(path),
+            };
+            let listed = files.iter().position(|(_, existing)| *existing == key);
+            match (term.exclude, listed) {
+                (false, None) => files.push((entry, key)),
+                (true, Some(index)) => {
+                    files.remove(index);
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(files.into_iter().map(|(entry, _)| entry).collect())
+}
+
+// A `files` or `directories` entry such as `docs/*.md` or `~/project/src/**/*.rs`; a path
+// that exists as written is taken literally even if it contains `[`
+fn is_glob(entry: &str, roots_list: &[roots::Root]) -> bool {
+    entry.contains(['*', '?', '[']) && !roots::resolve(entry, roots_list).exists()
+}
+
+fn expand_glob(entry: &str, roots_list: &[roots::Root]) -> Vec<PathBuf> {
+    let pattern = roots::resolve(entry, roots_list).to_string_lossy().to_string();
+    match glob::glob(&pattern) {
+        Ok(paths) => paths.flatten().collect(),
+        Err(err) => {
+            eprintln!("Invalid pattern '{}': {}", entry, err);
+            Vec::new()
+        }
+    }
+}
+
+// Configured files with patterns expanded in place; revision entries are kept as written
+fn profile_file_entries(profile: &ActiveProfile) -> Vec<String> {
+    let mut files = Vec::new();
+    for entry in &profile.files {
+        if git::parse_revision_entry(entry).is_some() || !is_glob(entry, &profile.roots) {
+            files.push(entry.clone());
+            continue;
+        }
+        let matched: Vec<PathBuf> = expand_glob(entry, &profile.roots)
+            .into_iter()
+            .filter(|path| path.is_file())
+            .collect();
+        if matched.is_empty() && !QUIET.load(Ordering::Relaxed) {
+            eprintln!("Pattern matched no files: {}", entry);
+        }
+        files.extend(matched.iter().map(|path| path.to_string_lossy().to_string()));
+    }
+    files
+}
+
+// Configured directories, resolved and with patterns expanded
+fn profile_directories(profile: &ActiveProfile) -> Vec<PathBuf> {
+    let mut directories = Vec::new();
+    for dir in &profile.directories {
+        if is_glob(dir, &profile.roots) {
+            let matched: Vec<PathBuf> = expand_glob(dir, &profile.roots)
+                .into_iter()
+                .filter(|path| path.is_dir())
+                .collect();
+            if matched.is_empty() && !QUIET.load(Ordering::Relaxed) {
+                eprintln!("Pattern matched no directories: {}", dir);
+            }
+            directories.extend(matched);
+            continue;
+        }
+        let dir_path = roots::resolve(dir, &profile.roots);
+        if dir_path.exists() && dir_path.is_dir() {
+            directories.push(dir_path);
+        } else if !QUIET.load(Ordering::Relaxed) {
+            eprintln!("Directory not found or not a directory: {}", dir_path.display());
+        }
+    }
+    directories
+}
+
+fn profile_files(profile: &ActiveProfile) -> Vec<String> {
+    profile_files_in(profile, &profile_directories(profile))
+}
+
+// Configured files followed by everything collected from the configured directories
+fn profile_files_in(profile: &ActiveProfile, directories: &[PathBuf]) -> Vec<String> {
+    let mut files = profile_file_entries(profile);
+    
+    // Collect files from directories
+    for dir_path in directories {
+        let files_in_dir = collect_profile_directory(dir_path, profile);
+        for file in files_in_dir {
+            files.push(file.to_string_lossy().to_string());
+        }
+    }
+    
+    let docs = profile_docs(profile);
+    if !docs.is_empty() {
+        // A doc that is also listed or collected moves to the front rather than appearing twice
+        let canonical = |path: &Path| fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        let doc_paths: Vec<PathBuf> = docs.iter().map(|doc| canonical(Path::new(doc))).collect();
+        files.retain(|file| !doc_paths.contains(&canonical(Path::new(file))));
+        files.splice(0..0, docs);
+    }
+    
+    files
+}
+
+// `include_docs = true`: the project docs, in the order they lead the bundle
+fn profile_docs(profile: &ActiveProfile) -> Vec<String> {
+    if !profile.include_docs {
+        return Vec::new();
+    }
+    project_docs::find(&project_root(profile))
+        .iter()
+        .map(|doc| doc.to_string_lossy().to_string())
+        .collect()
+}
+
+// The project tree's root, else the current directory: where `include_docs` and provenance look
+fn project_root(profile: &ActiveProfile) -> PathBuf {
+    profile
+        .project
+        .map(|project| expand_tilde(&project.path))
+        .filter(|path| path.is_dir())
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+fn on_off(enabled: bool) -> &'static str {
+    if enabled {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+// `--explain`: everything that decides what ends up in the bundle, printed before the run
+fn explain_plan(
+    config_path: &Path,
+    profile_name: Option<&str>,
+    profile: &ActiveProfile,
+    output_path: Option<&Path>,
+    diff_output: bool,
+) {
+    info!("Plan:");
+    info!("  config file:  {}", config_path.display());
+    if let Some(project_path) = project_config_path() {
+        info!("  local config: {}", project_path.display());
+    }
+    match profile_name {
+        Some(name) => info!("  profile:      {} (top-level settings as fallback)", name),
+        None => info!("  profile:      top-level configuration"),
+    }
+    if !profile.layers.is_empty() {
+        info!("  inherits:     {}", profile.layers.join(", "));
+    }
+    info!("  locked:       {}", if profile.locked { "yes" } else { "no" });
+
+    info!("  files:        {}", profile.files.len());
+    for file in &profile.files {
+        info!("    {}", file);
+    }
+    info!("  directories:  {}", profile.directories.len());
+    for dir in &profile.directories {
+        info!("    {} -> {}", dir, roots::resolve(dir, &profile.roots).display());
+    }
+    for root in &profile.roots {
+        info!("  root:         {} = {}", root.label, root.path.display());
+    }
+    let explain_tree = |project: &Project| {
+        info!(
+            "  project tree: {} (level {}, {})",
+            expand_tilde(&project.path).display(),
+            project.tree_level.map(|level| level.to_string()).unwrap_or_else(|| "unlimited".to_string()),
+            match project.tree.unwrap_or_default() {
+                _ if deterministic() => "built-in, --deterministic",
+                tree::TreeRenderer::Native => "built-in",
+                tree::TreeRenderer::Eza => "eza, built-in fallback",
+            }
+        )
+    };
+    match profile.project {
+        Some(project) => {
+            explain_tree(project);
+            profile.more_projects.iter().for_each(|project| explain_tree(project));
+        }
+        None if !profile.roots.is_empty() => {
+            info!("  project tree: one per root (level {})", DEFAULT_ROOT_TREE_LEVEL)
+        }
+        None => info!("  project tree: none"),
+    }
+    if let Some(selection) = GIT_SELECTION.get() {
+        let scope = if profile_name.is_some() { "the profile's files kept if among them" } else { "bundled as the file list" };
+        info!("  git:          {}, {}", selection.describe(), scope);
+    }
+    if CONFLICTS.load(Ordering::Relaxed) {
+        let scope = if profile_name.is_some() { "the profile's files kept if among them" } else { "bundled as the file list" };
+        info!("  conflicts:    conflicted files with their base, ours and theirs versions, {}", scope);
+    }
+    if profile.include_docs {
+        info!("  docs:         project docs first, from {}", project_root(profile).display());
+    }
+    if let Some(against) = profile.git_diff {
+        match profile.git_diff_context {
+            Some(lines) => info!("  git diff:     {} ({} context lines)", against, lines),
+            None => info!("  git diff:     {}", against),
+        }
+    }
+    if let Some(database) = profile.database {
+        info!(
+            "  database:     ${} ({})",
+            database.url_env.as_deref().unwrap_or("DATABASE_URL"),
+            database.engine.as_deref().unwrap_or("engine from URL")
+        );
+    }
+    if !profile.issues.is_empty() {
+        info!("  github:       {} (fetched with gh)", profile.issues.join(", "));
+    }
+
+    info!("  filters:      skip names containing {}", EXCLUDED_NAMES.join(", "));
+    if profile.gitignore {
+        info!("                skip paths matched by .gitignore, .ignore and global git excludes");
+    }
+    info!("                keep extensions {}", profile.extensions.extensions.join(" "));
+    if !profile.exclude_paths.is_empty() {
+        let patterns: Vec<&str> = profile.exclude_paths.iter().map(|pattern| pattern.as_str()).collect();
+        info!("                exclude_paths {}", patterns.join(" "));
+    }
+    info!(
+        "  io:           {} reader thread(s), {} byte buffer, {}",
+        tuning::threads(),
+        tuning::io_buffer_size(),
+        if tuning::follow_mounts() { "crossing mount points" } else { "staying on each directory's filesystem" }
+    );
+    info!("  ordering:     files as listed, then each directory sorted by name");
+
+    info!("  transforms:");
+    info!("    docs_only:        {}", on_off(DOCS_ONLY.load(Ordering::Relaxed)));
+    info!("    condense_schemas: {}", on_off(profile.condense_schemas));
+    let strip_imports = match profile.strip_imports {
+        Some(imports::StripImports::All(enabled)) => on_off(*enabled).to_string(),
+        Some(imports::StripImports::Languages(languages)) => languages.join(", "),
+        None => "off".to_string(),
+    };
+    info!("    strip_imports:    {}", strip_imports);
+    match profile.redaction_rules.len() {
+        0 => info!("    redaction:        {}", profile.redaction.name()),
+        rules => info!("    redaction:        {} and {} custom rule(s)", profile.redaction.name(), rules),
+    }
+    match profile.scan {
+        Some(scan::Scan::Block) if ALLOW_SECRETS.load(Ordering::Relaxed) => {
+            info!("    scan:             block, overridden by --allow-secrets")
+        }
+        Some(scan) => info!("    scan:             {}", scan.name()),
+        None => info!("    scan:             off"),
+    }
+    match profile.elide_blobs.and_then(blobs::ElideBlobs::min_length) {
+        Some(min_length) => info!("    elide_blobs:      base64/hex runs of {}+ characters", min_length),
+        None => info!("    elide_blobs:      off"),
+    }
+    match profile.max_line_length {
+        Some(max) => info!(
+            "    max_line_length:  {} ({})",
+            max,
+            match profile.long_lines {
+                long_lines::LongLines::Truncate => "truncate",
+                long_lines::LongLines::Wrap => "wrap",
+            }
+        ),
+        None => info!("    max_line_length:  off"),
+    }
+    let max_tokens_per_file = match profile.max_tokens_per_file {
+        None | Some(file_budget::MaxTokensPerFile::Defaults(false)) => "off".to_string(),
+        Some(file_budget::MaxTokensPerFile::Defaults(true)) => "per-language defaults".to_string(),
+        Some(file_budget::MaxTokensPerFile::All(limit)) => limit.to_string(),
+        Some(file_budget::MaxTokensPerFile::Languages(limits)) => {
+            let limits: Vec<String> = limits.iter().map(|(language, limit)| format!("{}={}", language, limit)).collect();
+            if limits.iter().any(|limit| limit.starts_with("default=")) {
+                limits.join(" ")
+            } else {
+                format!("{} (per-language defaults otherwise)", limits.join(" "))
+            }
+        }
+    };
+    info!("    max_tokens_per_file: {}", max_tokens_per_file);
+    if !profile.outline_paths.is_empty() {
+        let patterns: Vec<&str> = profile.outline_paths.iter().map(|pattern| pattern.as_str()).collect();
+        info!("    outline_paths:    {}", patterns.join(" "));
+    }
+    if !profile.truncate_paths.is_empty() {
+        let patterns: Vec<String> = profile
+            .truncate_paths
+            .iter()
+            .map(|(pattern, limit)| format!("{}={}", pattern.as_str(), limit))
+            .collect();
+        info!("    truncate_paths:   {}", patterns.join(" "));
+    }
+
+    let mut outputs = Vec::new();
+    let stdout = STDOUT_BUNDLE.load(Ordering::Relaxed);
+    let send_keys = SEND_KEYS.get();
+    if profile.clipboard && !NO_CLIPBOARD.load(Ordering::Relaxed) && !stdout && send_keys.is_none() {
+        outputs.push(format!(
+            "clipboard ({}, fallback: {})",
+            profile.clipboard_backend.name(),
+            profile.clipboard_fallback.name()
+        ));
+    }
+    if let Some(path) = output_path {
+        outputs.push(format!("file {}", path.display()));
+    }
+    if stdout {
+        outputs.push("stdout".to_string());
+    }
+    if let Some(send_keys) = send_keys {
+        let what = if send_keys.path { "the bundle's path" } else { "the bundle" };
+        outputs.push(format!("{} ({} pasted)", send_keys.label(), what));
+    }
+    if diff_output {
+        info!("  outputs:      none (--diff-output previews the file)");
+    } else if outputs.is_empty() {
+        info!("  outputs:      none");
+    } else {
+        info!("  outputs:      {}", outputs.join(", "));
+    }
+    info!("  cache:        {}", on_off(profile.use_cache));
+    let provenance = match (profile.provenance, profile.signing_key) {
+        (_, Some(key)) => format!("footer, signed with {}", key),
+        (true, None) => "footer".to_string(),
+        (false, None) => "off".to_string(),
+    };
+    info!("  provenance:   {}", provenance);
+    info!("  format:       {}", profile.format.name());
+    if !profile.language_overrides.is_empty() {
+        let overrides: Vec<String> = profile
+            .language_overrides
+            .iter()
+            .map(|(key, language)| format!("{}={}", key, language))
+            .collect();
+        info!("  languages:    {}", overrides.join(" "));
+    }
+    info!("  header:       {}", if profile.header.is_some() { "text" } else { "none" });
+    info!("  footer:       {}", if profile.footer.is_some() { "template" } else { "none" });
+    if let Some(prompt) = PROMPT.get() {
+        info!("  prompt:       {}", prompt.lines().next().unwrap_or_default());
+    }
+    if let Some(path) = TEMPLATE.get().cloned().or_else(|| profile.template.map(expand_tilde)) {
+        let mut names: Vec<&str> = profile.template_vars.keys().map(String::as_str).collect();
+        names.extend(TEMPLATE_VARS.get().into_iter().flatten().map(|(key, _)| key.as_str()));
+        names.sort();
+        names.dedup();
+        match names.is_empty() {
+            true => info!("  template:     {}", path.display()),
+            false => info!("  template:     {} (vars: {})", path.display(), names.join(", ")),
+        }
+    }
+    match profile.max_tokens {
+        Some(max_tokens) => info!(
+            "  budget:       {} tokens ({}), {}",
+            max_tokens,
+            tokens::ENCODING,
+            profile.budget_strategy.name()
+        ),
+        None => info!("  budget:       none"),
+    }
+    match profile.max_file_size {
+        Some(bytes) => info!(
+            "  file size:    max {}, larger files: {}",
+            file_size::describe(bytes),
+            profile.large_files.name()
+        ),
+        None => info!("  file size:    no limit"),
+    }
+    match profile.max_memory {
+        Some(bytes) => info!("  memory:       bundle assembled on disk past {}", file_size::describe(bytes)),
+        None => info!("  memory:       no limit"),
+    }
+    info!("");
+}
+
+fn run_subcommand(command: cli::Command) -> Result<(), Error> {
+    use cli::Command;
+    match command {
+        Command::Copy { .. } => unreachable!("bundled by main"),
+        Command::Profiles { command } => match command {
+            None | Some(cli::ProfilesCommand::List) => profiles::list()?,
+            Some(cli::ProfilesCommand::Show { name }) => profiles::show(&name)?,
+            Some(cli::ProfilesCommand::Add { name, files, dirs }) => profiles::add(&name, &files, &dirs)?,
+            Some(cli::ProfilesCommand::Remove { name }) => profiles::remove(&name)?,
+        },
+        Command::Config { command } => config_command(command)?,
+        Command::Init => init_config()?,
+        Command::Trim { profile, save } => trim::run(profile.as_deref(), save)?,
+        // Project-aware modes build their own file lists
+        Command::Cargo(raw) => modes::cargo::run(&raw.args)?,
+        Command::Node(raw) => modes::node::run(&raw.args)?,
+        Command::Python(raw) => modes::python::run(&raw.args)?,
+        Command::Iac(raw) => modes::iac::run(&raw.args)?,
+        Command::Compare(raw) => modes::compare::run(&raw.args)?,
+        Command::Errors(raw) => modes::errors::run(&raw.args)?,
+        Command::Failing(raw) => modes::failing::run(&raw.args)?,
+        Command::Dep(raw) => modes::dep::run(&raw.args)?,
+        Command::Triage(raw) => modes::triage::run(&raw.args)?,
+        Command::Cache(raw) => cache::run(&raw.args),
+        Command::Open(raw) => open::run(&raw.args)?,
+        Command::Auto(raw) => auto::run(&raw.args)?,
+        Command::Index(raw) => index::run(&raw.args)?,
+        Command::Semsearch(raw) => index::semsearch(&raw.args)?,
+        Command::Stats(raw) => history::stats(&raw.args),
+        Command::Usage(raw) => history::usage(&raw.args),
+        Command::Pack(raw) => pack::pack(&raw.args)?,
+        Command::Unpack(raw) => pack::unpack(&raw.args)?,
+        Command::Exclude(raw) => session::override_command("exclude", &raw.args),
+        Command::Add(raw) => session::override_command("add", &raw.args),
+        Command::Session(raw) => session::run(&raw.args),
+        Command::Which(raw) => which::run(&raw.args)?,
+        Command::GrepProfiles(raw) => grep::run(&raw.args)?,
+        Command::Audit(raw) => audit::run(&raw.args)?,
+        Command::Snip(raw) => snippets::snip(&raw.args)?,
+        Command::ClipWatch(raw) => snippets::clip_watch(&raw.args),
+    }
+    Ok(())
+}
+
+// Content transforms run in order: outlines, schema condensing, import stripping, blob elision,
+// line limits, per-file token limits, truncate_paths. Redaction runs before all of them, in
+// `render`, so a cut never hides half a secret from the rules.
+fn content_transform<'a>(profile: &ActiveProfile<'a>) -> Option<ContentTransform<'a>> {
+    let outline_paths = profile.outline_paths.clone();
+    let truncate_paths = profile.truncate_paths.clone();
+    let condense_schemas = profile.condense_schemas;
+    let strip_imports = profile.strip_imports;
+    let blob_min_length = profile.elide_blobs.and_then(blobs::ElideBlobs::min_length);
+    let max_line_length = profile.max_line_length;
+    let long_lines = profile.long_lines;
+    let max_tokens_per_file = profile.max_tokens_per_file;
+    if outline_paths.is_empty()
+        && truncate_paths.is_empty()
+        && !condense_schemas
+        && strip_imports.is_none()
+        && blob_min_length.is_none()
+        && max_line_length.is_none()
+        && max_tokens_per_file.is_none()
+    {
+        return None;
+    }
+    Some(Box::new(move |path: &Path, content: String| {
+        let content = if outline_paths.iter().any(|pattern| pattern.matches_path(path)) {
+            docs::docs_only(path, content)
+        } else {
+            content
+        };
+        let content = if condense_schemas {
+            condense::condense_schema(path, content)
+        } else {
+            content
+        };
+        let content = match strip_imports {
+            Some(setting) => imports::strip_imports(path, content, setting),
+            None => content,
+        };
+        let content = match blob_min_length {
+            Some(min_length) => blobs::elide_blobs(content, min_length),
+            None => content,
+        };
+        let content = match max_line_length {
+            Some(max) => long_lines::limit_line_length(content, max, long_lines),
+            None => content,
+        };
+        let content = match max_tokens_per_file {
+            Some(setting) => file_budget::limit_file_tokens(path, content, setting),
+            None => content,
+        };
+        match truncate_paths.iter().find(|(pattern, _)| pattern.matches_path(path)) {
+            Some((_, limit)) => file_budget::truncate_to_tokens(path, content, *limit),
+            None => content,
+        }
+    }))
+}
+
+// --template's file, or the profile's, read and checked before anything is collected
+fn load_template(
+    profile: &ActiveProfile,
+    profile_name: Option<&str>,
+    options: &Options,
+) -> Result<Option<template::Template>, Error> {
+    let Some(path) = options.template.clone().or_else(|| profile.template.map(expand_tilde)) else {
+        return Ok(None);
+    };
+    let mut values = profile.template_vars.clone();
+    values.extend(options.template_vars.iter().cloned());
+    values.insert("profile".to_string(), profile_name.unwrap_or("default").to_string());
+    template::Template::load(&path, values)
+        .map(Some)
+        .map_err(|err| Error::Config(format!("Invalid template {}: {}", path.display(), err)))
+}
+
+// The database schema, GitHub issues and the git diff, when the profile asks for them; like
+// every section, they are redacted on their way into the bundle
+fn profile_sections(profile: &ActiveProfile) -> Vec<Section> {
+    let mut sections = Vec::new();
+    if let Some(database) = profile.database {
+        if let Some(section) = database::schema_section(database) {
+            sections.push(section);
+        }
+    }
+    sections.extend(profile.issues.iter().filter_map(|entry| github::issue_section(entry)));
+    // The diff is not a file, so of the transforms only redaction applies to it
+    if let Some(against) = profile.git_diff {
+        sections.extend(git::diff_section(&project_root(profile), against, profile.git_diff_context));
+    }
+    sections
+}
+
+// The library API, for editors, scripts and other front ends. Progress messages are silenced,
+// failures come back as `Error` rather than ending the process, and only what is passed in
+// applies: no command-line flags, no session tweaks, no pending snippets unless asked for.
+// (`run` is the command line itself; some of its subcommands still exit on their own.)
+
+pub use clipboard::ClipboardBackend;
+pub use error::Error;
+pub use format::Format;
+pub use output::{deliver, ClipboardFallback, Outputs, Report};
+pub use send_keys::SendKeys;
+
+/// Settings applied on top of every profile, as the command-line flags of the same names apply
+/// them. `Options::default()` leaves each profile as configured.
+#[derive(Clone, Default)]
+pub struct Options {
+    /// `--format`: the layout in place of the profile's
+    pub format: Option<Format>,
+    /// `--max-tokens`: the token budget in place of the profile's
+    pub max_tokens: Option<usize>,
+    /// `--max-file-size`, in bytes
+    pub max_file_size: Option<u64>,
+    /// `--no-gitignore`: walk directories and draw trees without .gitignore and friends
+    pub no_gitignore: bool,
+    /// `--git-diff`: add `git diff` as a section, for "unstaged", "staged" or a ref
+    pub git_diff: Option<String>,
+    /// `--clipboard-backend`
+    pub clipboard_backend: Option<ClipboardBackend>,
+    /// `--docs-only`: Rust files reduced to item signatures and their `///` docs
+    pub docs_only: bool,
+    /// `--include-binary-as-note`: binary files listed with a one-line note
+    pub binary_as_note: bool,
+    /// `--deterministic`: files sorted by path after the project docs, trees always drawn built-in
+    pub deterministic: bool,
+    /// `--template`: lay the bundle out with this file instead of the profile's template
+    pub template: Option<PathBuf>,
+    /// `--var`: template values on top of the profile's `template_vars`
+    pub template_vars: Vec<(String, String)>,
+    /// Add the snippets `fdllm clip-watch` collected since the last bundle, as a run does
+    pub pending_snippets: bool,
+}
+
+// The options this run's flags set; built once they are all parsed
+fn command_line_options() -> &'static Options {
+    static OPTIONS: OnceLock<Options> = OnceLock::new();
+    OPTIONS.get_or_init(|| Options {
+        format: FORMAT.get().copied(),
+        max_tokens: MAX_TOKENS.get().copied(),
+        max_file_size: MAX_FILE_SIZE.get().copied(),
+        no_gitignore: NO_GITIGNORE.load(Ordering::Relaxed),
+        git_diff: GIT_DIFF.get().cloned(),
+        clipboard_backend: CLIPBOARD_BACKEND.get().copied(),
+        docs_only: DOCS_ONLY.load(Ordering::Relaxed),
+        binary_as_note: BINARY_AS_NOTE.load(Ordering::Relaxed),
+        deterministic: deterministic(),
+        template: TEMPLATE.get().cloned(),
+        template_vars: TEMPLATE_VARS.get().cloned().unwrap_or_default(),
+        pending_snippets: true,
+    })
+}
+
+impl Config {
+    /// The config a run would use: the global one with the project's `.fdllm.toml` merged over
+    /// it (a starter config is written when neither exists)
+    pub fn load() -> Result<Config, Error> {
+        load_config(&get_config_path()?)
+    }
+
+    /// A config from TOML text, with `extends` and `include_profiles` expanded
+    pub fn from_toml(text: &str) -> Result<Config, Error> {
+        let mut table: toml::Table = text.parse().map_err(|err: toml::de::Error| Error::Config(err.to_string()))?;
+        inherit::resolve(&mut table).map_err(Error::Config)?;
+        toml::Value::Table(table)
+            .try_into()
+            .map_err(|err: toml::de::Error| Error::Config(err.to_string()))
+    }
+
+    /// The profile names, sorted
+    pub fn profile_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self
+            .profiles
+            .iter()
+            .flat_map(|profiles| profiles.keys())
+            .map(String::as_str)
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// The profile called `name`, as written
+    pub fn profile(&self, name: &str) -> Option<&Profile> {
+        self.profiles.as_ref()?.get(name)
+    }
+}
+
+impl Profile {
+    /// The `files` entries as written in the config, before globs and roots are expanded
+    pub fn files(&self) -> &[String] {
+        self.files.as_deref().unwrap_or_default()
+    }
+
+    /// The `directories` entries as written, before they are walked
+    pub fn directories(&self) -> &[String] {
+        self.directories.as_deref().unwrap_or_default()
+    }
+}
+
+// Runs `body` with progress messages silenced, restoring the previous setting after
+// The active profile laid out as `render_payload` lays it out; its roots are taken
+fn render_profile(active: &mut ActiveProfile, profile: Option<&str>, options: &Options) -> Result<Rendered, Error> {
+    let redactor = redact::Redactor::new(active.redaction, &active.redaction_rules);
+    let mut bundle = Bundle {
+        files: profile_files(active),
+        docs: profile_docs(active),
+        project: active.project,
+        sections: profile_sections(active),
+        transform: content_transform(active),
+        use_cache: active.use_cache,
+        roots: std::mem::take(&mut active.roots),
+        rewrite_paths: active.rewrite_paths,
+        no_gitignore: !active.gitignore,
+        header: active
+            .header
+            .map(|header| header.replace("{{profile}}", profile.unwrap_or("default"))),
+        footer: active
+            .footer
+            .map(|template| template.replace("{{profile}}", profile.unwrap_or("default"))),
+        template: load_template(active, profile, options)?,
+        budget: active.max_tokens.map(|max_tokens| budget::Budget {
+            max_tokens,
+            strategy: active.budget_strategy,
+        }),
+        max_file_size: active.max_file_size.map(|bytes| file_size::Limit {
+            bytes,
+            strategy: active.large_files,
+        }),
+        format: active.format,
+        ..Default::default()
+    };
+    redact_sections(&mut bundle.sections, redactor.as_ref());
+    render(&mut bundle, redactor.as_ref(), options, false)
+}
+
+fn quietly<T>(body: impl FnOnce() -> T) -> T {
+    let quiet = QUIET.swap(true, Ordering::Relaxed);
+    let result = body();
+    QUIET.store(quiet, Ordering::Relaxed);
+    result
+}
+
+/// The working-tree files `profile` would bundle (None for the top-level configuration), in
+/// bundle order
+pub fn collect_files(config: &Config, profile: Option<&str>, options: &Options) -> Result<Vec<PathBuf>, Error> {
+    quietly(|| {
+        let active = active_profile(config, profile, options)?;
+        Ok(profile_files(&active)
+            .iter()
+            .filter(|entry| git::parse_revision_entry(entry).is_none())
+            .map(|entry| roots::resolve(entry, &active.roots))
+            .collect())
+    })
+}
+
+/// A bundle as text, with what went into it
+pub struct Payload {
+    /// The bundle, as it would be delivered
+    pub content: String,
+    /// Headers of the included files, as they appear in `content`
+    pub files: Vec<String>,
+    /// Counted with the o200k_base encoding
+    pub tokens: usize,
+}
+
+/// The bundle `profile` would deliver, built the way the command line builds it (trees,
+/// sections, redaction, transforms, budget, footer) but returned instead of delivered. No
+/// provenance footer is added, and --git and session tweaks don't apply.
+pub fn render_payload(config: &Config, profile: Option<&str>, options: &Options) -> Result<Payload, Error> {
+    quietly(|| {
+        let mut active = active_profile(config, profile, options)?;
+        let rendered = render_profile(&mut active, profile, options)?;
+        let content = match rendered.assembled {
+            spill::Assembled::Memory(content) => content,
+            // Without max_memory nothing is spilled, but a spill file reads back all the same
+            spill::Assembled::Spilled(spilled) => {
+                let content = fs::read_to_string(&spilled.path).unwrap_or_default();
+                spilled.discard();
+                content
+            }
+        };
+        Ok(Payload {
+            content,
+            files: rendered.included_paths,
+            tokens: rendered.tokens,
+        })
+    })
+}
+
+// The `fdllm` command line: flags, then a subcommand or the profiles to bundle
+pub fn run() -> Result<(), Error> {
+    let (cli, subcommand) = cli::parse();
+    let global = cli.global;
+    
+    // Global flags are accepted anywhere on the command line
+    PORCELAIN.store(global.porcelain, Ordering::Relaxed);
+    if cli.print_exit_codes {
+        exit_codes::print();
+        return Ok(());
+    }
+    // Before anything runs git, gh or a database dump, so their output doesn't follow the
+    // user's language, time zone or terminal width
+    if global.deterministic {
+        DETERMINISTIC.store(true, Ordering::Relaxed);
+        env::remove_var("LANGUAGE");
+        env::set_var("LC_ALL", "C");
+        env::set_var("LANG", "C");
+        env::set_var("TZ", "UTC");
+        env::set_var("COLUMNS", "80");
+        env::set_var("NO_COLOR", "1");
+    }
+    // `--timeout 30s` bounds the whole run; what is collected by then is still delivered
+    if let Some(limit) = global.timeout {
+        deadline::start(limit);
+    }
+    if global.portable {
+        enable_portable_mode()?;
+    }
+    if let Some(path) = global.config {
+        let _ = CONFIG_PATH.set(path);
+    }
+    if let Some(file_format) = global.format {
+        let _ = FORMAT.set(file_format);
+    }
+    if let Some(backend) = global.clipboard_backend {
+        let _ = CLIPBOARD_BACKEND.set(backend);
+    }
+    if let Some(max_tokens) = global.max_tokens {
+        let _ = MAX_TOKENS.set(max_tokens);
+    }
+    if let Some(max_file_size) = global.max_file_size {
+        let _ = MAX_FILE_SIZE.set(max_file_size);
+    }
+    NO_GITIGNORE.store(global.no_gitignore, Ordering::Relaxed);
+    NO_CLIPBOARD.store(global.no_clipboard, Ordering::Relaxed);
+    VERBOSE.store(global.verbose, Ordering::Relaxed);
+    DOCS_ONLY.store(global.docs_only, Ordering::Relaxed);
+    BINARY_AS_NOTE.store(global.include_binary_as_note, Ordering::Relaxed);
+    NO_SUGGEST.store(global.no_suggest, Ordering::Relaxed);
+    ALLOW_SECRETS.store(global.allow_secrets, Ordering::Relaxed);
+    DRY_RUN.store(global.dry_run, Ordering::Relaxed);
+    if global.dry_run {
+        STDOUT_DATA.store(true, Ordering::Relaxed);
+    }
+    if let Some(selection) = global.git {
+        let _ = GIT_SELECTION.set(selection);
+    }
+    CONFLICTS.store(global.conflicts, Ordering::Relaxed);
+    if let Some(against) = global.git_diff {
+        let _ = GIT_DIFF.set(against);
+    }
+    
+    // `--redaction <preset>` overrides the profile's preset for this run
+    let redaction_override = global.redaction;
+    if let Some(preset) = redaction_override {
+        let _ = REDACTION.set(preset);
+    }
+    // `--output <path>` replaces the profile's output_path; `--diff-output` only previews it
+    let output_override = global.output;
+    if let Some(path) = &output_override {
+        let _ = OUTPUT_PATH.set(expand_tilde(path));
+    }
+    if let Some(prompt) = global.prompt {
+        let _ = PROMPT.set(prompt);
+    }
+    if let Some(path) = &global.template {
+        let _ = TEMPLATE.set(expand_tilde(path));
+    }
+    if !global.vars.is_empty() {
+        let _ = TEMPLATE_VARS.set(global.vars);
+    }
+    if let Some(mut send_keys) = global.send_keys {
+        send_keys.path = global.send_path;
+        let _ = SEND_KEYS.set(send_keys);
+    }
+    if global.stdout {
+        STDOUT_BUNDLE.store(true, Ordering::Relaxed);
+        STDOUT_DATA.store(true, Ordering::Relaxed);
+    }
+    let explain = global.explain;
+    let diff_output = global.diff_output;
+    if diff_output {
+        STDOUT_DATA.store(true, Ordering::Relaxed);
+    }
+    
+    // Runs are recorded under the profile (or mode) name
+    let profile_names = match cli.command {
+        None => Vec::new(),
+        Some(cli::Command::Copy { profiles, more_profiles }) => [profiles, more_profiles].concat(),
+        Some(command) => {
+            history::begin(subcommand.as_deref().unwrap_or("default"));
+            return run_subcommand(command);
+        }
+    };
+    // `fdllm --file src/main.rs --dir src/handlers --tree . -L 2`, or paths in place of profiles
+    let mut adhoc_files = global.files;
+    let mut adhoc_dirs = global.dirs;
+    // `git ls-files '*.rs' | fdllm --stdin`; a directory in the list is walked like --dir
+    let piped = global.stdin;
+    if piped {
+        for path in stdin_paths::read() {
+            if Path::new(&path).is_dir() {
+                adhoc_dirs.push(path);
+            } else {
+                adhoc_files.push(path);
+            }
+        }
+    }
+    let adhoc_tree = global.tree.map(|path| Project {
+        path,
+        tree_level: global.level,
+        tree: None,
+    });
+    let names_paths = profile_names
+        .iter()
+        .any(|name| Path::new(name).exists() || github::is_reference(name));
+    let adhoc = !adhoc_files.is_empty() || !adhoc_dirs.is_empty() || adhoc_tree.is_some() || names_paths || piped;
+    // A one-off bundle of paths needs no config, and doesn't write the starter one
+    let configless = adhoc && CONFIG_PATH.get().is_none() && !default_config_path()?.exists() && project_config_path().is_none();
+    let config_path = if configless { default_config_path()? } else { get_config_path()? };
+    let config = if configless { Config::default() } else { load_config(&config_path)? };
+    // A name is a profile first; one that isn't, but exists on disk, is a path to bundle, and
+    // `gh:owner/repo#123` is an issue to fetch
+    let is_profile = |name: &str| config.profiles.as_ref().is_some_and(|profiles| profiles.contains_key(name));
+    let (paths, profile_names): (Vec<String>, Vec<String>) = profile_names
+        .into_iter()
+        .partition(|name| !is_profile(name) && (Path::new(name).exists() || github::is_reference(name)));
+    for path in paths {
+        if Path::new(&path).is_dir() {
+            adhoc_dirs.push(path);
+        } else {
+            adhoc_files.push(path);
+        }
+    }
+    // `fdllm copy "backend + shared - backend:tests"`
+    let mut terms = compose::parse(&profile_names)
+        .map_err(|err| Error::Config(format!("Invalid profile expression: {}", err)))?;
+    for term in &mut terms {
+        if let Some(name) = resolve_profile_name(&config, Some(term.profile.clone()))? {
+            term.profile = name;
+        }
+    }
+    // Settings come from the profiles added; the ones only taken away contribute nothing else
+    let mut profile_names: Vec<String> = terms
+        .iter()
+        .filter(|term| !term.exclude)
+        .map(|term| term.profile.clone())
+        .collect();
+    let mut named = HashSet::new();
+    profile_names.retain(|name| named.insert(name.clone()));
+    let composed = !compose::is_plain(&terms);
+    // Several profiles run as one, recorded and shown as `frontend+backend`
+    let joined_name = if composed {
+        Some(compose::describe(&terms))
+    } else {
+        (!profile_names.is_empty()).then(|| profile_names.join("+"))
+    };
+    history::begin(joined_name.as_deref().unwrap_or("default"));
+    let profile_name = joined_name.as_deref();
+    if config.usage_metrics.unwrap_or(false) {
+        history::track_paths();
+    }
+    
+    let options = command_line_options();
+    let mut profile = active_profile(&config, profile_names.first().map(String::as_str), options)?;
+    let others = profile_names
+        .iter()
+        .skip(1)
+        .map(|name| active_profile(&config, Some(name), options))
+        .collect::<Result<_, _>>()?;
+    merge_profiles(&mut profile, others);
+    // Taking files away is fine; another profile's files would widen a locked one
+    if profile.locked && profile_names.len() > 1 {
+        return Err(Error::Config(format!(
+            "Profile is locked: {} can't be combined with other profiles",
+            profile_names.join(", ")
+        )));
+    }
+    if let Some(preset) = redaction_override {
+        if profile.locked && preset < profile.redaction {
+            return Err(Error::Config(format!(
+                "Profile is locked: --redaction {} would weaken its '{}' preset",
+                preset.name(),
+                profile.redaction.name()
+            )));
+        }
+        profile.redaction = preset;
+    }
+    let (adhoc_files, adhoc_issues) = github::split_entries(Some(&adhoc_files));
+    if profile_names.is_empty() && adhoc {
+        // Only the paths given; the top-level settings still apply
+        profile.files = adhoc_files;
+        profile.issues = adhoc_issues;
+        profile.directories = adhoc_dirs;
+        profile.project = adhoc_tree.as_ref();
+    } else if adhoc {
+        if profile.locked {
+            return Err(Error::Config(
+                "Profile is locked: --file, --dir, --tree, --stdin and paths can't add to it".to_string(),
+            ));
+        }
+        if composed {
+            // The expression decides the profiles' files; these go on top of what it leaves
+            profile.files = adhoc_files;
+            profile.directories = adhoc_dirs;
+        } else {
+            for file in adhoc_files {
+                if !profile.files.contains(&file) {
+                    profile.files.push(file);
+                }
+            }
+            for dir in adhoc_dirs {
+                if !profile.directories.contains(&dir) {
+                    profile.directories.push(dir);
+                }
+            }
+        }
+        for issue in adhoc_issues {
+            if !profile.issues.contains(&issue) {
+                profile.issues.push(issue);
+            }
+        }
+        if let Some(tree) = adhoc_tree.as_ref() {
+            match profile.project {
+                None => profile.project = Some(tree),
+                Some(_) => profile.more_projects.push(tree),
+            }
+        }
+    }
+    if profile.locked && profile.scan == Some(scan::Scan::Block) && ALLOW_SECRETS.load(Ordering::Relaxed) {
+        return Err(Error::Config("Profile is locked: --allow-secrets would bypass its secret scan".to_string()));
+    }
+    let template = load_template(&profile, profile_name, options)?;
+    let output_path = output_override
+        .as_deref()
+        .or(profile.output_path)
+        .map(expand_tilde)
+        .map(|path| output::expand_template(&path, profile_name.unwrap_or("default")));
+    
+    // Before anything is walked or read
+    tuning::set(profile.tuning);
+    lang::set_overrides(profile.language_overrides.clone());
+    
+    if explain {
+        explain_plan(&config_path, profile_name, &profile, output_path.as_deref(), diff_output);
+    }
+    
+    let directories = profile_directories(&profile);
+    let mut files_to_copy = if composed {
+        let mut files = composed_files(&config, &terms, options)?;
+        info!("{}: {} file(s)", joined_name.as_deref().unwrap_or_default(), files.len());
+        if adhoc {
+            let canonical = |file: &str| {
+                let path = roots::resolve(file, &profile.roots);
+                fs::canonicalize(&path).unwrap_or(path)
+            };
+            let mut listed: HashSet<PathBuf> = files.iter().map(|file| canonical(file)).collect();
+            let mut added = profile_file_entries(&profile);
+            for dir_path in &directories {
+                added.extend(
+                    collect_profile_directory(dir_path, &profile)
+                        .iter()
+                        .map(|file| file.to_string_lossy().to_string()),
+                );
+            }
+            for file in added {
+                if git::parse_revision_entry(&file).is_some() || listed.insert(canonical(&file)) {
+                    files.push(file);
+                }
+            }
+        }
+        files
+    } else {
+        profile_files_in(&profile, &directories)
+    };
+    if profile_names.len() > 1 {
+        // Profiles often share files; the same file named two ways is bundled once
+        let before = files_to_copy.len();
+        let mut seen = HashSet::new();
+        files_to_copy.retain(|file| {
+            git::parse_revision_entry(file).is_some() || {
+                let path = roots::resolve(file, &profile.roots);
+                seen.insert(fs::canonicalize(&path).unwrap_or(path))
+            }
+        });
+        if files_to_copy.len() < before {
+            verbose!("{} file(s) shared between profiles bundled once", before - files_to_copy.len());
+        }
+    }
+    
+    // A profile's files are narrowed to the selection; without one, the selection is the file list
+    if let Some(selection) = GIT_SELECTION.get() {
+        let selected = git::selected_files(&project_root(&profile), selection)
+            .map_err(|err| Error::Failure(format!("--git: {}", err)))?;
+        if profile_name.is_some() {
+            let canonical = |path: &Path| fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+            let selected: Vec<PathBuf> = selected.iter().map(|path| canonical(path)).collect();
+            files_to_copy.retain(|file| selected.contains(&canonical(&roots::resolve(file, &profile.roots))));
+        } else {
+            files_to_copy = selected.iter().map(|path| path.to_string_lossy().to_string()).collect();
+        }
+        info!("--git: {} file(s), {}", files_to_copy.len(), selection.describe());
+    }
+    
+    // Each conflicted file as it stands, after the versions from both sides and their base
+    let mut conflicts_section = None;
+    if CONFLICTS.load(Ordering::Relaxed) {
+        let base = project_root(&profile);
+        let mut conflicts = git::conflicts(&base).map_err(|err| Error::Failure(format!("--conflicts: {}", err)))?;
+        if profile_name.is_some() {
+            let canonical = |path: &Path| fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+            let listed: Vec<PathBuf> = files_to_copy.iter().map(|file| canonical(&roots::resolve(file, &profile.roots))).collect();
+            conflicts.retain(|conflict| listed.contains(&canonical(&conflict.path)));
+        }
+        if conflicts.is_empty() {
+            porcelain_record("status", "error empty");
+            return Err(Error::Empty(format!("--conflicts: no conflicted files in {}", base.display())));
+        }
+        files_to_copy = conflicts.iter().flat_map(|conflict| conflict.entries(&base)).collect();
+        info!("--conflicts: {} conflicted file(s)", conflicts.len());
+        conflicts_section = Some(git::conflicts_section(&base, &conflicts));
+    }
+    
+    // Per-conversation tweaks from `fdllm exclude/add --for-session`
+    if let Some(session) = session::load() {
+        let before = files_to_copy.len();
+        files_to_copy.retain(|entry| !session.excludes(entry, &roots::resolve(entry, &profile.roots)));
+        let excluded = before - files_to_copy.len();
+        let added = if profile.locked && !session.add.is_empty() {
+            eprintln!("Profile is locked: ignoring {} session addition(s)", session.add.len());
+            0
+        } else {
+            files_to_copy.extend(session.add.iter().cloned());
+            session.add.len()
+        };
+        info!("Session overrides: {} file(s) excluded, {} added", excluded, added);
+    }
+    
+    let mut sections = profile_sections(&profile);
+    sections.extend(conflicts_section);
+    let transform = content_transform(&profile);
+    
+    // Signing needs the footer, so a key alone enables it
+    let provenance = if profile.provenance || profile.signing_key.is_some() {
+        Some(provenance::Settings {
+            config_path: config_path.clone(),
+            git_dir: project_root(&profile),
+            signing_key: profile.signing_key.map(str::to_string),
+        })
+    } else {
+        None
+    };
+    
+    let prune = if profile.prune_report > 0 && !profile.directories.is_empty() {
+        Some(prune::Settings {
+            top: profile.prune_report,
+            directories,
+            configured: !configless,
+        })
+    } else {
+        None
+    };
+    
+    let header = profile
+        .header
+        .map(|header| header.replace("{{profile}}", profile_name.unwrap_or("default")));
+    let footer = profile
+        .footer
+        .map(|template| template.replace("{{profile}}", profile_name.unwrap_or("default")));
+    
+    copy_bundle(Bundle {
+        files: files_to_copy,
+        docs: profile_docs(&profile),
+        project: profile.project,
+        more_projects: profile.more_projects,
+        sections,
+        transform,
+        use_cache: profile.use_cache,
+        roots: profile.roots,
+        rewrite_paths: profile.rewrite_paths,
+        provenance,
+        output_path,
+        skip_clipboard: !profile.clipboard,
+        clipboard_fallback: profile.clipboard_fallback,
+        clipboard_backend: profile.clipboard_backend,
+        diff_output,
+        prune,
+        header,
+        footer,
+        prompt: PROMPT.get().cloned(),
+        template,
+        no_gitignore: !profile.gitignore,
+        budget: profile.max_tokens.map(|max_tokens| budget::Budget {
+            max_tokens,
+            strategy: profile.budget_strategy,
+        }),
+        max_file_size: profile.max_file_size.map(|bytes| file_size::Limit {
+            bytes,
+            strategy: profile.large_files,
+        }),
+        max_memory: profile.max_memory,
+        format: profile.format,
+        protection: Some(Protection {
+            redaction: profile.redaction,
+            redaction_rules: profile.redaction_rules,
+            scan: profile.scan,
+        }),
+        ..Default::default()
+    })
+}
+
+const DEFAULT_ROOT_TREE_LEVEL: u32 = 2;
+
+// Extra named blocks (dependency lists, schemas, ...) placed between the tree and the files
+struct Section {
+    title: String,
+    body: String,
+}
+
+type ContentTransform<'a> = Box<dyn Fn(&Path, String) -> String + 'a>;
+
+// Everything that ends up in the clipboard payload
+#[derive(Default)]
+struct Bundle<'a> {
+    files: Vec<String>,
+    // The project docs among `files`, which stay in front in this order under --deterministic
+    docs: Vec<String>,
+    project: Option<&'a Project>,
+    // Further trees after the project's, one per other profile in a multi-profile run
+    more_projects: Vec<&'a Project>,
+    sections: Vec<Section>,
+    // Applied to each file's content before it is added (e.g. mode-specific redaction)
+    transform: Option<ContentTransform<'a>>,
+    // Read file contents through the on-disk cache
+    use_cache: bool,
+    roots: Vec<roots::Root>,
+    // Prefix rules applied to headers and tree titles
+    rewrite_paths: &'a [roots::PathRewrite],
+    // Append a provenance footer (and signature) after everything else
+    provenance: Option<provenance::Settings>,
+    // Extra file output, written atomically alongside the clipboard
+    output_path: Option<PathBuf>,
+    skip_clipboard: bool,
+    clipboard_fallback: output::ClipboardFallback,
+    // --clipboard-backend applies even without one
+    clipboard_backend: clipboard::ClipboardBackend,
+    // Print a diff against the existing output file instead of delivering anything
+    diff_output: bool,
+    // Report the subdirectories contributing the most tokens
+    prune: Option<prune::Settings>,
+    // Draw project trees without applying .gitignore and friends
+    no_gitignore: bool,
+    // Put before the trees
+    header: Option<String>,
+    // Appended after files and snippets; `{{file_count}}` is filled in here
+    footer: Option<String>,
+    // --prompt, between the files and the footer
+    prompt: Option<String>,
+    // Lays out the trees, sections, files and footer in place of the fixed order
+    template: Option<template::Template>,
+    // Token budget for the whole bundle; --max-tokens applies even without one
+    budget: Option<budget::Budget>,
+    // Size limit for each file; --max-file-size applies even without one
+    max_file_size: Option<file_size::Limit>,
+    // Assemble the bundle in a temp file once it passes this many bytes
+    max_memory: Option<u64>,
+    // Layout of each file block
+    format: format::Format,
+    // Redaction and the secret scan; without them, the top-level config's apply
+    protection: Option<Protection>,
+    // --dry-run: file contents stay in memory once spilled, to be sized
+    dry_run: bool,
+}
+
+// How a bundle is redacted and scanned. A profile run brings its profile's; modes, auto,
+// semsearch, pack and snippets get --redaction and the top-level config's from `copy_bundle`.
+struct Protection {
+    redaction: redact::Redaction,
+    redaction_rules: Vec<redact::CustomRule>,
+    // Look for secrets in the finished files and sections before delivering them
+    scan: Option<scan::Scan>,
+}
+
+impl Protection {
+    fn from_config() -> Result<Protection, Error> {
+        let config = config_if_any()?;
+        Ok(Protection {
+            redaction: REDACTION.get().copied().or(config.redaction).unwrap_or(redact::Redaction::Standard),
+            redaction_rules: config.redaction_rules.unwrap_or_default(),
+            scan: config.scan,
+        })
+    }
+}
+
+fn redact_sections(sections: &mut [Section], redactor: Option<&redact::Redactor>) {
+    if let Some(redactor) = redactor {
+        for section in sections {
+            section.body = redactor.redact(std::mem::take(&mut section.body));
+        }
+    }
+}
+
+fn report_redactions(redactor: &redact::Redactor, preset: redact::Redaction) {
+    if redactor.redacted() > 0 {
+        info!(
+            "Redacted {} value(s) ({} preset): {}",
+            redactor.redacted(),
+            preset.name(),
+            redactor.summary()
+        );
+    } else {
+        verbose!("Redacted nothing ({} preset)", preset.name());
+    }
+}
+
+fn copy_files(files_to_copy: Vec<String>, project_config: Option<&Project>) -> Result<(), Error> {
+    copy_bundle(Bundle {
+        files: files_to_copy,
+        project: project_config,
+        ..Default::default()
+    })
+}
+
+// Lists each finding without the secret itself; an error when the bundle must not be delivered
+fn report_secrets(mode: scan::Scan, findings: &[scan::Finding], delivering: bool) -> Result<(), Error> {
+    if findings.is_empty() {
+        verbose!("Secret scan found nothing");
+        return Ok(());
+    }
+    for finding in findings {
+        eprintln!("Possible secret ({}) in {}:{}: {}", finding.kind, finding.header, finding.line, finding.preview);
+        porcelain_record("secret", format!("{} {}:{}", finding.kind, finding.header, finding.line));
+    }
+    let blocked = mode == scan::Scan::Block && !ALLOW_SECRETS.load(Ordering::Relaxed);
+    if blocked && delivering {
+        return Err(Error::Secrets(format!(
+            "scan = \"block\": {} likely secret(s) found, nothing delivered; redact them (redaction_rules) or pass --allow-secrets",
+            findings.len()
+        )));
+    }
+    porcelain_record("warning", "secrets");
+    if mode == scan::Scan::Block && delivering {
+        eprintln!("{} likely secret(s) found, delivered anyway (--allow-secrets)", findings.len());
+    } else {
+        eprintln!("{} likely secret(s) found", findings.len());
+    }
+    Ok(())
+}
+
+// `--dry-run`: every file with its size and token count, then the bundle's totals; with
+// --porcelain, `file TOKENS BYTES STATE HEADER` and `total TOKENS BYTES` records
+fn print_dry_run(slots: &[Result<budget::Entry, String>], tokens: usize, bytes: usize) {
+    if !porcelain() {
+        println!("Dry run: nothing copied or written");
+        println!("{:>8}  {:>10}  file", "tokens", "bytes");
+    }
+    let mut included = 0;
+    for slot in slots {
+        let (tokens, bytes, state, header) = match slot {
+            Ok(entry) if entry.dropped => (entry.tokens, entry.content.len(), "dropped", &entry.header),
+            Ok(entry) => {
+                included += 1;
+                (entry.tokens, entry.content.len(), "included", &entry.header)
+            }
+            Err(header) => (0, 0, "denied", header),
+        };
+        if porcelain() {
+            porcelain_record("file", format!("{} {} {} {}", tokens, bytes, state, header));
+            continue;
+        }
+        match state {
+            "included" => println!("{:>8}  {:>10}  {}", tokens, bytes, header),
+            "dropped" => println!("{:>8}  {:>10}  {} (dropped to fit the token budget)", tokens, bytes, header),
+            _ => println!("{:>8}  {:>10}  {} (permission denied)", "-", "-", header),
+        }
+    }
+    if porcelain() {
+        porcelain_record("total", format!("{} {}", tokens, bytes));
+    } else {
+        println!(
+            "Total: {} tokens, {} bytes, {} file(s); trees, sections and footers included",
+            tokens, bytes, included
+        );
+    }
+}
+
+// What a bundle comes to before it is delivered anywhere
+struct Rendered {
+    assembled: spill::Assembled,
+    bytes: usize,
+    tokens: usize,
+    slots: Vec<Result<budget::Entry, String>>,
+    included_files: usize,
+    included_paths: Vec<String>,
+    file_tokens: Vec<(PathBuf, usize)>,
+    header_tokens: Vec<(String, usize)>,
+    denied: usize,
+    section_count: usize,
+    pending_snippets: Vec<snippets::Snippet>,
+    // Likely secrets in everything that went in, when scanning
+    findings: Vec<scan::Finding>,
+}
+
+// Trees, sections, files, snippets and footers in bundle order; the files and sections are
+// taken out of `bundle`, the rest is left for delivery. With `scanning`, each piece is scanned
+// as it goes in, before a spilled file's content is let go.
+fn render(bundle: &mut Bundle, redactor: Option<&redact::Redactor>, options: &Options, scanning: bool) -> Result<Rendered, Error> {
+    let mut findings = Vec::new();
+    let mut scan = |header: &str, content: &str| {
+        if scanning {
+            findings.extend(scan::scan(header, content));
+        }
+    };
+    // The same file listed in NFC and NFD (or twice outright) is bundled once
+    let mut files_to_copy = unicode_paths::dedup(std::mem::take(&mut bundle.files));
+    if options.deterministic {
+        let docs = std::mem::take(&mut bundle.docs);
+        files_to_copy.sort_by_cached_key(|entry| {
+            let rank = docs.iter().position(|doc| doc == entry).unwrap_or(docs.len());
+            (rank, unicode_paths::nfc(entry))
+        });
+    }
+    let project_config = bundle.project;
+    
+    let file_format = options.format.unwrap_or(bundle.format);
+    let header = bundle.header.as_ref().map(|header| format::prompt_block(file_format, header.trim_end()));
+    let prompt = bundle.prompt.as_ref().map(|prompt| format::prompt_block(file_format, prompt.trim_end()));
+    for (name, text) in [("header", &bundle.header), ("prompt", &bundle.prompt), ("footer", &bundle.footer)] {
+        if let Some(text) = text {
+            scan(name, text);
+        }
+    }
+    let mut combined_content = match (&header, &bundle.template) {
+        (Some(header), None) => header.clone(),
+        _ => String::new(),
+    };
+    
+    // Add project trees if specified
+    let tree_gitignore = !bundle.no_gitignore && !options.no_gitignore;
+    if project_config.is_some() {
+        for project in project_config.into_iter().chain(bundle.more_projects.iter().copied()) {
+            let project_path = expand_tilde(&project.path);
+            if !project_path.exists() {
+                eprintln!("Project path not found: {}", project_path.display());
+                continue;
+            }
+            // eza's output depends on its version and the terminal
+            let renderer = if options.deterministic { tree::TreeRenderer::Native } else { project.tree.unwrap_or_default() };
+            let tree_output = tree::render(&project_path, project.tree_level, renderer, tree_gitignore);
+            let tree_output = roots::rewrite_tree(&tree_output, bundle.rewrite_paths);
+            combined_content.push_str(&format::tree_block(
+                file_format,
+                &roots::rewrite(&project_path.display().to_string(), bundle.rewrite_paths),
+                &tree_output,
+            ));
+        }
+    } else {
+        // Without a project, each labeled root gets its own tree
+        for root in &bundle.roots {
+            if !root.path.is_dir() {
+                continue;
+            }
+            let tree_output = tree::native_tree(&root.path, Some(DEFAULT_ROOT_TREE_LEVEL), tree_gitignore);
+            let tree_output = roots::rewrite_tree(&tree_output, bundle.rewrite_paths);
+            combined_content.push_str(&format::tree_block(
+                file_format,
+                &format!("{} ({})", root.label, roots::rewrite(&root.path.display().to_string(), bundle.rewrite_paths)),
+                &tree_output,
+            ));
+        }
+    }
+    
+    let tree_length = combined_content.len();
+    let section_count = bundle.sections.len();
+    for section in std::mem::take(&mut bundle.sections) {
+        scan(&section.title, &section.body);
+        combined_content.push_str(&format::section_block(file_format, &section.title, &section.body));
+    }
+    
+    // Process files; `git:REV:path` entries are read from history instead of the working tree
+    let git_base = project_config
+        .map(|project| expand_tilde(&project.path))
+        .filter(|path| path.is_dir())
+        .unwrap_or_else(|| PathBuf::from("."));
+    let size_limit = match options.max_file_size {
+        Some(bytes) => Some(file_size::Limit {
+            bytes,
+            strategy: bundle.max_file_size.as_ref().map(|limit| limit.strategy).unwrap_or_default(),
+        }),
+        None => bundle.max_file_size.take(),
+    };
+    let mut readable_files = Vec::new();
+    // Historical versions and truncated files are read on their own
+    let mut read_apart = Vec::new();
+    let mut oversized = Vec::new();
+    for file in files_to_copy {
+        if let Some((rev, path)) = git::parse_revision_entry(&file) {
+            match git::show_file(rev, path, &bundle.roots, &git_base) {
+                Ok(content) => read_apart.push((readable_files.len(), file.clone(), PathBuf::from(path), Ok(content))),
+                Err(err) => eprintln!("Failed to read {}: {}", file, err),
+            }
+            continue;
+        }
+        let file_path = roots::resolve(&file, &bundle.roots);
+        let file_path = unicode_paths::locate(&file_path).unwrap_or(file_path);
+        if let Some(kind) = special::kind(&file_path) {
+            eprintln!("Skipping {}: {}", kind, file_path.display());
+        } else if special::is_pseudo(&file_path) {
+            eprintln!("Skipping {}: pseudo filesystem, its files can block on read", file_path.display());
+        } else if file_path.exists() && file_path.is_file() {
+            let header = unicode_paths::nfc(&roots::display(&file, &file_path, &bundle.roots));
+            let header = roots::rewrite(&header, bundle.rewrite_paths);
+            let size = file_path.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+            match &size_limit {
+                Some(limit) if size > limit.bytes && limit.strategy == file_size::LargeFiles::Skip => {
+                    oversized.push(header);
+                }
+                Some(limit) if size > limit.bytes => {
+                    let content = file_size::read_truncated(&file_path, size, limit);
+                    read_apart.push((readable_files.len(), header, file_path, content));
+                }
+                _ => readable_files.push((header, file_path)),
+            }
+        } else {
+            eprintln!("File not found or not a file: {}", file_path.display());
+        }
+    }
+    
+    let paths: Vec<PathBuf> = readable_files.iter().map(|(_, file_path)| file_path.clone()).collect();
+    let contents = cache::read_files(&paths, bundle.use_cache);
+    
+    // Files read on their own keep their place relative to the files around them
+    let truncated = read_apart.len() - read_apart.iter().filter(|(_, file, _, _)| git::parse_revision_entry(file).is_some()).count();
+    let mut loaded: Vec<(String, PathBuf, std::io::Result<String>)> = readable_files
+        .into_iter()
+        .zip(contents)
+        .map(|((header, file_path), content)| (header, file_path, content))
+        .collect();
+    for (index, header, path, content) in read_apart.into_iter().rev() {
+        loaded.insert(index, (header, path, content));
+    }
+    if let Some(limit) = &size_limit {
+        if !oversized.is_empty() {
+            info!(
+                "Skipped {} file(s) over max_file_size ({}): {}",
+                oversized.len(),
+                file_size::describe(limit.bytes),
+                oversized.join(", ")
+            );
+        }
+        if truncated > 0 {
+            info!(
+                "Truncated {} file(s) over max_file_size ({}) to their {}",
+                truncated,
+                file_size::describe(limit.bytes),
+                limit.strategy.name()
+            );
+        }
+    }
+    
+    let docs_only = options.docs_only;
+    let placeholder =
+        |header: &str| format::file_block(file_format, header, Path::new(header), PERMISSION_DENIED_PLACEHOLDER);
+    
+    // Unreadable files keep their place as a placeholder carrying the header
+    let mut slots: Vec<Result<budget::Entry, String>> = Vec::new();
+    let binary_as_note = options.binary_as_note;
+    for (file, file_path, content) in loaded {
+        // Sniffed rather than trusted from the extension: a NUL byte, or content that isn't UTF-8
+        let binary = match &content {
+            Ok(file_content) => binary::looks_binary(file_content),
+            Err(err) => err.kind() == std::io::ErrorKind::InvalidData,
+        };
+        if binary {
+            if binary_as_note {
+                slots.push(Ok(budget::Entry::new(file, file_path.clone(), binary::note(&file_path), file_format)));
+            } else {
+                info!("Skipping binary file: {}", file_path.display());
+            }
+            continue;
+        }
+        match content {
+            Ok(file_content) => {
+                let file_content = if docs_only {
+                    docs::docs_only(&file_path, file_content)
+                } else {
+                    file_content
+                };
+                let file_content = match redactor {
+                    Some(redactor) => redactor.redact(file_content),
+                    None => file_content,
+                };
+                let file_content = match &bundle.transform {
+                    Some(transform) => transform(&file_path, file_content),
+                    None => file_content,
+                };
+                slots.push(Ok(budget::Entry::new(file, file_path, file_content, file_format)));
+            },
+            Err(err) if err.kind() == std::io::ErrorKind::PermissionDenied => {
+                eprintln!("Permission denied: {}", file_path.display());
+                slots.push(Err(file));
+            }
+            Err(err) => {
+                eprintln!("Failed to read file {}: {}", file_path.display(), err);
+            }
+        }
+    }
+    
+    let mut trailer = String::new();
+    let denied_directories = take_denied_directories();
+    for dir in &denied_directories {
+        trailer.push_str(&placeholder(&roots::rewrite(&format!("{}/", dir.display()), bundle.rewrite_paths)));
+    }
+    
+    // Snippets collected by clip-watch since the last bundle
+    let pending_snippets = if options.pending_snippets { snippets::load_pending() } else { Vec::new() };
+    for snippet in &pending_snippets {
+        let content = match redactor {
+            Some(redactor) => redactor.redact(snippet.content.clone()),
+            None => snippet.content.clone(),
+        };
+        scan(&format!("Snippet: {}", snippet.name), &content);
+        trailer.push_str(&format::section_block(file_format, &format!("Snippet: {}", snippet.name), &content));
+    }
+    
+    // Only files give way to the budget; everything else counts as fixed
+    let budget = match options.max_tokens {
+        Some(max_tokens) => Some(budget::Budget {
+            max_tokens,
+            strategy: bundle.budget.as_ref().map(|budget| budget.strategy).unwrap_or_default(),
+        }),
+        None => bundle.budget.take(),
+    };
+    if let Some(budget) = &budget {
+        let placeholders: String = slots.iter().filter_map(|slot| slot.as_ref().err()).map(|header| placeholder(header)).collect();
+        let fixed = tokens::count(&combined_content)
+            + tokens::count(&placeholders)
+            + tokens::count(&trailer)
+            + bundle.footer.as_deref().map(tokens::count).unwrap_or(0)
+            + prompt.as_deref().map(tokens::count).unwrap_or(0)
+            + bundle.template.as_ref().map(|template| tokens::count(&template.literal)).unwrap_or(0);
+        let mut entries: Vec<&mut budget::Entry> = slots.iter_mut().filter_map(|slot| slot.as_mut().ok()).collect();
+        budget::enforce(&mut entries, fixed, budget)?;
+    }
+    
+    let mut manifest = provenance::Manifest::new();
+    let mut included_files = 0;
+    let mut included_paths = Vec::new();
+    let mut file_tokens = Vec::new();
+    let mut header_tokens = Vec::new();
+    let mut denied = denied_directories.len();
+    let dry_run = bundle.dry_run;
+    // A template needs the files as one value, so they are gathered in memory first and the
+    // trees and sections wait to be placed with them
+    let mut assembly = match &bundle.template {
+        Some(_) => spill::Assembly::new(String::new(), None),
+        None => spill::Assembly::new(std::mem::take(&mut combined_content), bundle.max_memory),
+    };
+    for slot in &mut slots {
+        match slot {
+            Ok(entry) if entry.dropped => {}
+            Ok(entry) => {
+                included_files += 1;
+                manifest.add(&entry.header, &entry.content);
+                included_paths.push(entry.header.clone());
+                file_tokens.push((entry.path.clone(), entry.tokens));
+                header_tokens.push((entry.header.clone(), entry.tokens));
+                scan(&entry.header, &entry.content);
+                assembly.push_str(&entry.render());
+                verbose!("Added {} ({} tokens)", entry.header, entry.tokens);
+                // Once on disk, the file's content is not needed again (--dry-run still sizes it)
+                if assembly.spilled() && !dry_run {
+                    entry.content = String::new();
+                }
+            }
+            Err(header) => {
+                denied += 1;
+                assembly.push_str(&placeholder(header));
+            }
+        }
+    }
+    assembly.push_str(&trailer);
+    
+    let footer = bundle
+        .footer
+        .as_ref()
+        .map(|footer| format::instructions_block(file_format, footer.replace("{{file_count}}", &included_files.to_string()).trim_end()));
+    if let Some(template) = &bundle.template {
+        let files = match assembly.finish()? {
+            spill::Assembled::Memory(files) => files,
+            spill::Assembled::Spilled(_) => unreachable!("an assembly without a limit stays in memory"),
+        };
+        let mut filled = BTreeMap::new();
+        filled.insert("sections", combined_content.split_off(tree_length));
+        filled.insert("tree", combined_content);
+        filled.insert("files", files);
+        filled.insert("file_count", included_files.to_string());
+        filled.insert("footer", footer.unwrap_or_default());
+        filled.insert("header", header.unwrap_or_default());
+        filled.insert("prompt", prompt.unwrap_or_default());
+        assembly = spill::Assembly::new(template.render(&filled), bundle.max_memory);
+    } else if !assembly.is_empty() {
+        // The request follows the files; guidance for the model rides at the very end, but stays
+        // covered by the provenance footer
+        for block in prompt.iter().chain(&footer) {
+            assembly.push_str(block);
+        }
+    }
+    
+    if let Some(settings) = &bundle.provenance {
+        if !assembly.is_empty() {
+            // The signature covers the whole bundle at once
+            let Some(content) = assembly.in_memory().or(settings.signing_key.is_none().then_some("")) else {
+                if let Ok(spill::Assembled::Spilled(spilled)) = assembly.finish() {
+                    spilled.discard();
+                }
+                return Err(Error::Config(
+                    "The bundle passed max_memory, so it can't be signed; raise max_memory or drop signing_key".to_string(),
+                ));
+            };
+            // Only a signing key can fail, and then the bundle is still in memory
+            let footer = provenance::footer(settings, manifest, content)?;
+            assembly.push_str(&footer);
+        }
+    }
+    
+    let assembled = assembly.finish()?;
+    let (bytes, tokens) = match &assembled {
+        spill::Assembled::Memory(content) => (content.len(), tokens::count(content)),
+        spill::Assembled::Spilled(spilled) => (spilled.bytes, spilled.tokens),
+    };
+    Ok(Rendered {
+        assembled,
+        bytes,
+        tokens,
+        slots,
+        included_files,
+        included_paths,
+        file_tokens,
+        header_tokens,
+        denied,
+        section_count,
+        pending_snippets,
+        findings,
+    })
+}
+
+fn copy_bundle(mut bundle: Bundle) -> Result<(), Error> {
+    if bundle.files.is_empty() && bundle.sections.is_empty() && bundle.project.is_none() {
+        if deadline::cut_short() {
+            porcelain_record("status", "error timeout");
+            return Err(timeout_error("nothing was collected"));
+        }
+        porcelain_record("status", "error empty");
+        return Err(Error::Empty("No files provided via config or directories".to_string()));
+    }
+    
+    // Every bundle is redacted and scanned here, whichever command built it
+    let protection = match bundle.protection.take() {
+        Some(protection) => protection,
+        None => Protection::from_config()?,
+    };
+    let redactor = redact::Redactor::new(protection.redaction, &protection.redaction_rules);
+    redact_sections(&mut bundle.sections, redactor.as_ref());
+    bundle.dry_run = DRY_RUN.load(Ordering::Relaxed);
+    let Rendered {
+        assembled,
+        bytes,
+        tokens,
+        slots,
+        included_files,
+        included_paths,
+        file_tokens,
+        header_tokens,
+        denied,
+        section_count,
+        pending_snippets,
+        findings,
+    } = render(&mut bundle, redactor.as_ref(), command_line_options(), protection.scan.is_some())?;
+    if let Some(redactor) = &redactor {
+        report_redactions(redactor, protection.redaction);
+    }
+    if matches!(&assembled, spill::Assembled::Memory(content) if content.is_empty()) {
+        if deadline::cut_short() {
+            porcelain_record("status", "error timeout");
+            return Err(timeout_error("nothing was collected"));
+        }
+        porcelain_record("status", "error empty");
+        return Err(Error::Empty("No valid files or project tree found to copy".to_string()));
+    }
+    
+    if let Some(mode) = protection.scan {
+        // Nothing leaves with --dry-run or --diff-output, so those only report
+        let delivering = !DRY_RUN.load(Ordering::Relaxed) && !bundle.diff_output;
+        if let Err(err) = report_secrets(mode, &findings, delivering) {
+            if let spill::Assembled::Spilled(spilled) = assembled {
+                spilled.discard();
+            }
+            porcelain_record("status", "error secrets");
+            return Err(err);
+        }
+    }
+    
+    if DRY_RUN.load(Ordering::Relaxed) {
+        print_dry_run(&slots, tokens, bytes);
+        if let spill::Assembled::Spilled(spilled) = assembled {
+            spilled.discard();
+        }
+        return Ok(());
+    }
+    
+    // A profile run arrives with --output or output_path filled in; modes take --output from here
+    let output_path = bundle.output_path.or_else(|| {
+        OUTPUT_PATH
+            .get()
+            .map(|path| output::expand_template(path, history::profile().unwrap_or("default")))
+    });
+    if bundle.diff_output {
+        let combined_content = match assembled {
+            spill::Assembled::Memory(content) => content,
+            spill::Assembled::Spilled(spilled) => {
+                spilled.discard();
+                return Err(Error::Config(
+                    "The bundle passed max_memory, too large to diff; raise max_memory to preview it".to_string(),
+                ));
+            }
+        };
+        let path = output_path
+            .as_ref()
+            .ok_or_else(|| Error::Config("--diff-output needs an output file (--output or output_path)".to_string()))?;
+        let diff = output::diff_against(path, &combined_content);
+        if diff.is_empty() {
+            eprintln!("{} is up to date", path.display());
+        } else {
+            print!("{}", diff);
+        }
+        return Ok(());
+    }
+    
+    // --stdout and --send-keys take the clipboard's place; configured files are still written
+    let stdout = STDOUT_BUNDLE.load(Ordering::Relaxed);
+    let send_keys = SEND_KEYS.get().cloned();
+    let outputs = output::Outputs {
+        clipboard: !bundle.skip_clipboard && !NO_CLIPBOARD.load(Ordering::Relaxed) && !stdout && send_keys.is_none(),
+        clipboard_fallback: bundle.clipboard_fallback,
+        clipboard_backend: CLIPBOARD_BACKEND.get().copied().unwrap_or(bundle.clipboard_backend),
+        file: output_path,
+        stdout,
+        send_keys,
+    };
+    if !outputs.clipboard && outputs.file.is_none() && !outputs.stdout && outputs.send_keys.is_none() {
+        if let spill::Assembled::Spilled(spilled) = assembled {
+            spilled.discard();
+        }
+        porcelain_record("status", "error output");
+        return Err(Error::Config(
+            "No outputs configured: set output_path, pass --stdout or --send-keys, or re-enable the clipboard".to_string(),
+        ));
+    }
+    
+    // Recorded for `fdllm stats`, counted before the content is handed over
+    let spilled = matches!(assembled, spill::Assembled::Spilled(_));
+    let (encodings, report) = match assembled {
+        spill::Assembled::Memory(content) => (tokens::count_all(&content, tokens), output::deliver(content, &outputs)),
+        spill::Assembled::Spilled(spilled) => {
+            info!(
+                "The bundle passed max_memory ({}) and was assembled on disk",
+                file_size::describe(bundle.max_memory.unwrap_or_default())
+            );
+            (spilled.encodings, output::deliver_spilled(&spilled.path, &outputs))
+        }
+    };
+    
+    // Snippets stay pending unless every output received them
+    if report.failed.is_empty() && !pending_snippets.is_empty() {
+        info!("Included {} collected snippet(s)", pending_snippets.len());
+        snippets::clear_pending();
+    }
+    
+    // A clipboard failure outranks a file failure: it is the output people wait on
+    let clipboard_failed = report.failed.iter().any(|failed| failed == "clipboard");
+    let failure = |message: String| {
+        if clipboard_failed {
+            Error::Clipboard(message)
+        } else {
+            Error::Output(message)
+        }
+    };
+    if report.delivered.is_empty() {
+        let kind = report.failed[0].split(' ').next().unwrap_or("output").to_string();
+        porcelain_record("status", format!("error {}", kind));
+        return Err(failure(format!("Nothing was delivered: {} failed", report.failed.join(", "))));
+    }
+    
+    let timed_out = deadline::cut_short();
+    if report.failed.is_empty() && timed_out {
+        porcelain_record("status", "partial timeout");
+    } else if report.failed.is_empty() {
+        porcelain_record("status", "ok");
+    } else {
+        porcelain_record("status", "error partial");
+    }
+    porcelain_record("files", included_files);
+    porcelain_record("sections", section_count);
+    porcelain_record("snippets", pending_snippets.len());
+    porcelain_record("denied", denied);
+    porcelain_record("bytes", bytes);
+    porcelain_record("tokens", tokens);
+    for delivered in &report.delivered {
+        porcelain_record("output", delivered);
+    }
+    for failed in &report.failed {
+        porcelain_record("failed", failed);
+    }
+    if report.clipboard_fallback {
+        porcelain_record("warning", "clipboard-fallback");
+    }
+    if spilled {
+        porcelain_record("warning", "spilled");
+    }
+    
+    history::record(tokens, encodings, &included_paths, bytes);
+    
+    tokens::summary(tokens, &header_tokens);
+    
+    if denied > 0 {
+        eprintln!("Skipped {} unreadable path(s) (permission denied); the bundle is incomplete", denied);
+    }
+    
+    if let Some(settings) = &bundle.prune {
+        prune::report(settings, &file_tokens);
+    }
+    
+    if !report.failed.is_empty() {
+        return Err(failure(format!(
+            "Delivered to {} but not to {}",
+            report.delivered.join(", "),
+            report.failed.join(", ")
+        )));
+    }
+    
+    if timed_out {
+        return Err(timeout_error("the bundle is partial"));
+    }
+    Ok(())
+}
+
+// What --timeout cut short, for a run that ends when it expired
+fn timeout_error(outcome: &str) -> Error {
+    Error::TimedOut(format!(
+        "Timed out after {:.1}s; {}:{}",
+        deadline::limit().unwrap_or_default().as_secs_f64(),
+        outcome,
+        deadline::skipped_report()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A fresh directory under the temp dir, unique to the test and this process. State (pending
+    // snippets, caches) goes to a scratch app directory too, never the real one.
+    fn scratch(name: &str) -> PathBuf {
+        let _ = PORTABLE_DIR.set(env::temp_dir().join(format!("fdllm-app-{}", std::process::id())));
+        let dir = env::temp_dir().join(format!("fdllm-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    // Forward slashes keep Windows paths valid inside TOML strings
+    fn toml_path(path: &Path) -> String {
+        path.display().to_string().replace('\\', "/")
+    }
+
+    #[test]
+    fn from_toml_expands_inheritance() {
+        let config = Config::from_toml(
+            r#"
+            [profiles.base]
+            files = ["a.rs"]
+            [profiles.child]
+            extends = "base"
+            files = ["b.rs"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.profile_names(), ["base", "child"]);
+        assert_eq!(config.profile("child").unwrap().files(), ["a.rs", "b.rs"]);
+        assert!(config.profile("missing").is_none());
+    }
+
+    #[test]
+    fn from_toml_reports_errors() {
+        assert!(Config::from_toml("files = [").is_err());
+        assert!(Config::from_toml("[profiles.a]\nfiles = 3").is_err());
+        let cycle = Config::from_toml("[profiles.a]\nextends = \"b\"\n[profiles.b]\nextends = \"a\"");
+        assert!(cycle.err().unwrap().to_string().contains("cycle"));
+    }
+
+    #[test]
+    fn collect_files_walks_directories() {
+        let dir = scratch("collect");
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join("src/main.rs"), "fn main() {}\n").unwrap();
+        fs::write(dir.join("src/lib.rs"), "pub fn run() {}\n").unwrap();
+        fs::write(dir.join("notes.md"), "# Notes\n").unwrap();
+        let config = Config::from_toml(&format!(
+            "[profiles.app]\nfiles = [\"{}\"]\ndirectories = [\"{}\"]\n",
+            toml_path(&dir.join("notes.md")),
+            toml_path(&dir.join("src")),
+        ))
+        .unwrap();
+
+        let mut files = collect_files(&config, Some("app"), &Options::default()).unwrap();
+        files.sort();
+        let expected = [dir.join("notes.md"), dir.join("src/lib.rs"), dir.join("src/main.rs")];
+        assert_eq!(files.len(), 3);
+        for (file, expected) in files.iter().zip(&expected) {
+            assert_eq!(fs::canonicalize(file).unwrap(), fs::canonicalize(expected).unwrap());
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn profiles_resolve_top_level_roots() {
+        let dir = scratch("roots");
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join("src/main.rs"), "fn main() {}\n").unwrap();
+        let config = Config::from_toml(&format!(
+            "[roots]\napp = \"{}\"\n[profiles.app]\nfiles = [\"app:src/main.rs\"]\n",
+            toml_path(&dir),
+        ))
+        .unwrap();
+
+        assert_eq!(collect_files(&config, Some("app"), &Options::default()).unwrap(), [dir.join("src/main.rs")]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn render_payload_lays_out_files_and_footer() {
+        let dir = scratch("render");
+        let file = dir.join("main.rs");
+        fs::write(&file, "fn main() {}\n").unwrap();
+        let config = Config::from_toml(&format!(
+            "[profiles.app]\nfiles = [\"{}\"]\nformat = \"markdown\"\nheader = \"Context for {{{{profile}}}}\"\nfooter = \"Review {{{{profile}}}}\"\n",
+            toml_path(&file),
+        ))
+        .unwrap();
+
+        let payload = render_payload(&config, Some("app"), &Options::default()).unwrap();
+        assert_eq!(payload.files.len(), 1);
+        assert!(payload.content.contains("```rust\nfn main() {}\n```"));
+        assert!(payload.content.starts_with("Context for app\n\n"));
+        assert!(payload.content.contains("Review app"));
+        assert_eq!(payload.tokens, tokens::count(&payload.content));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn deliver_writes_the_file_output() {
+        let dir = scratch("deliver");
+        let path = dir.join("nested/bundle.md");
+        let outputs = Outputs {
+            clipboard: false,
+            clipboard_fallback: ClipboardFallback::Error,
+            clipboard_backend: ClipboardBackend::Auto,
+            file: Some(path.clone()),
+            stdout: false,
+            send_keys: None,
+        };
+
+        let report = deliver("bundle\n".to_string(), &outputs);
+        assert_eq!(report.delivered, [format!("file {}", path.display())]);
+        assert!(report.failed.is_empty());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "bundle\n");
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
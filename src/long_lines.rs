@@ -0,0 +1,67 @@
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+// What to do with lines longer than `max_line_length`
+#[derive(Deserialize, JsonSchema, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LongLines {
+    // Keep the first `max_line_length` characters and note how much was dropped
+    Truncate,
+    // Split into `max_line_length` chunks, marking each continuation
+    Wrap,
+}
+
+// Byte offset of the `chars`-th character, or None when the line is shorter
+fn char_boundary(line: &str, chars: usize) -> Option<usize> {
+    line.char_indices().nth(chars).map(|(index, _)| index)
+}
+
+fn truncate(line: &str, max: usize, output: &mut String) {
+    match char_boundary(line, max) {
+        Some(cut) => {
+            let elided = line[cut..].chars().count();
+            output.push_str(&line[..cut]);
+            output.push_str(&format!(" … [fdllm: {} chars elided]", elided));
+        }
+        None => output.push_str(line),
+    }
+}
+
+fn wrap(line: &str, max: usize, output: &mut String) {
+    let mut rest = line;
+    let mut first = true;
+    loop {
+        if !first {
+            output.push_str("\n↪ ");
+        }
+        first = false;
+        match char_boundary(rest, max) {
+            Some(cut) => {
+                output.push_str(&rest[..cut]);
+                rest = &rest[cut..];
+            }
+            None => {
+                output.push_str(rest);
+                break;
+            }
+        }
+    }
+}
+
+pub fn limit_line_length(content: String, max: usize, mode: LongLines) -> String {
+    if max == 0 || !content.lines().any(|line| char_boundary(line, max).is_some()) {
+        return content;
+    }
+
+    let mut output = String::with_capacity(content.len());
+    for (index, line) in content.split('\n').enumerate() {
+        if index > 0 {
+            output.push('\n');
+        }
+        match mode {
+            LongLines::Truncate => truncate(line, max, &mut output),
+            LongLines::Wrap => wrap(line, max, &mut output),
+        }
+    }
+    output
+}
@@ -0,0 +1,164 @@
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::{collect_files_from_directory, copy_files, deadline, exit_codes, Error};
+
+#[derive(Deserialize)]
+struct Metadata {
+    packages: Vec<Package>,
+    workspace_root: String,
+}
+
+#[derive(Deserialize)]
+struct Package {
+    name: String,
+    manifest_path: String,
+    targets: Vec<Target>,
+}
+
+#[derive(Deserialize)]
+struct Target {
+    kind: Vec<String>,
+    src_path: String,
+}
+
+fn read_metadata(manifest_path: Option<&str>) -> Option<Metadata> {
+    let mut command = Command::new("cargo");
+    command
+        .arg("metadata")
+        .arg("--format-version")
+        .arg("1")
+        .arg("--no-deps");
+
+    if let Some(path) = manifest_path {
+        command.arg("--manifest-path").arg(path);
+    }
+
+    let output = deadline::output(&mut command).ok()?;
+
+    if !output.status.success() {
+        eprintln!(
+            "Failed to run cargo metadata:\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        return None;
+    }
+
+    match serde_json::from_slice(&output.stdout) {
+        Ok(metadata) => Some(metadata),
+        Err(err) => {
+            eprintln!("Failed to parse cargo metadata: {}", err);
+            None
+        }
+    }
+}
+
+// Files that describe a single workspace member: manifest, build script and src/
+fn package_files(package: &Package) -> Vec<PathBuf> {
+    let manifest_path = PathBuf::from(&package.manifest_path);
+    let mut files = vec![manifest_path.clone()];
+
+    for target in &package.targets {
+        if target.kind.iter().any(|kind| kind == "custom-build") {
+            files.push(PathBuf::from(&target.src_path));
+        }
+    }
+
+    let src_dir = manifest_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join("src");
+    if src_dir.is_dir() {
+        for file in collect_files_from_directory(&src_dir) {
+            if !files.contains(&file) {
+                files.push(file);
+            }
+        }
+    }
+
+    files
+}
+
+pub fn run(args: &[String]) -> Result<(), Error> {
+    let mut crate_names = Vec::new();
+    let mut manifest_path = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--crate" | "-p" => match iter.next() {
+                Some(name) => crate_names.push(name.clone()),
+                None => {
+                    eprintln!("Missing crate name after {}", arg);
+                    std::process::exit(exit_codes::CONFIG);
+                }
+            },
+            "--manifest-path" => match iter.next() {
+                Some(path) => manifest_path = Some(path.clone()),
+                None => {
+                    eprintln!("Missing path after --manifest-path");
+                    std::process::exit(exit_codes::CONFIG);
+                }
+            },
+            _ => {
+                eprintln!("Unknown argument for cargo mode: {}", arg);
+                eprintln!("Usage: fdllm cargo [--crate NAME]... [--manifest-path PATH]");
+                std::process::exit(exit_codes::CONFIG);
+            }
+        }
+    }
+
+    let metadata = match read_metadata(manifest_path.as_deref()) {
+        Some(metadata) => metadata,
+        None => std::process::exit(1),
+    };
+
+    // Without --crate every workspace member is included
+    let selected: Vec<&Package> = if crate_names.is_empty() {
+        metadata.packages.iter().collect()
+    } else {
+        let mut selected = Vec::new();
+        for name in &crate_names {
+            match metadata.packages.iter().find(|package| &package.name == name) {
+                Some(package) => selected.push(package),
+                None => {
+                    let members: Vec<&str> = metadata
+                        .packages
+                        .iter()
+                        .map(|package| package.name.as_str())
+                        .collect();
+                    eprintln!(
+                        "Crate '{}' is not a workspace member (available: {})",
+                        name,
+                        members.join(", ")
+                    );
+                    std::process::exit(exit_codes::CONFIG);
+                }
+            }
+        }
+        selected
+    };
+
+    info!("Using cargo workspace: {}", metadata.workspace_root);
+
+    let mut files_to_copy: Vec<String> = Vec::new();
+
+    // The root manifest carries shared dependencies and workspace settings
+    let root_manifest = Path::new(&metadata.workspace_root).join("Cargo.toml");
+    if root_manifest.is_file() {
+        files_to_copy.push(root_manifest.to_string_lossy().to_string());
+    }
+
+    for package in selected {
+        info!("Including crate: {}", package.name);
+        for file in package_files(package) {
+            let file = file.to_string_lossy().to_string();
+            if !files_to_copy.contains(&file) {
+                files_to_copy.push(file);
+            }
+        }
+    }
+
+    copy_files(files_to_copy, None)
+}
@@ -0,0 +1,132 @@
+use std::path::Path;
+
+use crate::git::run_git;
+use crate::{copy_bundle, exit_codes, Bundle, Error, Section};
+
+struct Change {
+    status: char,
+    old_path: Option<String>,
+    new_path: Option<String>,
+}
+
+// `git diff --name-status` lines: `M\tpath`, `A\tpath`, `D\tpath`, `R087\told\tnew`
+fn parse_name_status(output: &str) -> Vec<Change> {
+    let mut changes = Vec::new();
+    for line in output.lines() {
+        let mut fields = line.split('\t');
+        let status = match fields.next().and_then(|status| status.chars().next()) {
+            Some(status) => status,
+            None => continue,
+        };
+        let first = fields.next().map(str::to_string);
+        let second = fields.next().map(str::to_string);
+        let change = match status {
+            'A' => Change { status, old_path: None, new_path: first },
+            'D' => Change { status, old_path: first, new_path: None },
+            'R' | 'C' => Change { status, old_path: first, new_path: second },
+            _ => Change { status, old_path: first.clone(), new_path: first },
+        };
+        changes.push(change);
+    }
+    changes
+}
+
+fn show(rev: &str, path: &str) -> String {
+    // Paths from --name-status are relative to the repository root, as `REV:path` expects
+    match run_git(Path::new("."), &["show", &format!("{}:{}", rev, path)]) {
+        Ok(content) => content,
+        Err(err) => {
+            eprintln!("Failed to read {} at {}: {}", path, rev, err);
+            std::process::exit(1);
+        }
+    }
+}
+
+pub fn run(args: &[String]) -> Result<(), Error> {
+    let mut refs = Vec::new();
+    let mut paths = Vec::new();
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--path" => match iter.next() {
+                Some(path) => paths.push(path.clone()),
+                None => {
+                    eprintln!("Missing path after --path");
+                    std::process::exit(exit_codes::CONFIG);
+                }
+            },
+            _ if !arg.starts_with('-') && refs.len() < 2 => refs.push(arg.clone()),
+            _ => {
+                eprintln!("Unknown argument for compare mode: {}", arg);
+                eprintln!("Usage: fdllm compare REF1 REF2 [--path PATH]...");
+                std::process::exit(exit_codes::CONFIG);
+            }
+        }
+    }
+
+    let (old_ref, new_ref) = match refs.as_slice() {
+        [old_ref, new_ref] => (old_ref.as_str(), new_ref.as_str()),
+        _ => {
+            eprintln!("Usage: fdllm compare REF1 REF2 [--path PATH]...");
+            std::process::exit(exit_codes::CONFIG);
+        }
+    };
+
+    let mut diff_args = vec!["diff", "-M", old_ref, new_ref, "--"];
+    diff_args.extend(paths.iter().map(String::as_str));
+
+    let mut name_status_args = vec!["diff", "--name-status", "-M", old_ref, new_ref, "--"];
+    name_status_args.extend(paths.iter().map(String::as_str));
+
+    let changes = match run_git(Path::new("."), &name_status_args) {
+        Ok(output) => parse_name_status(&output),
+        Err(err) => {
+            eprintln!("git diff failed: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    if changes.is_empty() {
+        eprintln!("No changes between {} and {}", old_ref, new_ref);
+        std::process::exit(exit_codes::EMPTY);
+    }
+
+    info!("Comparing {}..{}: {} changed file(s)", old_ref, new_ref, changes.len());
+
+    let diff = run_git(Path::new("."), &diff_args).unwrap_or_else(|err| {
+        eprintln!("git diff failed: {}", err);
+        std::process::exit(1);
+    });
+
+    let mut sections = vec![Section {
+        title: format!("Diff {}..{}", old_ref, new_ref),
+        body: diff,
+    }];
+
+    // Each file's OLD version directly followed by its NEW version
+    for change in &changes {
+        if let Some(old_path) = &change.old_path {
+            sections.push(Section {
+                title: format!("OLD ({}): {}", old_ref, old_path),
+                body: show(old_ref, old_path),
+            });
+        }
+        if let Some(new_path) = &change.new_path {
+            sections.push(Section {
+                title: format!("NEW ({}): {}", new_ref, new_path),
+                body: show(new_ref, new_path),
+            });
+        }
+        if change.status == 'D' {
+            info!("Deleted in {}: {}", new_ref, change.old_path.as_deref().unwrap_or_default());
+        } else if change.status == 'A' {
+            info!("Added in {}: {}", new_ref, change.new_path.as_deref().unwrap_or_default());
+        }
+    }
+
+    copy_bundle(Bundle {
+        sections,
+        ..Default::default()
+    })
+}
@@ -0,0 +1,157 @@
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::{collect_files_from_directory, copy_bundle, exit_codes, home_dir, roots, Bundle, Error};
+
+#[derive(Deserialize)]
+struct Lockfile {
+    #[serde(default)]
+    package: Vec<LockedPackage>,
+}
+
+#[derive(Deserialize)]
+struct LockedPackage {
+    name: String,
+    version: String,
+    source: Option<String>,
+}
+
+// The nearest Cargo.lock at or above the working directory
+fn find_lockfile() -> Option<PathBuf> {
+    let cwd = env::current_dir().ok()?;
+    cwd.ancestors()
+        .map(|dir| dir.join("Cargo.lock"))
+        .find(|path| path.is_file())
+}
+
+fn cargo_home() -> Option<PathBuf> {
+    env::var_os("CARGO_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| home_dir().map(|home| home.join(".cargo")))
+}
+
+// Unpacked sources live in registry/src/<index>/<name>-<version>, one directory per index
+fn registry_source(name: &str, version: &str) -> Option<PathBuf> {
+    let src = cargo_home()?.join("registry").join("src");
+    let dir_name = format!("{}-{}", name, version);
+    fs::read_dir(src)
+        .ok()?
+        .flatten()
+        .map(|index| index.path().join(&dir_name))
+        .find(|path| path.is_dir())
+}
+
+pub fn run(args: &[String]) -> Result<(), Error> {
+    let usage = "Usage: fdllm dep <crate> [--version VERSION]";
+    let mut name = None;
+    let mut version = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--version" => match iter.next() {
+                Some(value) => version = Some(value.as_str()),
+                None => {
+                    eprintln!("--version expects a version from Cargo.lock");
+                    std::process::exit(exit_codes::CONFIG);
+                }
+            },
+            _ if !arg.starts_with('-') && name.is_none() => name = Some(arg.as_str()),
+            _ => {
+                eprintln!("{}", usage);
+                std::process::exit(exit_codes::CONFIG);
+            }
+        }
+    }
+    let name = match name {
+        Some(name) => name,
+        None => {
+            eprintln!("{}", usage);
+            std::process::exit(exit_codes::CONFIG);
+        }
+    };
+
+    let lockfile_path = match find_lockfile() {
+        Some(path) => path,
+        None => {
+            eprintln!("No Cargo.lock found in this directory or its parents");
+            std::process::exit(1);
+        }
+    };
+    let lockfile: Lockfile = match fs::read_to_string(&lockfile_path)
+        .map_err(|err| err.to_string())
+        .and_then(|content| toml::from_str(&content).map_err(|err| err.to_string()))
+    {
+        Ok(lockfile) => lockfile,
+        Err(err) => {
+            eprintln!("Failed to read {}: {}", lockfile_path.display(), err);
+            std::process::exit(1);
+        }
+    };
+
+    // Crates and their lockfile entries may spell the name with `-` or `_`
+    let normalized = name.replace('-', "_");
+    let candidates: Vec<&LockedPackage> = lockfile
+        .package
+        .iter()
+        .filter(|package| package.name.replace('-', "_") == normalized)
+        .filter(|package| version.is_none_or(|version| package.version == version))
+        .collect();
+    let package = match candidates.as_slice() {
+        [package] => *package,
+        [] => {
+            eprintln!("'{}' is not in {}", name, lockfile_path.display());
+            std::process::exit(exit_codes::CONFIG);
+        }
+        _ => {
+            let versions: Vec<&str> = candidates.iter().map(|package| package.version.as_str()).collect();
+            eprintln!(
+                "Cargo.lock has several versions of '{}' ({}); pick one with --version",
+                name,
+                versions.join(", ")
+            );
+            std::process::exit(exit_codes::CONFIG);
+        }
+    };
+
+    match package.source.as_deref() {
+        Some(source) if source.starts_with("registry+") || source.starts_with("sparse+") => {}
+        Some(source) => {
+            eprintln!("'{}' comes from {}; only registry crates are supported", package.name, source);
+            std::process::exit(exit_codes::CONFIG);
+        }
+        None => {
+            eprintln!("'{}' is a path or workspace crate; bundle it as a profile instead", package.name);
+            std::process::exit(exit_codes::CONFIG);
+        }
+    }
+
+    let source_dir = match registry_source(&package.name, &package.version) {
+        Some(dir) => dir,
+        None => {
+            eprintln!(
+                "Source for {} {} is not unpacked in the cargo registry; run `cargo build` first",
+                package.name, package.version
+            );
+            std::process::exit(1);
+        }
+    };
+    info!("Bundling {} {} from {}", package.name, package.version, source_dir.display());
+
+    let files: Vec<String> = collect_files_from_directory(&source_dir)
+        .iter()
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+
+    // Headers read `serde-1.0.200/src/lib.rs` instead of the full registry path
+    let label = format!("{}-{}", package.name, package.version);
+    let dep_root = BTreeMap::from([(label, source_dir.to_string_lossy().to_string())]);
+    copy_bundle(Bundle {
+        files,
+        roots: roots::load_roots(Some(&dep_root)),
+        ..Default::default()
+    })
+}
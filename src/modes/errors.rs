@@ -0,0 +1,167 @@
+use crate::diagnostics::{excerpt_sections, find_locations, push_location, run_command, Location};
+use crate::{copy_bundle, exit_codes, Bundle, Error, Section};
+
+const DEFAULT_CONTEXT: usize = 10;
+
+// Cargo subcommands that accept --message-format
+const CARGO_JSON_SUBCOMMANDS: [&str; 5] = ["build", "check", "clippy", "test", "run"];
+
+struct Diagnostics {
+    text: String,
+    locations: Vec<Location>,
+}
+
+// Ask cargo for JSON diagnostics unless the user already chose a message format
+fn with_json_messages(command_line: &[String]) -> Vec<String> {
+    let mut command_line = command_line.to_vec();
+    let is_cargo = command_line.first().map(|program| program == "cargo").unwrap_or(false);
+    if is_cargo
+        && command_line.len() > 1
+        && CARGO_JSON_SUBCOMMANDS.contains(&command_line[1].as_str())
+        && !command_line.iter().any(|arg| arg.starts_with("--message-format"))
+    {
+        command_line.insert(2, "--message-format=json".to_string());
+    }
+    command_line
+}
+
+// rustc JSON messages carry the rendered text plus exact primary spans
+fn parse_rustc_json(stdout: &str) -> Option<Diagnostics> {
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+
+    for line in stdout.lines() {
+        let value: serde_json::Value = match serde_json::from_str(line) {
+            Ok(value) => value,
+            Err(_) => continue,
+        };
+        if value.get("reason").and_then(|reason| reason.as_str()) != Some("compiler-message") {
+            continue;
+        }
+        let message = match value.get("message") {
+            Some(message) => message,
+            None => continue,
+        };
+        let level = message.get("level").and_then(|level| level.as_str()).unwrap_or_default();
+        let rendered = message
+            .get("rendered")
+            .and_then(|rendered| rendered.as_str())
+            .unwrap_or_default()
+            .to_string();
+
+        let mut locations = Vec::new();
+        if let Some(spans) = message.get("spans").and_then(|spans| spans.as_array()) {
+            for span in spans {
+                if let (Some(file), Some(line)) = (
+                    span.get("file_name").and_then(|file| file.as_str()),
+                    span.get("line_start").and_then(|line| line.as_u64()),
+                ) {
+                    push_location(&mut locations, file, &line.to_string());
+                }
+            }
+        }
+
+        match level {
+            "error" | "error: internal compiler error" => errors.push((rendered, locations)),
+            "warning" => warnings.push((rendered, locations)),
+            _ => {}
+        }
+    }
+
+    // Warnings only matter when there is nothing worse to fix
+    let messages = if errors.is_empty() { warnings } else { errors };
+    if messages.is_empty() {
+        return None;
+    }
+
+    let mut text = String::new();
+    let mut locations: Vec<Location> = Vec::new();
+    for (rendered, message_locations) in messages {
+        text.push_str(&rendered);
+        for location in message_locations {
+            if !locations.contains(&location) {
+                locations.push(location);
+            }
+        }
+    }
+
+    Some(Diagnostics { text, locations })
+}
+
+fn parse_text_output(stdout: &str, stderr: &str) -> Diagnostics {
+    let mut text = String::new();
+    text.push_str(stderr);
+    // Leftover cargo JSON records (build-finished, artifacts) are noise here
+    let stdout: String = stdout
+        .lines()
+        .filter(|line| !line.starts_with("{\"reason\""))
+        .map(|line| format!("{}\n", line))
+        .collect();
+    if !stdout.trim().is_empty() {
+        if !text.is_empty() && !text.ends_with('\n') {
+            text.push('\n');
+        }
+        text.push_str(&stdout);
+    }
+    let locations = find_locations(&text);
+    Diagnostics { text, locations }
+}
+
+pub fn run(args: &[String]) -> Result<(), Error> {
+    let mut context = DEFAULT_CONTEXT;
+    let mut command_line: Vec<String> = Vec::new();
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--context" | "-C" => match iter.next().and_then(|value| value.parse().ok()) {
+                Some(value) => context = value,
+                None => {
+                    eprintln!("Expected a number of lines after {}", arg);
+                    std::process::exit(exit_codes::CONFIG);
+                }
+            },
+            "--" => {
+                command_line.extend(iter.by_ref().cloned());
+            }
+            _ => {
+                command_line.push(arg.clone());
+                command_line.extend(iter.by_ref().cloned());
+            }
+        }
+    }
+
+    if command_line.is_empty() {
+        eprintln!("Usage: fdllm errors [--context N] -- <command> [args...]");
+        std::process::exit(exit_codes::CONFIG);
+    }
+
+    let command_line = with_json_messages(&command_line);
+    let output = run_command(&command_line);
+
+    let diagnostics = match parse_rustc_json(&output.stdout) {
+        Some(diagnostics) => diagnostics,
+        None => parse_text_output(&output.stdout, &output.stderr),
+    };
+
+    if output.success && diagnostics.locations.is_empty() {
+        info!("Command succeeded without diagnostics; nothing to bundle");
+        return Ok(());
+    }
+
+    info!(
+        "Found {} referenced location(s) in command output",
+        diagnostics.locations.len()
+    );
+
+    let mut sections = vec![Section {
+        title: format!("Output of `{}`", command_line.join(" ")),
+        body: diagnostics.text,
+    }];
+    sections.extend(excerpt_sections(&diagnostics.locations, context));
+
+    copy_bundle(Bundle {
+        sections,
+        ..Default::default()
+    })
+}
@@ -0,0 +1,249 @@
+use regex::Regex;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::diagnostics::{find_locations, project_files, resolve_project_file, run_command};
+use crate::{copy_bundle, exit_codes, Bundle, Error, Section};
+
+// Only the tail of very long test logs is kept
+const MAX_OUTPUT_LINES: usize = 2000;
+
+// Heuristic source matches per failing test file
+const MAX_SOURCES_PER_TEST: usize = 3;
+
+struct FailingTest {
+    name: String,
+    file: Option<PathBuf>,
+}
+
+// `Running unittests src/lib.rs (target/...)` / `Running tests/api.rs (target/...)`
+fn cargo_binary(line: &str) -> Option<PathBuf> {
+    let rest = line.trim().strip_prefix("Running ")?;
+    let rest = rest.strip_prefix("unittests ").unwrap_or(rest);
+    let path = rest.split(" (").next()?;
+    resolve_project_file(path)
+}
+
+// Maps `foo::bar::tests::it_works` to src/foo/bar.rs (or bar/mod.rs) below the binary's root
+fn rust_test_file(name: &str, binary: Option<&Path>) -> Option<PathBuf> {
+    let binary = binary?;
+    if binary.starts_with("tests") || binary.components().any(|part| part.as_os_str() == "tests") {
+        return Some(binary.to_path_buf());
+    }
+
+    let base = binary.parent().unwrap_or_else(|| Path::new(""));
+    let modules: Vec<&str> = name.split("::").collect();
+    for len in (1..modules.len()).rev() {
+        let module_path: PathBuf = modules[..len].iter().collect();
+        let file = base.join(&module_path).with_extension("rs");
+        if file.is_file() {
+            return Some(file);
+        }
+        let mod_file = base.join(&module_path).join("mod.rs");
+        if mod_file.is_file() {
+            return Some(mod_file);
+        }
+    }
+    Some(binary.to_path_buf())
+}
+
+fn cargo_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"^test (\S+) \.\.\. FAILED").expect("valid cargo test pattern"))
+}
+
+fn pytest_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"^FAILED (\S+?\.py)::(\S+)").expect("valid pytest pattern"))
+}
+
+fn jest_file_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"^\s*FAIL\s+(\S+)").expect("valid jest pattern"))
+}
+
+fn go_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"^\s*--- FAIL: (\S+)").expect("valid go test pattern"))
+}
+
+fn parse_failures(output: &str) -> Vec<FailingTest> {
+    let mut failures: Vec<FailingTest> = Vec::new();
+    let mut current_binary: Option<PathBuf> = None;
+
+    for line in output.lines() {
+        if let Some(binary) = cargo_binary(line) {
+            current_binary = Some(binary);
+        } else if let Some(caps) = cargo_pattern().captures(line) {
+            failures.push(FailingTest {
+                name: caps[1].to_string(),
+                file: rust_test_file(&caps[1], current_binary.as_deref()),
+            });
+        } else if let Some(caps) = pytest_pattern().captures(line) {
+            failures.push(FailingTest {
+                name: format!("{}::{}", &caps[1], &caps[2]),
+                file: resolve_project_file(&caps[1]),
+            });
+        } else if let Some(caps) = go_pattern().captures(line) {
+            failures.push(FailingTest {
+                name: caps[1].to_string(),
+                file: None,
+            });
+        } else if let Some(caps) = jest_file_pattern().captures(line) {
+            if let Some(file) = resolve_project_file(&caps[1]) {
+                failures.push(FailingTest {
+                    name: caps[1].to_string(),
+                    file: Some(file),
+                });
+            }
+        }
+    }
+
+    failures
+}
+
+fn is_test_file(path: &Path) -> bool {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    stem.starts_with("test_")
+        || stem.ends_with("_test")
+        || stem.ends_with("_tests")
+        || name.contains(".test.")
+        || name.contains(".spec.")
+        || path.components().any(|part| {
+            let part = part.as_os_str();
+            part == "tests" || part == "__tests__"
+        })
+}
+
+// Source file stems a test file most likely exercises: test_foo.py -> foo, foo.spec.ts -> foo
+fn source_stem(test_file: &Path) -> Option<String> {
+    let name = test_file.file_name()?.to_string_lossy().to_string();
+    let stem = name.split('.').next()?.to_string();
+    let stem = stem
+        .strip_prefix("test_")
+        .or_else(|| stem.strip_suffix("_tests"))
+        .or_else(|| stem.strip_suffix("_test"))
+        .unwrap_or(&stem)
+        .to_string();
+    if stem.is_empty() {
+        None
+    } else {
+        Some(stem)
+    }
+}
+
+fn matching_sources(test_file: &Path, project: &[PathBuf]) -> Vec<PathBuf> {
+    let stem = match source_stem(test_file) {
+        Some(stem) => stem,
+        None => return Vec::new(),
+    };
+    let extension = test_file.extension();
+
+    let mut matches: Vec<&PathBuf> = project
+        .iter()
+        .filter(|path| path.as_path() != test_file && !is_test_file(path))
+        .filter(|path| path.extension() == extension)
+        .filter(|path| {
+            let file_stem = path.file_stem().unwrap_or_default().to_string_lossy();
+            let parent_name = path
+                .parent()
+                .and_then(|parent| parent.file_name())
+                .unwrap_or_default()
+                .to_string_lossy();
+            // src/foo.rs or src/foo/mod.rs, foo/index.ts
+            file_stem == stem.as_str()
+                || (parent_name == stem.as_str() && (file_stem == "mod" || file_stem == "index" || file_stem == "__init__"))
+        })
+        .collect();
+
+    // Prefer sources next to the test
+    let test_dir = test_file.parent();
+    matches.sort_by_key(|path| path.parent() != test_dir);
+    matches.into_iter().take(MAX_SOURCES_PER_TEST).cloned().collect()
+}
+
+// cargo prints failure details between the first `---- name stdout ----` and the summary list
+fn failure_details(output: &str) -> String {
+    if let Some(start) = output.find("\n---- ") {
+        let details = &output[start + 1..];
+        let end = details.find("\nfailures:\n").map(|end| end + 1).unwrap_or(details.len());
+        return details[..end].to_string();
+    }
+
+    let lines: Vec<&str> = output.lines().collect();
+    let start = lines.len().saturating_sub(MAX_OUTPUT_LINES);
+    let mut tail = lines[start..].join("\n");
+    tail.push('\n');
+    tail
+}
+
+fn push_unique(files: &mut Vec<PathBuf>, path: PathBuf) {
+    if !files.contains(&path) {
+        files.push(path);
+    }
+}
+
+pub fn run(args: &[String]) -> Result<(), Error> {
+    let command_line: Vec<String> = match args.first().map(String::as_str) {
+        Some("--") => args[1..].to_vec(),
+        _ => args.to_vec(),
+    };
+
+    if command_line.is_empty() {
+        eprintln!("Usage: fdllm failing -- <test command> [args...]");
+        std::process::exit(exit_codes::CONFIG);
+    }
+
+    let output = run_command(&command_line);
+    let combined = format!("{}\n{}", output.stdout, output.stderr);
+    let failures = parse_failures(&combined);
+
+    if failures.is_empty() {
+        if output.success {
+            info!("All tests passed; nothing to bundle");
+            return Ok(());
+        }
+        eprintln!("Test command failed but no failing tests were recognised; bundling its output");
+    } else {
+        info!("Found {} failing test(s)", failures.len());
+    }
+
+    let mut files: Vec<PathBuf> = Vec::new();
+
+    for failure in &failures {
+        if let Some(file) = &failure.file {
+            push_unique(&mut files, file.clone());
+        }
+    }
+
+    // Panics, assertion locations and tracebacks point straight at the code under test
+    for location in find_locations(&combined) {
+        push_unique(&mut files, location.path);
+    }
+
+    let project = project_files();
+    for test_file in files.clone().iter().filter(|file| is_test_file(file)) {
+        for source in matching_sources(test_file, &project) {
+            push_unique(&mut files, source);
+        }
+    }
+
+    let mut sections = Vec::new();
+    if !failures.is_empty() {
+        sections.push(Section {
+            title: "Failing tests".to_string(),
+            body: failures.iter().map(|failure| format!("- {}\n", failure.name)).collect(),
+        });
+    }
+    sections.push(Section {
+        title: format!("Output of `{}`", command_line.join(" ")),
+        body: failure_details(&combined),
+    });
+
+    copy_bundle(Bundle {
+        files: files.iter().map(|file| file.to_string_lossy().to_string()).collect(),
+        sections,
+        ..Default::default()
+    })
+}
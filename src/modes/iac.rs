@@ -0,0 +1,406 @@
+use regex::Regex;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::{copy_bundle, exit_codes, expand_tilde, Bundle, Error};
+
+// Provider caches and state are not source, and state files are full of secrets
+const IAC_EXCLUDED: [&str; 3] = [".terraform", ".git", "terraform.tfstate.d"];
+
+const REDACTED: &str = "\"<redacted>\"";
+
+// Variable names that are treated as sensitive even without `sensitive = true`
+const SECRET_NAME_HINTS: [&str; 7] = [
+    "password",
+    "passwd",
+    "secret",
+    "token",
+    "private_key",
+    "access_key",
+    "credential",
+];
+
+fn source_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r#"(?m)^\s*source\s*=\s*"(\.\.?/[^"]*)""#).expect("valid source pattern"))
+}
+
+fn variable_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r#"(?m)^\s*variable\s+"([^"]+)"\s*\{"#).expect("valid variable pattern"))
+}
+
+fn sensitive_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"(?m)^\s*sensitive\s*=\s*true\b").expect("valid sensitive pattern"))
+}
+
+fn assignment_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r"^(\s*)([A-Za-z_][A-Za-z0-9_-]*)(\s*=\s*)(.*)$").expect("valid assignment pattern")
+    })
+}
+
+fn is_iac_file(path: &Path) -> bool {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    name.ends_with(".tf") || name.ends_with(".tfvars") || name.ends_with(".tf.json")
+}
+
+fn collect_iac_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let mut entries: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries.flatten().map(|entry| entry.path()).collect(),
+        Err(err) => {
+            eprintln!("Failed to read directory {}: {}", dir.display(), err);
+            return;
+        }
+    };
+    entries.sort();
+
+    for path in entries {
+        let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        if IAC_EXCLUDED.contains(&name.as_str()) {
+            continue;
+        }
+        if path.is_dir() {
+            collect_iac_files(&path, files);
+        } else if is_iac_file(&path) && !files.contains(&path) {
+            files.push(path);
+        }
+    }
+}
+
+// Local module sources (`source = "./modules/vpc"`) referenced from a .tf file
+fn local_module_dirs(tf_file: &Path, content: &str) -> Vec<PathBuf> {
+    let base = tf_file.parent().unwrap_or_else(|| Path::new("."));
+    source_pattern()
+        .captures_iter(content)
+        .map(|caps| base.join(&caps[1]))
+        .filter(|dir| dir.is_dir())
+        .collect()
+}
+
+// Byte ranges of `variable "name" { ... }` bodies, with the variable name
+fn variable_blocks(content: &str) -> Vec<(String, usize, usize)> {
+    let mut blocks = Vec::new();
+
+    for caps in variable_pattern().captures_iter(content) {
+        let start = caps.get(0).unwrap().end();
+        let mut depth = 1;
+        let mut end = content.len();
+        for (offset, ch) in content[start..].char_indices() {
+            match ch {
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        end = start + offset;
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+        blocks.push((caps[1].to_string(), start, end));
+    }
+
+    blocks
+}
+
+// `{"variable": {"name": {"sensitive": true, "default": ...}}}` in a .tf.json file
+fn json_variables(document: &mut Value) -> Option<&mut serde_json::Map<String, Value>> {
+    document.get_mut("variable")?.as_object_mut()
+}
+
+fn sensitive_variables(files: &[PathBuf]) -> HashSet<String> {
+    let mut names = HashSet::new();
+
+    for file in files {
+        let Ok(content) = fs::read_to_string(file) else {
+            continue;
+        };
+        let file_name = file.to_string_lossy();
+        if file_name.ends_with(".tf.json") {
+            let Ok(mut document) = serde_json::from_str::<Value>(&content) else {
+                continue;
+            };
+            for (name, body) in json_variables(&mut document).into_iter().flatten() {
+                if body.get("sensitive") == Some(&Value::Bool(true)) {
+                    names.insert(name.clone());
+                }
+            }
+        } else if file_name.ends_with(".tf") {
+            for (name, start, end) in variable_blocks(&content) {
+                if sensitive_pattern().is_match(&content[start..end]) {
+                    names.insert(name);
+                }
+            }
+        }
+    }
+
+    names
+}
+
+fn looks_secret(name: &str) -> bool {
+    let name = name.to_lowercase();
+    SECRET_NAME_HINTS.iter().any(|hint| name.contains(hint))
+}
+
+fn bracket_balance(line: &str) -> i32 {
+    let mut balance = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    for ch in line.chars() {
+        if in_string {
+            match ch {
+                '\\' if !escaped => {
+                    escaped = true;
+                    continue;
+                }
+                '"' if !escaped => in_string = false,
+                _ => {}
+            }
+            escaped = false;
+            continue;
+        }
+        match ch {
+            '"' => in_string = true,
+            '[' | '{' | '(' => balance += 1,
+            ']' | '}' | ')' => balance -= 1,
+            '#' => break,
+            _ => {}
+        }
+    }
+    balance
+}
+
+// Replaces the value of every top-level `name = value` assignment selected by
+// `should_redact`, including multi-line lists/maps and heredocs
+fn redact_assignments(content: &str, should_redact: impl Fn(&str) -> bool) -> (String, usize) {
+    let mut output = Vec::new();
+    let mut redactions = 0;
+    let mut depth = 0;
+    let mut lines = content.lines();
+
+    while let Some(line) = lines.next() {
+        let caps = match assignment_pattern().captures(line) {
+            Some(caps) if depth == 0 && should_redact(&caps[2]) => caps,
+            _ => {
+                depth += bracket_balance(line);
+                output.push(line.to_string());
+                continue;
+            }
+        };
+
+        output.push(format!("{}{}{}{}", &caps[1], &caps[2], &caps[3], REDACTED));
+        redactions += 1;
+
+        // Skip the rest of the value when it spans several lines
+        let value = caps[4].trim();
+        if let Some(marker) = value.strip_prefix("<<") {
+            let terminator = marker.trim_start_matches('-').trim();
+            for line in lines.by_ref() {
+                if line.trim() == terminator {
+                    break;
+                }
+            }
+        } else {
+            let mut balance = bracket_balance(value);
+            while balance > 0 {
+                match lines.next() {
+                    Some(line) => balance += bracket_balance(line),
+                    None => break,
+                }
+            }
+        }
+    }
+
+    let mut redacted = output.join("\n");
+    if content.ends_with('\n') {
+        redacted.push('\n');
+    }
+    (redacted, redactions)
+}
+
+// Redacts defaults of sensitive variables inside `variable` blocks
+fn redact_variable_defaults(content: &str, sensitive: &HashSet<String>) -> (String, usize) {
+    let mut output = String::new();
+    let mut redactions = 0;
+    let mut last = 0;
+
+    for (name, start, end) in variable_blocks(content) {
+        if !sensitive.contains(&name) {
+            continue;
+        }
+        let (body, count) = redact_assignments(&content[start..end], |key| key == "default");
+        output.push_str(&content[last..start]);
+        output.push_str(&body);
+        redactions += count;
+        last = end;
+    }
+    output.push_str(&content[last..]);
+
+    (output, redactions)
+}
+
+// The same for a .tf.json file; a file that doesn't parse is left as it is
+fn redact_json_defaults(content: String, sensitive: &HashSet<String>) -> (String, usize) {
+    let Ok(mut document) = serde_json::from_str::<Value>(&content) else {
+        return (content, 0);
+    };
+    let mut redactions = 0;
+    for (name, body) in json_variables(&mut document).into_iter().flatten() {
+        if let Some(default) = body.get_mut("default").filter(|_| sensitive.contains(name)) {
+            *default = Value::String("<redacted>".to_string());
+            redactions += 1;
+        }
+    }
+    if redactions == 0 {
+        return (content, 0);
+    }
+
+    let mut redacted = serde_json::to_string_pretty(&document).expect("a parsed document serializes");
+    if content.ends_with('\n') {
+        redacted.push('\n');
+    }
+    (redacted, redactions)
+}
+
+pub fn run(args: &[String]) -> Result<(), Error> {
+    let mut root = PathBuf::from(".");
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--root" => match iter.next() {
+                Some(path) => root = expand_tilde(path),
+                None => {
+                    eprintln!("Missing path after --root");
+                    std::process::exit(exit_codes::CONFIG);
+                }
+            },
+            _ => {
+                eprintln!("Unknown argument for iac mode: {}", arg);
+                eprintln!("Usage: fdllm iac [--root PATH]");
+                std::process::exit(exit_codes::CONFIG);
+            }
+        }
+    }
+
+    if !root.is_dir() {
+        eprintln!("Directory not found or not a directory: {}", root.display());
+        std::process::exit(exit_codes::CONFIG);
+    }
+
+    let mut files = Vec::new();
+    collect_iac_files(&root, &mut files);
+
+    // Follow local module sources that live outside the root (e.g. ../modules)
+    let mut index = 0;
+    while index < files.len() {
+        let file = files[index].clone();
+        if let Ok(content) = fs::read_to_string(&file) {
+            for dir in local_module_dirs(&file, &content) {
+                let already_included = files
+                    .iter()
+                    .any(|included| included.parent() == Some(dir.as_path()));
+                if !already_included {
+                    info!("Including module: {}", dir.display());
+                    collect_iac_files(&dir, &mut files);
+                }
+            }
+        }
+        index += 1;
+    }
+
+    let sensitive = sensitive_variables(&files);
+    info!(
+        "Found {} Terraform files ({} sensitive variables)",
+        files.len(),
+        sensitive.len()
+    );
+
+    let files_to_copy = files
+        .iter()
+        .map(|file| file.to_string_lossy().to_string())
+        .collect();
+
+    let transform = move |path: &Path, content: String| {
+        let name = path.to_string_lossy();
+        let (content, redactions) = if name.ends_with(".tfvars") {
+            redact_assignments(&content, |key| sensitive.contains(key) || looks_secret(key))
+        } else if name.ends_with(".tf") {
+            redact_variable_defaults(&content, &sensitive)
+        } else if name.ends_with(".tf.json") {
+            redact_json_defaults(content, &sensitive)
+        } else {
+            (content, 0)
+        };
+        if redactions > 0 {
+            info!("Redacted {} value(s) in {}", redactions, path.display());
+        }
+        content
+    };
+
+    copy_bundle(Bundle {
+        files: files_to_copy,
+        transform: Some(Box::new(transform)),
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sensitive(names: &[&str]) -> HashSet<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn redacts_defaults_of_sensitive_tf_variables() {
+        let content = "variable \"db_password\" {\n  sensitive = true\n  default   = \"hunter22\"\n}\n\nvariable \"region\" {\n  default = \"eu-west-1\"\n}\n";
+        let mut found = HashSet::new();
+        for (name, start, end) in variable_blocks(content) {
+            if sensitive_pattern().is_match(&content[start..end]) {
+                found.insert(name);
+            }
+        }
+        assert_eq!(found, sensitive(&["db_password"]));
+
+        let (redacted, count) = redact_variable_defaults(content, &found);
+        assert_eq!(count, 1);
+        assert_eq!(
+            redacted,
+            "variable \"db_password\" {\n  sensitive = true\n  default   = \"<redacted>\"\n}\n\nvariable \"region\" {\n  default = \"eu-west-1\"\n}\n"
+        );
+    }
+
+    #[test]
+    fn redacts_defaults_of_sensitive_tf_json_variables() {
+        let content = r#"{"variable": {"api_token": {"sensitive": true, "default": "s3cr3t"}, "region": {"default": "eu-west-1"}}}"#;
+        let (redacted, count) = redact_json_defaults(content.to_string(), &sensitive(&["api_token"]));
+        assert_eq!(count, 1);
+        let document: Value = serde_json::from_str(&redacted).unwrap();
+        assert_eq!(document["variable"]["api_token"]["default"], "<redacted>");
+        assert_eq!(document["variable"]["region"]["default"], "eu-west-1");
+
+        // Nothing sensitive, or nothing parseable: the file is kept byte for byte
+        assert_eq!(redact_json_defaults(content.to_string(), &sensitive(&[])), (content.to_string(), 0));
+        assert_eq!(redact_json_defaults("{".to_string(), &sensitive(&["api_token"])), ("{".to_string(), 0));
+    }
+
+    #[test]
+    fn redacts_multi_line_tfvars_values() {
+        let content = "region = \"eu-west-1\"\ndb_password = <<EOT\nhunter22\nEOT\nsecrets = {\n  a = \"1\"\n}\ntags = { team = \"core\" }\n";
+        let (redacted, count) = redact_assignments(content, looks_secret);
+        assert_eq!(count, 2);
+        assert_eq!(
+            redacted,
+            "region = \"eu-west-1\"\ndb_password = \"<redacted>\"\nsecrets = \"<redacted>\"\ntags = { team = \"core\" }\n"
+        );
+    }
+}
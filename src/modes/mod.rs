@@ -0,0 +1,9 @@
+pub mod cargo;
+pub mod node;
+pub mod python;
+pub mod iac;
+pub mod errors;
+pub mod failing;
+pub mod triage;
+pub mod compare;
+pub mod dep;
@@ -0,0 +1,211 @@
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::{collect_files_excluding, copy_files, exit_codes, expand_tilde, Error};
+
+// Build output and installed dependencies never belong in the bundle
+const NODE_EXCLUDED: [&str; 6] = [
+    "node_modules",
+    "dist",
+    "build",
+    "coverage",
+    ".next",
+    ".turbo",
+];
+
+// Top-level files that describe how a package is built and typed
+const PACKAGE_FILES: [&str; 2] = ["package.json", "tsconfig.json"];
+
+#[derive(Deserialize)]
+struct PackageJson {
+    name: Option<String>,
+    workspaces: Option<Workspaces>,
+}
+
+// npm/yarn accept both a plain list and `{ "packages": [...] }`
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Workspaces {
+    List(Vec<String>),
+    Object { packages: Option<Vec<String>> },
+}
+
+impl Workspaces {
+    fn patterns(&self) -> Vec<String> {
+        match self {
+            Workspaces::List(patterns) => patterns.clone(),
+            Workspaces::Object { packages } => packages.clone().unwrap_or_default(),
+        }
+    }
+}
+
+struct NodePackage {
+    name: String,
+    dir: PathBuf,
+}
+
+fn read_package_json(dir: &Path) -> Option<PackageJson> {
+    let path = dir.join("package.json");
+    let content = fs::read_to_string(&path).ok()?;
+    match serde_json::from_str(&content) {
+        Ok(package) => Some(package),
+        Err(err) => {
+            eprintln!("Failed to parse {}: {}", path.display(), err);
+            None
+        }
+    }
+}
+
+fn workspace_packages(root: &Path, patterns: &[String]) -> Vec<NodePackage> {
+    let mut packages = Vec::new();
+
+    for pattern in patterns {
+        let full_pattern = root.join(pattern).to_string_lossy().to_string();
+        let entries = match glob::glob(&full_pattern) {
+            Ok(entries) => entries,
+            Err(err) => {
+                eprintln!("Invalid workspace pattern '{}': {}", pattern, err);
+                continue;
+            }
+        };
+
+        for dir in entries.flatten() {
+            if !dir.is_dir() || packages.iter().any(|package: &NodePackage| package.dir == dir) {
+                continue;
+            }
+            if let Some(package_json) = read_package_json(&dir) {
+                let name = package_json
+                    .name
+                    .unwrap_or_else(|| dir.file_name().unwrap_or_default().to_string_lossy().to_string());
+                packages.push(NodePackage { name, dir });
+            }
+        }
+    }
+
+    packages
+}
+
+fn package_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = PACKAGE_FILES
+        .iter()
+        .map(|name| dir.join(name))
+        .filter(|path| path.is_file())
+        .collect();
+
+    let src_dir = dir.join("src");
+    if src_dir.is_dir() {
+        files.extend(collect_files_excluding(&src_dir, &NODE_EXCLUDED));
+    }
+
+    files
+}
+
+pub fn run(args: &[String]) -> Result<(), Error> {
+    let mut package_names = Vec::new();
+    let mut root = PathBuf::from(".");
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--package" | "-p" => match iter.next() {
+                Some(name) => package_names.push(name.clone()),
+                None => {
+                    eprintln!("Missing package name after {}", arg);
+                    std::process::exit(exit_codes::CONFIG);
+                }
+            },
+            "--root" => match iter.next() {
+                Some(path) => root = expand_tilde(path),
+                None => {
+                    eprintln!("Missing path after --root");
+                    std::process::exit(exit_codes::CONFIG);
+                }
+            },
+            _ => {
+                eprintln!("Unknown argument for node mode: {}", arg);
+                eprintln!("Usage: fdllm node [--package NAME]... [--root PATH]");
+                std::process::exit(exit_codes::CONFIG);
+            }
+        }
+    }
+
+    let root_package = match read_package_json(&root) {
+        Some(package) => package,
+        None => {
+            eprintln!("No package.json found in {}", root.display());
+            std::process::exit(1);
+        }
+    };
+
+    let patterns = root_package
+        .workspaces
+        .as_ref()
+        .map(|workspaces| workspaces.patterns())
+        .unwrap_or_default();
+
+    let mut files_to_copy: Vec<String> = Vec::new();
+    let mut add_files = |files: Vec<PathBuf>| {
+        for file in files {
+            let file = file.to_string_lossy().to_string();
+            if !files_to_copy.contains(&file) {
+                files_to_copy.push(file);
+            }
+        }
+    };
+
+    if patterns.is_empty() {
+        if !package_names.is_empty() {
+            eprintln!("--package requires a package.json with workspaces");
+            std::process::exit(exit_codes::CONFIG);
+        }
+        info!(
+            "Using node package: {}",
+            root_package.name.as_deref().unwrap_or("(unnamed)")
+        );
+        add_files(package_files(&root));
+    } else {
+        let packages = workspace_packages(&root, &patterns);
+
+        // Without --package every workspace package is included
+        let selected: Vec<&NodePackage> = if package_names.is_empty() {
+            packages.iter().collect()
+        } else {
+            let mut selected = Vec::new();
+            for name in &package_names {
+                match packages.iter().find(|package| &package.name == name) {
+                    Some(package) => selected.push(package),
+                    None => {
+                        let available: Vec<&str> =
+                            packages.iter().map(|package| package.name.as_str()).collect();
+                        eprintln!(
+                            "Package '{}' is not a workspace package (available: {})",
+                            name,
+                            available.join(", ")
+                        );
+                        std::process::exit(exit_codes::CONFIG);
+                    }
+                }
+            }
+            selected
+        };
+
+        info!("Using node workspace: {}", root.display());
+
+        // The root manifest carries shared scripts and tooling configuration
+        add_files(
+            PACKAGE_FILES
+                .iter()
+                .map(|name| root.join(name))
+                .filter(|path| path.is_file())
+                .collect(),
+        );
+
+        for package in selected {
+            info!("Including package: {}", package.name);
+            add_files(package_files(&package.dir));
+        }
+    }
+
+    copy_files(files_to_copy, None)
+}
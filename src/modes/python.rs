@@ -0,0 +1,391 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::{collect_files_excluding, copy_bundle, exit_codes, expand_tilde, Bundle, Error, Section};
+
+// Virtualenvs, caches and build output never belong in the bundle
+const PYTHON_EXCLUDED: [&str; 9] = [
+    ".venv",
+    "venv",
+    "__pycache__",
+    ".mypy_cache",
+    ".pytest_cache",
+    ".ruff_cache",
+    ".tox",
+    "build",
+    "dist",
+];
+
+const TEST_DIRS: [&str; 2] = ["tests", "test"];
+
+#[derive(Default)]
+struct PythonProject {
+    name: Option<String>,
+    manifests: Vec<PathBuf>,
+    // Directories packages live in (project root, `src/`, or a configured package_dir)
+    package_roots: Vec<PathBuf>,
+    // Explicitly declared packages, dotted names relative to a package root
+    packages: Vec<String>,
+    dependencies: Vec<String>,
+}
+
+fn string_list(value: Option<&toml::Value>) -> Vec<String> {
+    value
+        .and_then(|value| value.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| item.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// Poetry dependency tables map names to version strings or detail tables
+fn poetry_dependencies(table: Option<&toml::Value>, suffix: &str) -> Vec<String> {
+    let mut dependencies = Vec::new();
+    if let Some(table) = table.and_then(|table| table.as_table()) {
+        for (name, spec) in table {
+            if name == "python" {
+                continue;
+            }
+            let version = match spec {
+                toml::Value::String(version) => version.clone(),
+                toml::Value::Table(details) => details
+                    .get("version")
+                    .and_then(|version| version.as_str())
+                    .unwrap_or("*")
+                    .to_string(),
+                _ => "*".to_string(),
+            };
+            dependencies.push(format!("{} {}{}", name, version, suffix));
+        }
+    }
+    dependencies
+}
+
+fn read_pyproject(root: &Path, project: &mut PythonProject) {
+    let path = root.join("pyproject.toml");
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(_) => return,
+    };
+    let value: toml::Value = match toml::from_str(&content) {
+        Ok(value) => value,
+        Err(err) => {
+            eprintln!("Failed to parse {}: {}", path.display(), err);
+            return;
+        }
+    };
+    project.manifests.push(path);
+
+    // PEP 621 metadata
+    if let Some(meta) = value.get("project") {
+        if let Some(name) = meta.get("name").and_then(|name| name.as_str()) {
+            project.name = Some(name.to_string());
+        }
+        project.dependencies.extend(string_list(meta.get("dependencies")));
+        if let Some(extras) = meta.get("optional-dependencies").and_then(|extras| extras.as_table()) {
+            for (extra, deps) in extras {
+                for dep in string_list(Some(deps)) {
+                    project.dependencies.push(format!("{} (extra: {})", dep, extra));
+                }
+            }
+        }
+    }
+
+    let tool = match value.get("tool") {
+        Some(tool) => tool,
+        None => return,
+    };
+
+    if let Some(poetry) = tool.get("poetry") {
+        if project.name.is_none() {
+            project.name = poetry.get("name").and_then(|name| name.as_str()).map(str::to_string);
+        }
+        project.dependencies.extend(poetry_dependencies(poetry.get("dependencies"), ""));
+        project
+            .dependencies
+            .extend(poetry_dependencies(poetry.get("dev-dependencies"), " (dev)"));
+        if let Some(groups) = poetry.get("group").and_then(|groups| groups.as_table()) {
+            for (group, settings) in groups {
+                let suffix = format!(" (group: {})", group);
+                project
+                    .dependencies
+                    .extend(poetry_dependencies(settings.get("dependencies"), &suffix));
+            }
+        }
+        if let Some(packages) = poetry.get("packages").and_then(|packages| packages.as_array()) {
+            for package in packages {
+                if let Some(include) = package.get("include").and_then(|include| include.as_str()) {
+                    project.packages.push(include.to_string());
+                }
+                if let Some(from) = package.get("from").and_then(|from| from.as_str()) {
+                    project.package_roots.push(root.join(from));
+                }
+            }
+        }
+    }
+
+    if let Some(setuptools) = tool.get("setuptools") {
+        match setuptools.get("packages") {
+            Some(toml::Value::Array(_)) => {
+                project.packages.extend(string_list(setuptools.get("packages")));
+            }
+            Some(toml::Value::Table(packages)) => {
+                if let Some(find) = packages.get("find") {
+                    for dir in string_list(find.get("where")) {
+                        project.package_roots.push(root.join(dir));
+                    }
+                }
+            }
+            _ => {}
+        }
+        if let Some(dir) = setuptools
+            .get("package-dir")
+            .and_then(|dirs| dirs.get(""))
+            .and_then(|dir| dir.as_str())
+        {
+            project.package_roots.push(root.join(dir));
+        }
+    }
+}
+
+// Minimal INI reader: `[section]`, `key = value` and indented continuation lines
+fn parse_setup_cfg(content: &str) -> HashMap<String, HashMap<String, String>> {
+    let mut sections: HashMap<String, HashMap<String, String>> = HashMap::new();
+    let mut section = String::new();
+    let mut key: Option<String> = None;
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with(';') {
+            continue;
+        }
+        if trimmed.starts_with('[') && trimmed.ends_with(']') {
+            section = trimmed[1..trimmed.len() - 1].trim().to_string();
+            key = None;
+            continue;
+        }
+        let entries = sections.entry(section.clone()).or_default();
+        if line.starts_with(char::is_whitespace) {
+            if let Some(key) = &key {
+                let value = entries.entry(key.clone()).or_default();
+                value.push('\n');
+                value.push_str(trimmed);
+            }
+        } else if let Some((name, value)) = trimmed.split_once('=') {
+            let name = name.trim().to_string();
+            entries.insert(name.clone(), value.trim().to_string());
+            key = Some(name);
+        }
+    }
+
+    sections
+}
+
+fn cfg_list(value: &str) -> Vec<String> {
+    value
+        .lines()
+        .flat_map(|line| line.split(','))
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+fn read_setup_cfg(root: &Path, project: &mut PythonProject) {
+    let path = root.join("setup.cfg");
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(_) => return,
+    };
+    let sections = parse_setup_cfg(&content);
+    project.manifests.push(path);
+
+    if let Some(metadata) = sections.get("metadata") {
+        if project.name.is_none() {
+            project.name = metadata.get("name").cloned();
+        }
+    }
+
+    if let Some(options) = sections.get("options") {
+        if let Some(requires) = options.get("install_requires") {
+            project.dependencies.extend(cfg_list(requires));
+        }
+        if let Some(packages) = options.get("packages") {
+            if !packages.starts_with("find") {
+                project.packages.extend(cfg_list(packages));
+            }
+        }
+        if let Some(package_dir) = options.get("package_dir") {
+            for mapping in cfg_list(package_dir) {
+                if let Some(("", dir)) = mapping.split_once('=').map(|(key, dir)| (key.trim(), dir.trim())) {
+                    project.package_roots.push(root.join(dir));
+                }
+            }
+        }
+    }
+
+    if let Some(extras) = sections.get("options.extras_require") {
+        for (extra, requires) in extras {
+            for dep in cfg_list(requires) {
+                project.dependencies.push(format!("{} (extra: {})", dep, extra));
+            }
+        }
+    }
+}
+
+// Top-level packages (directories with __init__.py) below a package root
+fn discover_packages(package_root: &Path) -> Vec<PathBuf> {
+    let mut packages = Vec::new();
+    if let Ok(entries) = fs::read_dir(package_root) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !path.is_dir()
+                || PYTHON_EXCLUDED.contains(&name.as_str())
+                || TEST_DIRS.contains(&name.as_str())
+                || name.starts_with('.')
+            {
+                continue;
+            }
+            if path.join("__init__.py").is_file() {
+                packages.push(path);
+            }
+        }
+    }
+    packages.sort();
+    packages
+}
+
+fn package_dirs(root: &Path, project: &PythonProject) -> Vec<PathBuf> {
+    let mut roots = project.package_roots.clone();
+    if roots.is_empty() {
+        roots.push(root.to_path_buf());
+        roots.push(root.join("src"));
+    }
+
+    let mut dirs = Vec::new();
+    if !project.packages.is_empty() {
+        // Sub-packages are covered by walking their top-level package
+        for package in &project.packages {
+            let top_level = package.split('.').next().unwrap_or(package);
+            for package_root in &roots {
+                let dir = package_root.join(top_level);
+                if dir.is_dir() && !dirs.contains(&dir) {
+                    dirs.push(dir);
+                }
+            }
+        }
+    } else {
+        for package_root in &roots {
+            for dir in discover_packages(package_root) {
+                if !dirs.contains(&dir) {
+                    dirs.push(dir);
+                }
+            }
+        }
+    }
+
+    // Fall back to the distribution name for flat layouts without __init__.py
+    if dirs.is_empty() {
+        if let Some(name) = &project.name {
+            let module = name.replace('-', "_").to_lowercase();
+            for package_root in &roots {
+                let dir = package_root.join(&module);
+                if dir.is_dir() {
+                    dirs.push(dir);
+                }
+            }
+        }
+    }
+
+    dirs
+}
+
+pub fn run(args: &[String]) -> Result<(), Error> {
+    let mut root = PathBuf::from(".");
+    let mut include_tests = false;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--tests" => include_tests = true,
+            "--root" => match iter.next() {
+                Some(path) => root = expand_tilde(path),
+                None => {
+                    eprintln!("Missing path after --root");
+                    std::process::exit(exit_codes::CONFIG);
+                }
+            },
+            _ => {
+                eprintln!("Unknown argument for python mode: {}", arg);
+                eprintln!("Usage: fdllm python [--tests] [--root PATH]");
+                std::process::exit(exit_codes::CONFIG);
+            }
+        }
+    }
+
+    let mut project = PythonProject::default();
+    read_pyproject(&root, &mut project);
+    read_setup_cfg(&root, &mut project);
+
+    if project.manifests.is_empty() {
+        eprintln!("No pyproject.toml or setup.cfg found in {}", root.display());
+        std::process::exit(1);
+    }
+
+    info!(
+        "Using python project: {}",
+        project.name.as_deref().unwrap_or("(unnamed)")
+    );
+
+    let mut files_to_copy: Vec<String> = Vec::new();
+    let mut add_file = |file: &Path| {
+        let file = file.to_string_lossy().to_string();
+        if !files_to_copy.contains(&file) {
+            files_to_copy.push(file);
+        }
+    };
+
+    for manifest in &project.manifests {
+        add_file(manifest);
+    }
+
+    let mut dirs = package_dirs(&root, &project);
+    if include_tests {
+        dirs.extend(
+            TEST_DIRS
+                .iter()
+                .map(|name| root.join(name))
+                .filter(|dir| dir.is_dir()),
+        );
+    }
+
+    for dir in &dirs {
+        info!("Including package directory: {}", dir.display());
+        for file in collect_files_excluding(dir, &PYTHON_EXCLUDED) {
+            add_file(&file);
+        }
+    }
+
+    let mut sections = Vec::new();
+    if !project.dependencies.is_empty() {
+        let body: String = project
+            .dependencies
+            .iter()
+            .map(|dep| format!("- {}\n", dep))
+            .collect();
+        sections.push(Section {
+            title: "Python Dependencies".to_string(),
+            body,
+        });
+    }
+
+    copy_bundle(Bundle {
+        files: files_to_copy,
+        sections,
+        ..Default::default()
+    })
+}
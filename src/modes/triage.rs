@@ -0,0 +1,168 @@
+use regex::Regex;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::diagnostics::{map_to_project, project_files};
+use crate::{copy_bundle, exit_codes, expand_tilde, Bundle, Error, Section};
+
+// Lines of log kept before a trace starts and after it ends
+const LEAD_LINES: usize = 3;
+const TRAIL_LINES: usize = 2;
+
+// Consecutive trace lines may be separated by a few unrelated lines (e.g. notes in a backtrace)
+const MAX_GAP: usize = 2;
+
+const MAX_EXCERPTS: usize = 20;
+
+struct TraceMatchers {
+    markers: Vec<Regex>,
+    frames: Vec<Regex>,
+    java_frame: Regex,
+}
+
+impl TraceMatchers {
+    fn new() -> Self {
+        TraceMatchers {
+            markers: vec![
+                // Rust
+                Regex::new(r"panicked at|stack backtrace:|RUST_BACKTRACE").unwrap(),
+                // Python
+                Regex::new(r"Traceback \(most recent call last\)").unwrap(),
+                // Java/JVM and JS errors
+                Regex::new(r"Exception in thread|Caused by:|^\s*\w*(Error|Exception)\b.*:").unwrap(),
+                // Go
+                Regex::new(r"^panic:|^goroutine \d+ \[").unwrap(),
+            ],
+            frames: vec![
+                // Python: File "app/models.py", line 42
+                Regex::new(r#"File "([^"]+)", line \d+"#).unwrap(),
+                // Rust backtrace / JS stack: at ./src/x.rs:12:5, at fn (/srv/app/x.js:10:5)
+                Regex::new(r"\bat (?:.*\()?([^\s()]+\.[A-Za-z0-9]+):\d+(?::\d+)?\)?").unwrap(),
+                // Rust panic location / Go frames: src/main.rs:10:5, /app/main.go:12 +0x1d
+                Regex::new(r"(?:^|\s)([^\s:()]+\.(?:rs|go|py|js|ts|rb|c|cc|cpp|h|hpp|kt|swift)):\d+").unwrap(),
+            ],
+            // at com.foo.Bar.method(Bar.java:42)
+            java_frame: Regex::new(r"\bat ([\w$.]+)\.[\w$<>]+\(([\w$]+\.(?:java|kt|scala)):\d+\)").unwrap(),
+        }
+    }
+
+    // Source path referenced by a stack frame, as printed in the log
+    fn frame_path(&self, line: &str) -> Option<String> {
+        if let Some(caps) = self.java_frame.captures(line) {
+            // Package path of the class plus the file name the JVM reports
+            let class_path = &caps[1];
+            let package: Vec<&str> = class_path.split('.').collect();
+            let dir = package[..package.len().saturating_sub(1)].join("/");
+            let path = if dir.is_empty() {
+                caps[2].to_string()
+            } else {
+                format!("{}/{}", dir, &caps[2])
+            };
+            return Some(path);
+        }
+        self.frames
+            .iter()
+            .find_map(|pattern| pattern.captures(line).map(|caps| caps[1].to_string()))
+    }
+
+    fn is_trace_line(&self, line: &str) -> bool {
+        self.markers.iter().any(|pattern| pattern.is_match(line)) || self.frame_path(line).is_some()
+    }
+}
+
+// Ranges of log lines that belong to stack traces, padded with a little context
+fn trace_ranges(lines: &[&str], matchers: &TraceMatchers) -> Vec<(usize, usize)> {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+
+    for (index, line) in lines.iter().enumerate() {
+        if !matchers.is_trace_line(line) {
+            continue;
+        }
+        match ranges.last_mut() {
+            Some(last) if index <= last.1 + MAX_GAP + 1 => last.1 = index,
+            _ => ranges.push((index, index)),
+        }
+    }
+
+    let mut padded: Vec<(usize, usize)> = Vec::new();
+    for (start, end) in ranges {
+        let start = start.saturating_sub(LEAD_LINES);
+        let end = (end + TRAIL_LINES).min(lines.len().saturating_sub(1));
+        match padded.last_mut() {
+            Some(last) if start <= last.1 + 1 => last.1 = end,
+            _ => padded.push((start, end)),
+        }
+    }
+    padded
+}
+
+pub fn run(args: &[String]) -> Result<(), Error> {
+    let log_path = match args {
+        [path] => expand_tilde(path),
+        _ => {
+            eprintln!("Usage: fdllm triage <logfile>");
+            std::process::exit(exit_codes::CONFIG);
+        }
+    };
+
+    let log = match fs::read(&log_path) {
+        Ok(bytes) => String::from_utf8_lossy(&bytes).to_string(),
+        Err(err) => {
+            eprintln!("Failed to read log file {}: {}", log_path.display(), err);
+            std::process::exit(1);
+        }
+    };
+
+    let matchers = TraceMatchers::new();
+    let lines: Vec<&str> = log.lines().collect();
+    let ranges = trace_ranges(&lines, &matchers);
+
+    if ranges.is_empty() {
+        eprintln!("No stack traces or panics found in {}", log_path.display());
+        std::process::exit(exit_codes::EMPTY);
+    }
+
+    if ranges.len() > MAX_EXCERPTS {
+        eprintln!(
+            "Found {} traces; keeping the last {}",
+            ranges.len(),
+            MAX_EXCERPTS
+        );
+    }
+    let ranges = &ranges[ranges.len().saturating_sub(MAX_EXCERPTS)..];
+
+    let project = project_files();
+    let mut files: Vec<PathBuf> = Vec::new();
+    let mut excerpt = String::new();
+
+    for (start, end) in ranges {
+        if !excerpt.is_empty() {
+            excerpt.push_str("...\n");
+        }
+        for (offset, line) in lines[*start..=*end].iter().enumerate() {
+            excerpt.push_str(&format!("{:>6} | {}\n", start + offset + 1, line));
+            if let Some(frame_path) = matchers.frame_path(line) {
+                if let Some(path) = map_to_project(&frame_path, &project) {
+                    if !files.contains(&path) {
+                        files.push(path);
+                    }
+                }
+            }
+        }
+    }
+
+    info!(
+        "Found {} trace excerpt(s) referencing {} project file(s)",
+        ranges.len(),
+        files.len()
+    );
+
+    copy_bundle(Bundle {
+        files: files.iter().map(|file| file.to_string_lossy().to_string()).collect(),
+        sections: vec![Section {
+            title: format!("Log excerpt: {}", log_path.display()),
+            body: excerpt,
+        }],
+        ..Default::default()
+    })
+}
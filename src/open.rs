@@ -0,0 +1,158 @@
+use regex::Regex;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+
+use crate::{
+    active_profile, command_line_options, exit_codes, expand_tilde, get_config_path, load_config, roots, ActiveProfile,
+    Error,
+};
+
+struct Reference {
+    path: String,
+    line: Option<usize>,
+}
+
+fn lines_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"^(.*?)\s+\(lines (\d+)-\d+\)$").expect("valid line range pattern"))
+}
+
+fn position_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"^(.*?):(\d+)(?::\d+)?$").expect("valid position pattern"))
+}
+
+// Accepts `src/foo.rs:42`, `src/foo.rs:42:7`, `# NOTE: src/foo.rs:` or `src/foo.rs (lines 10-20)`
+fn parse_reference(input: &str) -> Reference {
+    let trimmed = input.trim();
+    let trimmed = trimmed.strip_prefix("# NOTE:").unwrap_or(trimmed).trim();
+    let trimmed = trimmed.trim_matches('`').trim_end_matches(':');
+
+    if let Some(caps) = lines_pattern().captures(trimmed) {
+        return Reference {
+            path: caps[1].to_string(),
+            line: caps[2].parse().ok(),
+        };
+    }
+
+    if let Some(caps) = position_pattern().captures(trimmed) {
+        return Reference {
+            path: caps[1].to_string(),
+            line: caps[2].parse().ok(),
+        };
+    }
+
+    Reference {
+        path: trimmed.to_string(),
+        line: None,
+    }
+}
+
+// Roots by label first, then the paths the profile already knows about
+fn resolve(path: &str, profile: &ActiveProfile) -> Option<PathBuf> {
+    let mut candidates = Vec::new();
+
+    if let Some((label, rest)) = path.split_once('/') {
+        for root in profile.roots.iter().filter(|root| root.label == label) {
+            candidates.push(root.path.join(rest));
+        }
+    }
+    candidates.push(roots::resolve(path, &profile.roots));
+    candidates.push(expand_tilde(path));
+
+    if let Some(project) = profile.project {
+        candidates.push(expand_tilde(&project.path).join(path));
+    }
+    for root in &profile.roots {
+        candidates.push(root.path.join(path));
+    }
+    for dir in &profile.directories {
+        let dir = roots::resolve(dir, &profile.roots);
+        // `src/foo.rs` against a configured `~/proj/src` directory
+        if let Some(parent) = dir.parent() {
+            candidates.push(parent.join(path));
+        }
+        candidates.push(dir.join(path));
+    }
+
+    candidates.into_iter().find(|candidate| candidate.is_file())
+}
+
+pub fn editor_command(path: &Path, line: Option<usize>) -> Command {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+
+    // $EDITOR may carry arguments, e.g. "code --wait"
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi").to_string();
+    let mut command = Command::new(&program);
+    command.args(parts);
+
+    let name = Path::new(&program)
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+
+    match line {
+        Some(line) => match name.as_str() {
+            "code" | "codium" | "cursor" | "code-insiders" => {
+                command.arg("-g").arg(format!("{}:{}", path.display(), line));
+            }
+            "subl" | "zed" | "hx" | "helix" => {
+                command.arg(format!("{}:{}", path.display(), line));
+            }
+            _ => {
+                // vi, vim, nvim, nano, emacs, micro, kak all understand +LINE
+                command.arg(format!("+{}", line)).arg(path);
+            }
+        },
+        None => {
+            command.arg(path);
+        }
+    }
+
+    command
+}
+
+pub fn run(args: &[String]) -> Result<(), Error> {
+    let (input, profile_name) = match args {
+        [input] => (input, None),
+        [input, profile] => (input, Some(profile.as_str())),
+        _ => {
+            eprintln!("Usage: fdllm open <path[:line]> [profile]");
+            std::process::exit(exit_codes::CONFIG);
+        }
+    };
+
+    let reference = parse_reference(input);
+    let config = load_config(&get_config_path()?)?;
+    let profile = active_profile(&config, profile_name, command_line_options())?;
+
+    let path = match resolve(&reference.path, &profile) {
+        Some(path) => path,
+        None => {
+            eprintln!("Could not resolve '{}' against the profile's roots", reference.path);
+            std::process::exit(exit_codes::CONFIG);
+        }
+    };
+
+    info!("Opening {}", path.display());
+
+    let mut command = editor_command(&path, reference.line);
+    match command.status() {
+        Ok(status) if status.success() => {}
+        Ok(status) => {
+            eprintln!("Editor exited with {}", status);
+            std::process::exit(1);
+        }
+        Err(err) => {
+            eprintln!("Failed to launch editor: {}", err);
+            std::process::exit(1);
+        }
+    }
+    Ok(())
+}
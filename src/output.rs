@@ -0,0 +1,529 @@
+use schemars::JsonSchema;
+use serde::Deserialize;
+use similar::TextDiff;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::clipboard::{self, ClipboardBackend};
+use crate::provenance::{self, utc_timestamp};
+use crate::send_keys::{self, SendKeys};
+
+// What to do when no clipboard backend works (headless servers, no X or Wayland session)
+#[derive(Deserialize, JsonSchema, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ClipboardFallback {
+    // Write the bundle to a file in the temp directory and print its path
+    #[default]
+    File,
+    // Print the bundle on stdout (a file is used when stdout is reserved, e.g. --porcelain)
+    Stdout,
+    // Count the clipboard as a failed output
+    Error,
+}
+
+impl ClipboardFallback {
+    pub fn name(self) -> &'static str {
+        match self {
+            ClipboardFallback::File => "file",
+            ClipboardFallback::Stdout => "stdout",
+            ClipboardFallback::Error => "error",
+        }
+    }
+}
+
+// Where the finished bundle goes; every configured output is attempted
+pub struct Outputs {
+    pub clipboard: bool,
+    pub clipboard_fallback: ClipboardFallback,
+    pub clipboard_backend: ClipboardBackend,
+    pub file: Option<PathBuf>,
+    pub stdout: bool,
+    pub send_keys: Option<SendKeys>,
+}
+
+// Porcelain-style labels (`clipboard`, `file <path>`, `stdout`) of what was and wasn't delivered
+#[derive(Default)]
+pub struct Report {
+    pub delivered: Vec<String>,
+    pub failed: Vec<String>,
+    // Set when the bundle went to the clipboard fallback instead
+    pub clipboard_fallback: bool,
+}
+
+// `{{profile}}`, `{{date}}` (2024-05-01) and `{{time}}` (143005, UTC like the date) in an output
+// path, so each run can leave its own dated file behind
+pub fn expand_template(path: &Path, profile: &str) -> PathBuf {
+    let template = path.to_string_lossy();
+    if !template.contains("{{") {
+        return path.to_path_buf();
+    }
+    let timestamp = utc_timestamp(provenance::now());
+    let expanded = template
+        .replace("{{profile}}", &profile.replace(['/', '\\'], "-"))
+        .replace("{{date}}", &timestamp[..10])
+        .replace("{{time}}", &timestamp[11..19].replace(':', ""));
+    if let Some(start) = expanded.find("{{") {
+        let placeholder = &expanded[start..];
+        let placeholder = &placeholder[..placeholder.find("}}").map(|end| end + 2).unwrap_or(placeholder.len())];
+        eprintln!("Warning: unknown placeholder {} in output path (expected {{{{profile}}}}, {{{{date}}}} or {{{{time}}}})", placeholder);
+    }
+    PathBuf::from(expanded)
+}
+
+fn file_label(path: &Path) -> String {
+    format!("file {}", path.display())
+}
+
+// Written next to the target so the final rename never crosses filesystems
+fn staging_path(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!(".{}.fdllm-{}.tmp", name, std::process::id()))
+}
+
+// A new file only this user can read; an existing file or a symlink at `path` is an error,
+// never something written through
+fn create_private(path: &Path) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)
+}
+
+// The staging file, private until it takes over an existing file's permissions
+fn create_staging(target: &Path) -> io::Result<(PathBuf, File)> {
+    let staging = staging_path(target);
+    // Left behind by a run that died with the same pid; removing a symlink leaves its target
+    let _ = fs::remove_file(&staging);
+    let file = create_private(&staging)?;
+    if let Ok(metadata) = fs::metadata(target) {
+        file.set_permissions(metadata.permissions())?;
+    }
+    Ok((staging, file))
+}
+
+fn stage_file(path: &Path, content: &str) -> io::Result<PathBuf> {
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() {
+            fs::create_dir_all(parent)?;
+        }
+    }
+    let (staging, mut file) = create_staging(path)?;
+    let result = file.write_all(content.as_bytes()).and_then(|_| file.sync_all());
+    match result {
+        Ok(()) => Ok(staging),
+        Err(err) => {
+            let _ = fs::remove_file(&staging);
+            Err(err)
+        }
+    }
+}
+
+// Replaces the file in one step; a symlinked file (dotfiles) is replaced where it points
+pub fn write_atomically(path: &Path, content: &str) -> std::io::Result<()> {
+    let target = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let staging = stage_file(&target, content)?;
+    fs::rename(&staging, &target).inspect_err(|_| {
+        let _ = fs::remove_file(&staging);
+    })
+}
+
+// Like `stage_file`, from a bundle that is already on disk
+fn stage_copy(path: &Path, source: &Path) -> io::Result<PathBuf> {
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() {
+            fs::create_dir_all(parent)?;
+        }
+    }
+    let (staging, mut file) = create_staging(path)?;
+    let result = File::open(source)
+        .and_then(|mut source| io::copy(&mut source, &mut file))
+        .and_then(|_| file.sync_all());
+    match result {
+        Ok(()) => Ok(staging),
+        Err(err) => {
+            let _ = fs::remove_file(&staging);
+            Err(err)
+        }
+    }
+}
+
+// A private file in the temp directory for a bundle with nowhere else to go. The name is easy
+// to guess, so one that is already taken (by anyone) is skipped rather than opened
+pub fn create_fallback() -> io::Result<(PathBuf, File)> {
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis())
+        .unwrap_or(0);
+    let mut attempt = 0;
+    loop {
+        let name = match attempt {
+            0 => format!("fdllm-bundle-{}-{}.md", stamp, std::process::id()),
+            _ => format!("fdllm-bundle-{}-{}-{}.md", stamp, std::process::id(), attempt),
+        };
+        let path = std::env::temp_dir().join(name);
+        match create_private(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists && attempt < 100 => attempt += 1,
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+fn write_fallback(content: &str) -> Result<PathBuf, String> {
+    let (path, mut file) = create_fallback().map_err(|err| format!("Failed to create a file in the temp directory: {}", err))?;
+    file.write_all(content.as_bytes()).map_err(|err| {
+        let _ = fs::remove_file(&path);
+        format!("Failed to write {}: {}", path.display(), err)
+    })?;
+    Ok(path)
+}
+
+// Delivers the bundle somewhere else when the clipboard can't take it; returns its label
+fn clipboard_fallback(content: &str, fallback: ClipboardFallback) -> Option<String> {
+    if fallback == ClipboardFallback::Stdout && !crate::stdout_reserved() {
+        print!("{}", content);
+        // Whatever is reported after the bundle must not look like part of it
+        crate::STDOUT_DATA.store(true, std::sync::atomic::Ordering::Relaxed);
+        return Some("stdout".to_string());
+    }
+    if fallback == ClipboardFallback::Error {
+        return None;
+    }
+    match write_fallback(content) {
+        Ok(path) => Some(file_label(&path)),
+        Err(err) => {
+            eprintln!("{}", err);
+            None
+        }
+    }
+}
+
+// One destination for the bundle. `prepare` runs for every sink before any `deliver`, so a sink
+// that can fail early (a file that can't be written) stops the run before anything is committed.
+trait Sink {
+    fn label(&self) -> String;
+    fn prepare(&mut self, _content: &str) -> Result<(), String> {
+        Ok(())
+    }
+    // The label of where the bundle ended up
+    fn deliver(&mut self, content: &str) -> Result<String, String>;
+    // Undoes `prepare` when another sink failed to prepare
+    fn abandon(&mut self) {}
+    // Whether the bundle went somewhere other than `label` says
+    fn fell_back(&self) -> bool {
+        false
+    }
+}
+
+struct ClipboardSink {
+    backend: ClipboardBackend,
+    fallback: ClipboardFallback,
+    fell_back: bool,
+}
+
+impl Sink for ClipboardSink {
+    fn label(&self) -> String {
+        "clipboard".to_string()
+    }
+
+    fn deliver(&mut self, content: &str) -> Result<String, String> {
+        let err = match clipboard::copy(content, self.backend) {
+            Ok(ClipboardBackend::Osc52) => {
+                info!("File contents and project tree sent to the terminal's clipboard (OSC 52)");
+                return Ok(self.label());
+            }
+            Ok(ClipboardBackend::WlCopy) => {
+                info!("File contents and project tree copied to clipboard (wl-copy)");
+                return Ok(self.label());
+            }
+            Ok(_) => {
+                info!("File contents and project tree copied to clipboard");
+                return Ok(self.label());
+            }
+            Err(err) => err,
+        };
+        match clipboard_fallback(content, self.fallback) {
+            Some(label) => {
+                eprintln!("Warning: {}; the bundle went to {} instead", err, label);
+                self.fell_back = true;
+                Ok(label)
+            }
+            None => Err(err),
+        }
+    }
+
+    fn fell_back(&self) -> bool {
+        self.fell_back
+    }
+}
+
+// Staged next to the target and renamed into place, so readers never see half a bundle
+struct FileSink {
+    path: PathBuf,
+    staging: Option<PathBuf>,
+}
+
+impl Sink for FileSink {
+    fn label(&self) -> String {
+        file_label(&self.path)
+    }
+
+    fn prepare(&mut self, content: &str) -> Result<(), String> {
+        let staging = stage_file(&self.path, content)
+            .map_err(|err| format!("Failed to write {}: {}", self.path.display(), err))?;
+        self.staging = Some(staging);
+        Ok(())
+    }
+
+    fn deliver(&mut self, _content: &str) -> Result<String, String> {
+        let staging = self.staging.take().expect("file sink is prepared before delivery");
+        match fs::rename(&staging, &self.path) {
+            Ok(()) => {
+                info!("Bundle written to {}", self.path.display());
+                Ok(self.label())
+            }
+            Err(err) => {
+                let _ = fs::remove_file(&staging);
+                Err(format!("Failed to write {}: {}", self.path.display(), err))
+            }
+        }
+    }
+
+    fn abandon(&mut self) {
+        if let Some(staging) = self.staging.take() {
+            let _ = fs::remove_file(staging);
+        }
+    }
+}
+
+struct StdoutSink;
+
+impl Sink for StdoutSink {
+    fn label(&self) -> String {
+        "stdout".to_string()
+    }
+
+    fn deliver(&mut self, content: &str) -> Result<String, String> {
+        let mut stdout = std::io::stdout().lock();
+        stdout
+            .write_all(content.as_bytes())
+            .and_then(|_| stdout.flush())
+            .map_err(|err| format!("Failed to write to stdout: {}", err))?;
+        Ok(self.label())
+    }
+}
+
+// The pane is checked in `prepare`, so a mistyped target stops the run before any file is
+// written. With --send-path the pasted path is the output file's, which the file sink ahead of
+// this one has renamed into place by then, or else a file of its own in the temp directory.
+struct SendKeysSink {
+    target: SendKeys,
+    output_file: Option<PathBuf>,
+    pane_id: Option<String>,
+    temp_file: Option<PathBuf>,
+}
+
+impl Sink for SendKeysSink {
+    fn label(&self) -> String {
+        self.target.label()
+    }
+
+    fn prepare(&mut self, content: &str) -> Result<(), String> {
+        let pane_id = send_keys::pane_id(&self.target.pane)
+            .map_err(|err| format!("Cannot paste into tmux pane {}: {}", self.target.pane, err))?;
+        self.pane_id = Some(pane_id);
+        if self.target.path && self.output_file.is_none() {
+            self.temp_file = Some(write_fallback(content)?);
+        }
+        Ok(())
+    }
+
+    fn deliver(&mut self, content: &str) -> Result<String, String> {
+        let pane_id = self.pane_id.as_deref().expect("send-keys sink is prepared before delivery");
+        let path = self.output_file.as_ref().or(self.temp_file.as_ref());
+        let text = match path {
+            Some(path) if self.target.path => path.display().to_string(),
+            _ => content.to_string(),
+        };
+        send_keys::send(pane_id, &text).map_err(|err| format!("Failed to paste into tmux pane {}: {}", self.target.pane, err))?;
+        match path {
+            Some(path) if self.target.path => info!("Path of the bundle ({}) pasted into tmux pane {}", path.display(), self.target.pane),
+            _ => info!("File contents and project tree pasted into tmux pane {}", self.target.pane),
+        }
+        Ok(self.label())
+    }
+
+    fn abandon(&mut self) {
+        if let Some(path) = self.temp_file.take() {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+fn sinks(outputs: &Outputs) -> Vec<Box<dyn Sink>> {
+    let mut sinks: Vec<Box<dyn Sink>> = Vec::new();
+    if outputs.clipboard {
+        sinks.push(Box::new(ClipboardSink {
+            backend: outputs.clipboard_backend,
+            fallback: outputs.clipboard_fallback,
+            fell_back: false,
+        }));
+    }
+    if let Some(path) = &outputs.file {
+        sinks.push(Box::new(FileSink {
+            path: path.clone(),
+            staging: None,
+        }));
+    }
+    if outputs.stdout {
+        sinks.push(Box::new(StdoutSink));
+    }
+    if let Some(target) = &outputs.send_keys {
+        sinks.push(Box::new(SendKeysSink {
+            target: target.clone(),
+            output_file: outputs.file.clone(),
+            pane_id: None,
+            temp_file: None,
+        }));
+    }
+    sinks
+}
+
+// Prepares every sink first so a failed file write aborts before anything is committed;
+// after that each output succeeds or fails on its own and the report says which.
+pub fn deliver(content: String, outputs: &Outputs) -> Report {
+    let mut report = Report::default();
+    let mut sinks = sinks(outputs);
+
+    for index in 0..sinks.len() {
+        if let Err(err) = sinks[index].prepare(&content) {
+            eprintln!("{}", err);
+            report.failed.push(sinks[index].label());
+            for sink in &mut sinks[..index] {
+                sink.abandon();
+            }
+            return report;
+        }
+    }
+
+    for sink in &mut sinks {
+        match sink.deliver(&content) {
+            Ok(label) => {
+                report.delivered.push(label);
+                report.clipboard_fallback |= sink.fell_back();
+            }
+            Err(err) => {
+                eprintln!("{}", err);
+                report.failed.push(sink.label());
+            }
+        }
+    }
+
+    report
+}
+
+// A bundle that passed max_memory, already in `spill`: it is copied into the output file and
+// streamed to stdout, and the clipboard, which would need all of it in memory, is left out. With
+// no other output the spill file itself is kept and reported in the clipboard's place. A tmux pane
+// gets the path of the bundle on disk, as with --send-path.
+pub fn deliver_spilled(spill: &Path, outputs: &Outputs) -> Report {
+    let mut report = Report::default();
+    if let Some(path) = &outputs.file {
+        let written = stage_copy(path, spill).and_then(|staging| {
+            fs::rename(&staging, path).inspect_err(|_| {
+                let _ = fs::remove_file(&staging);
+            })
+        });
+        match written {
+            Ok(()) => {
+                info!("Bundle written to {}", path.display());
+                report.delivered.push(file_label(path));
+            }
+            Err(err) => {
+                eprintln!("Failed to write {}: {}", path.display(), err);
+                report.failed.push(file_label(path));
+                let _ = fs::remove_file(spill);
+                return report;
+            }
+        }
+    }
+    if outputs.stdout {
+        let streamed = File::open(spill).and_then(|mut file| {
+            let mut stdout = std::io::stdout().lock();
+            std::io::copy(&mut file, &mut stdout)?;
+            stdout.flush()
+        });
+        match streamed {
+            Ok(()) => report.delivered.push("stdout".to_string()),
+            Err(err) => {
+                eprintln!("Failed to write to stdout: {}", err);
+                report.failed.push("stdout".to_string());
+            }
+        }
+    }
+    if let Some(target) = &outputs.send_keys {
+        let path = outputs.file.as_deref().unwrap_or(spill);
+        if !target.path {
+            eprintln!("Warning: the bundle is too large to paste under max_memory; its path was pasted instead");
+        }
+        let sent = send_keys::pane_id(&target.pane).and_then(|pane_id| send_keys::send(&pane_id, &path.display().to_string()));
+        match sent {
+            Ok(()) => {
+                info!("Path of the bundle ({}) pasted into tmux pane {}", path.display(), target.pane);
+                report.delivered.push(target.label());
+                // The pasted path may be the spill file's, which has to stay
+                if outputs.file.is_none() {
+                    return report;
+                }
+            }
+            Err(err) => {
+                eprintln!("Failed to paste into tmux pane {}: {}", target.pane, err);
+                report.failed.push(target.label());
+            }
+        }
+    }
+    if outputs.file.is_none() && !outputs.stdout && outputs.send_keys.is_none() {
+        eprintln!("Warning: the bundle is too large for the clipboard under max_memory; it went to {} instead", spill.display());
+        report.delivered.push(file_label(spill));
+        report.clipboard_fallback = true;
+        return report;
+    }
+    if outputs.clipboard {
+        eprintln!("Warning: the bundle is too large for the clipboard under max_memory; it was not copied");
+    }
+    let _ = fs::remove_file(spill);
+    report
+}
+
+// Unified diff from the file's current content (empty if it does not exist yet) to `content`
+pub fn diff_against(path: &Path, content: &str) -> String {
+    let existing = fs::read_to_string(path).unwrap_or_default();
+    let old_header = if path.exists() {
+        path.display().to_string()
+    } else {
+        "/dev/null".to_string()
+    };
+    TextDiff::from_lines(existing.as_str(), content)
+        .unified_diff()
+        .context_radius(3)
+        .header(&old_header, &path.display().to_string())
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_output_path_templates() {
+        let path = expand_template(Path::new("/tmp/{{profile}}-{{date}}-{{time}}.md"), "a/b");
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        // a-b-YYYY-MM-DD-HHMMSS.md
+        assert!(name.starts_with("a-b-"), "{}", name);
+        assert_eq!(name.len(), "a-b-".len() + 10 + 1 + 6 + ".md".len(), "{}", name);
+        assert_eq!(expand_template(Path::new("/tmp/plain.md"), "x"), Path::new("/tmp/plain.md"));
+    }
+}
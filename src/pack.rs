@@ -0,0 +1,249 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::provenance::{self, utc_timestamp};
+use crate::{
+    active_profile, command_line_options, copy_bundle, exit_codes, get_config_path, git, load_config, profile_files,
+    roots, unicode_paths, Bundle, Error, Section,
+};
+
+// A context pack is a zip holding manifest.json plus every file under files/
+const FORMAT: &str = "fdllm-pack-1";
+const MANIFEST_NAME: &str = "manifest.json";
+
+#[derive(Serialize, Deserialize)]
+struct PackManifest {
+    format: String,
+    fdllm_version: String,
+    profile: String,
+    created: String,
+    files: Vec<PackedFile>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PackedFile {
+    // The header the file had in the original bundle
+    path: String,
+    archive_path: String,
+    sha256: String,
+    bytes: usize,
+}
+
+fn sha256(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+// `/home/me/app/src/main.rs` -> `files/home/me/app/src/main.rs`; `..` can't climb out
+fn archive_path(header: &str) -> String {
+    let parts: Vec<String> = Path::new(header)
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => Some(part.to_string_lossy().to_string()),
+            Component::ParentDir => Some("_".to_string()),
+            _ => None,
+        })
+        .collect();
+    format!("files/{}", parts.join("/"))
+}
+
+fn fail(what: &str, err: impl std::fmt::Display) -> ! {
+    eprintln!("{}: {}", what, err);
+    std::process::exit(1);
+}
+
+pub fn pack(args: &[String]) -> Result<(), Error> {
+    let usage = "Usage: fdllm pack [profile] [-o FILE]";
+    let mut profile_name = None;
+    let mut output = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "-o" | "--output" => match iter.next() {
+                Some(value) => output = Some(PathBuf::from(value)),
+                None => {
+                    eprintln!("{} expects a file name", arg);
+                    std::process::exit(exit_codes::CONFIG);
+                }
+            },
+            _ if !arg.starts_with('-') && profile_name.is_none() => profile_name = Some(arg.as_str()),
+            _ => {
+                eprintln!("{}", usage);
+                std::process::exit(exit_codes::CONFIG);
+            }
+        }
+    }
+    let name = profile_name.unwrap_or("default");
+    let output = output.unwrap_or_else(|| PathBuf::from(format!("{}.fdllm-pack.zip", name)));
+
+    let config = load_config(&get_config_path()?)?;
+    let profile = active_profile(&config, profile_name, command_line_options())?;
+
+    let mut manifest = PackManifest {
+        format: FORMAT.to_string(),
+        fdllm_version: env!("CARGO_PKG_VERSION").to_string(),
+        profile: name.to_string(),
+        created: utc_timestamp(provenance::now()),
+        files: Vec::new(),
+    };
+    let mut contents = Vec::new();
+    for entry in unicode_paths::dedup(profile_files(&profile)) {
+        if git::parse_revision_entry(&entry).is_some() {
+            eprintln!("Skipping {}: packs hold working-tree files only", entry);
+            continue;
+        }
+        let path = roots::resolve(&entry, &profile.roots);
+        let path = unicode_paths::locate(&path).unwrap_or(path);
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                eprintln!("Failed to read file {}: {}", path.display(), err);
+                continue;
+            }
+        };
+        let header = unicode_paths::nfc(&roots::display(&entry, &path, &profile.roots));
+        manifest.files.push(PackedFile {
+            archive_path: archive_path(&header),
+            path: header,
+            sha256: sha256(&bytes),
+            bytes: bytes.len(),
+        });
+        contents.push(bytes);
+    }
+    if manifest.files.is_empty() {
+        eprintln!("No files to pack");
+        std::process::exit(exit_codes::EMPTY);
+    }
+
+    let file = File::create(&output).unwrap_or_else(|err| fail(&format!("Failed to create {}", output.display()), err));
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let manifest_json = serde_json::to_string_pretty(&manifest).expect("Failed to serialize pack manifest");
+    let result = zip
+        .start_file(MANIFEST_NAME, options)
+        .map_err(|err| err.to_string())
+        .and_then(|_| zip.write_all(manifest_json.as_bytes()).map_err(|err| err.to_string()))
+        .and_then(|_| {
+            for (packed, bytes) in manifest.files.iter().zip(&contents) {
+                zip.start_file(packed.archive_path.as_str(), options).map_err(|err| err.to_string())?;
+                zip.write_all(bytes).map_err(|err| err.to_string())?;
+            }
+            Ok(())
+        })
+        .and_then(|_| zip.finish().map(|_| ()).map_err(|err| err.to_string()));
+    if let Err(err) = result {
+        let _ = fs::remove_file(&output);
+        fail(&format!("Failed to write {}", output.display()), err);
+    }
+    info!("Packed {} files from '{}' into {}", manifest.files.len(), name, output.display());
+    Ok(())
+}
+
+pub fn unpack(args: &[String]) -> Result<(), Error> {
+    let usage = "Usage: fdllm unpack <pack.zip> [--into DIR]";
+    let mut pack_path = None;
+    let mut into = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--into" => match iter.next() {
+                Some(value) => into = Some(PathBuf::from(value)),
+                None => {
+                    eprintln!("--into expects a directory");
+                    std::process::exit(exit_codes::CONFIG);
+                }
+            },
+            _ if !arg.starts_with('-') && pack_path.is_none() => pack_path = Some(PathBuf::from(arg)),
+            _ => {
+                eprintln!("{}", usage);
+                std::process::exit(exit_codes::CONFIG);
+            }
+        }
+    }
+    let pack_path = match pack_path {
+        Some(path) => path,
+        None => {
+            eprintln!("{}", usage);
+            std::process::exit(exit_codes::CONFIG);
+        }
+    };
+
+    // `backend.fdllm-pack.zip` unpacks into `backend/` next to it
+    let into = into.unwrap_or_else(|| {
+        let name = pack_path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let stem = name.trim_end_matches(".zip").trim_end_matches(".fdllm-pack");
+        pack_path.with_file_name(stem)
+    });
+    if into.exists() && fs::read_dir(&into).is_ok_and(|mut entries| entries.next().is_some()) {
+        eprintln!("{} already exists and is not empty; choose another with --into", into.display());
+        std::process::exit(exit_codes::CONFIG);
+    }
+
+    let file = File::open(&pack_path).unwrap_or_else(|err| fail(&format!("Failed to open {}", pack_path.display()), err));
+    let mut zip = ZipArchive::new(file).unwrap_or_else(|err| fail(&format!("Not a context pack {}", pack_path.display()), err));
+    let manifest: PackManifest = {
+        let mut content = String::new();
+        let read = zip
+            .by_name(MANIFEST_NAME)
+            .map_err(|err| err.to_string())
+            .and_then(|mut entry| entry.read_to_string(&mut content).map_err(|err| err.to_string()));
+        if let Err(err) = read {
+            fail(&format!("Failed to read {} from {}", MANIFEST_NAME, pack_path.display()), err);
+        }
+        serde_json::from_str(&content).unwrap_or_else(|err| fail("Invalid pack manifest", err))
+    };
+    if manifest.format != FORMAT {
+        eprintln!("Unsupported pack format '{}' (expected {})", manifest.format, FORMAT);
+        std::process::exit(1);
+    }
+
+    let mut sections = Vec::new();
+    for packed in &manifest.files {
+        let mut bytes = Vec::new();
+        let read = zip.by_name(&packed.archive_path).map_err(|err| err.to_string()).and_then(|mut entry| {
+            // Paths come from someone else's machine: nothing may land outside `into`
+            if entry.enclosed_name().is_none() {
+                return Err("unsafe path".to_string());
+            }
+            entry.read_to_end(&mut bytes).map_err(|err| err.to_string())
+        });
+        if let Err(err) = read {
+            fail(&format!("Failed to read {} from the pack", packed.archive_path), err);
+        }
+        if sha256(&bytes) != packed.sha256 {
+            eprintln!("Checksum mismatch for {}; the pack is corrupt", packed.path);
+            std::process::exit(1);
+        }
+
+        let target = into.join(&packed.archive_path);
+        let written = fs::create_dir_all(target.parent().unwrap_or(&into)).and_then(|_| fs::write(&target, &bytes));
+        if let Err(err) = written {
+            fail(&format!("Failed to write {}", target.display()), err);
+        }
+        sections.push(Section {
+            title: packed.path.clone(),
+            body: String::from_utf8_lossy(&bytes).to_string(),
+        });
+    }
+    fs::write(into.join(MANIFEST_NAME), serde_json::to_string_pretty(&manifest).unwrap_or_default())
+        .unwrap_or_else(|err| fail(&format!("Failed to write {}", into.join(MANIFEST_NAME).display()), err));
+
+    info!(
+        "Unpacked {} files from '{}' ({}, fdllm {}) into {}",
+        manifest.files.len(),
+        manifest.profile,
+        manifest.created,
+        manifest.fdllm_version,
+        into.display()
+    );
+
+    // The reassembled bundle keeps the original headers
+    copy_bundle(Bundle {
+        sections,
+        ..Default::default()
+    })
+}
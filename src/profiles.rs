@@ -0,0 +1,294 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::Ordering;
+use toml_edit::{Array, DocumentMut, Item, Table, TableLike};
+
+use crate::{
+    exit_codes, expand_tilde, get_config_path, is_glob, load_config, output, porcelain_record, project_config_path,
+    suggest, Config, Error, QUIET,
+};
+
+// `fdllm profiles list`: each profile with what it collects
+pub fn list() -> Result<(), Error> {
+    let config = load_config(&get_config_path()?)?;
+    let names = config.profile_names();
+    if names.is_empty() {
+        info!("No profiles defined in config");
+        return Ok(());
+    }
+    for name in names {
+        let profile = &config.profiles.as_ref().expect("profiles are present")[name];
+        porcelain_record("profile", name);
+        let mut parts = Vec::new();
+        let files = profile.files.as_ref().map_or(0, Vec::len);
+        let directories = profile.directories.as_ref().map_or(0, Vec::len);
+        if files > 0 {
+            parts.push(format!("{} file(s)", files));
+        }
+        if directories > 0 {
+            parts.push(format!("{} directory(ies)", directories));
+        }
+        if let Some(project) = &profile.project {
+            parts.push(format!("tree {}", project.path));
+        }
+        if profile.database.is_some() {
+            parts.push("database schema".to_string());
+        }
+        if profile.locked.unwrap_or(false) {
+            parts.push("locked".to_string());
+        }
+        info!("{:<20} {}", name, parts.join(", "));
+    }
+    Ok(())
+}
+
+// A missing file is an empty config, so `add` can start one
+pub fn read_document(path: &Path) -> Result<DocumentMut, Error> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(Error::Config(format!("Failed to read config file {}: {}", path.display(), err))),
+    };
+    content
+        .parse()
+        .map_err(|err| Error::Config(format!("Failed to parse config file: {}\nError: {}", path.display(), err)))
+}
+
+// Checked like `fdllm config edit` checks, before anything is written
+pub fn save(path: &Path, document: &DocumentMut) -> Result<(), Error> {
+    let content = document.to_string();
+    if let Err(err) = Config::from_toml(&content) {
+        return Err(Error::Config(format!("Not saved: {} would no longer load: {}", path.display(), err)));
+    }
+    output::write_atomically(path, &content)
+        .map_err(|err| Error::Output(format!("Failed to write {}: {}", path.display(), err)))
+}
+
+fn not_found(name: &str, document: &DocumentMut) -> Error {
+    let names: Vec<&str> = profiles(document).map(|profiles| profiles.iter().map(|(name, _)| name).collect()).unwrap_or_default();
+    Error::UnknownProfile {
+        name: name.to_string(),
+        suggestions: suggest::close_matches(name, &names).into_iter().map(String::from).collect(),
+    }
+}
+
+pub fn profiles(document: &DocumentMut) -> Option<&dyn TableLike> {
+    document.get("profiles")?.as_table_like()
+}
+
+// The profile's table as written, comments included, under its `[profiles.NAME]` header
+fn profile_toml(document: &DocumentMut, name: &str) -> Option<String> {
+    let profile = profiles(document)?.get(name)?;
+    let mut wrapper = Table::new();
+    wrapper.set_implicit(true);
+    wrapper.insert(name, profile.clone());
+    let mut single = DocumentMut::new();
+    single.insert("profiles", Item::Table(wrapper));
+    Some(single.to_string())
+}
+
+fn path_array(paths: &[String]) -> Item {
+    let mut array = Array::new();
+    for path in paths {
+        array.push(path.as_str());
+    }
+    toml_edit::value(array)
+}
+
+// A new `[profiles.NAME]` table after everything else in the file
+fn insert_profile(document: &mut DocumentMut, name: &str, files: &[String], dirs: &[String]) -> Result<(), String> {
+    let profiles = document.entry("profiles").or_insert_with(|| {
+        let mut table = Table::new();
+        table.set_implicit(true);
+        Item::Table(table)
+    });
+    let Some(profiles) = profiles.as_table_like_mut() else {
+        return Err("`profiles` in the config is not a table".to_string());
+    };
+    if profiles.contains_key(name) {
+        return Err(format!("Profile '{}' already exists; change it with `fdllm config edit`", name));
+    }
+    let mut profile = Table::new();
+    if !files.is_empty() {
+        profile.insert("files", path_array(files));
+    }
+    if !dirs.is_empty() {
+        profile.insert("directories", path_array(dirs));
+    }
+    profiles.insert(name, Item::Table(profile));
+    Ok(())
+}
+
+fn layers(profile: &Item) -> Vec<&str> {
+    let extends = profile.get("extends").and_then(Item::as_str);
+    let included = profile.get("include_profiles").and_then(Item::as_array);
+    extends.into_iter().chain(included.into_iter().flatten().filter_map(|value| value.as_str())).collect()
+}
+
+// A profile others are layered on stays, rather than leaving them pointing at nothing
+fn remove_profile(document: &mut DocumentMut, name: &str) -> Result<(), String> {
+    let Some(profiles) = document.get_mut("profiles").and_then(Item::as_table_like_mut) else {
+        return Err(format!("Profile '{}' not found in config", name));
+    };
+    if !profiles.contains_key(name) {
+        return Err(format!("Profile '{}' not found in config", name));
+    }
+    let dependents: Vec<&str> = profiles
+        .iter()
+        .filter(|(_, profile)| layers(profile).contains(&name))
+        .map(|(other, _)| other)
+        .collect();
+    if !dependents.is_empty() {
+        return Err(format!(
+            "Profile '{}' is inherited by {}; remove it from their extends/include_profiles first",
+            name,
+            dependents.join(", ")
+        ));
+    }
+    profiles.remove(name);
+    Ok(())
+}
+
+// Relative paths are made absolute, since the config is read from wherever fdllm runs;
+// `~/...`, globs and absolute paths are kept as typed
+fn config_entry(path: &str, directory: bool) -> String {
+    let entry = if path.starts_with('~') || Path::new(path).is_absolute() {
+        path.to_string()
+    } else {
+        let current = std::env::current_dir().unwrap_or_default();
+        let absolute: PathBuf = current
+            .join(path)
+            .components()
+            .filter(|component| *component != Component::CurDir)
+            .collect();
+        absolute.to_string_lossy().to_string()
+    };
+    let resolved = expand_tilde(&entry);
+    let exists = if directory { resolved.is_dir() } else { resolved.is_file() };
+    if !exists && !is_glob(&entry, &[]) && !QUIET.load(Ordering::Relaxed) {
+        eprintln!("Not found, added anyway: {}", resolved.display());
+    }
+    entry
+}
+
+// `fdllm profiles show NAME`: the project config's definition wins, as it does when bundling
+pub fn show(name: &str) -> Result<(), Error> {
+    let config_path = get_config_path()?;
+    let mut sources: Vec<&Path> = project_config_path().map(|path| path.as_path()).into_iter().collect();
+    sources.push(&config_path);
+    let mut global = None;
+    for source in sources {
+        let document = read_document(source)?;
+        if let Some(text) = profile_toml(&document, name) {
+            println!("# {}", source.display());
+            print!("{}", text);
+            let config = load_config(&config_path)?;
+            if let Some(profile) = config.profile(name).filter(|_| !layers(&document["profiles"][name]).is_empty()) {
+                println!(
+                    "# with inherited settings: {} file(s), {} directory(ies)",
+                    profile.files().len(),
+                    profile.directories().len()
+                );
+            }
+            return Ok(());
+        }
+        global = Some(document);
+    }
+    Err(not_found(name, &global.expect("the global config is always read")))
+}
+
+// `fdllm profiles add NAME --files ... --dirs ...`
+pub fn add(name: &str, files: &[String], dirs: &[String]) -> Result<(), Error> {
+    if files.is_empty() && dirs.is_empty() {
+        eprintln!("Nothing to add: give --files and/or --dirs");
+        std::process::exit(exit_codes::CONFIG);
+    }
+    let config_path = get_config_path()?;
+    let mut document = read_document(&config_path)?;
+    if profiles(&document).is_some_and(|profiles| profiles.contains_key(name)) {
+        eprintln!("Profile '{}' already exists; change it with `fdllm config edit`", name);
+        std::process::exit(exit_codes::CONFIG);
+    }
+    let files: Vec<String> = files.iter().map(|file| config_entry(file, false)).collect();
+    let dirs: Vec<String> = dirs.iter().map(|dir| config_entry(dir, true)).collect();
+    if let Err(err) = insert_profile(&mut document, name, &files, &dirs) {
+        eprintln!("{}", err);
+        std::process::exit(exit_codes::CONFIG);
+    }
+    save(&config_path, &document)?;
+    porcelain_record("added", name);
+    info!("Added profile '{}' to {}", name, config_path.display());
+    Ok(())
+}
+
+// `fdllm profiles remove NAME`
+pub fn remove(name: &str) -> Result<(), Error> {
+    let config_path = get_config_path()?;
+    let mut document = read_document(&config_path)?;
+    if profiles(&document).is_none_or(|profiles| !profiles.contains_key(name)) {
+        return Err(not_found(name, &document));
+    }
+    if let Err(err) = remove_profile(&mut document, name) {
+        eprintln!("{}", err);
+        std::process::exit(exit_codes::CONFIG);
+    }
+    save(&config_path, &document)?;
+    porcelain_record("removed", name);
+    info!("Removed profile '{}' from {}", name, config_path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"# Shared settings
+format = "markdown"
+
+[profiles.base]
+# The API service
+directories = ["~/app/api"]
+
+[profiles.child]
+extends = "base"
+files = ["~/app/README.md"]
+"#;
+
+    #[test]
+    fn adds_profiles_and_keeps_comments() {
+        let mut document: DocumentMut = CONFIG.parse().unwrap();
+        insert_profile(&mut document, "web", &["/srv/web/index.ts".to_string()], &["/srv/web/src".to_string()]).unwrap();
+        let text = document.to_string();
+        assert!(text.starts_with(CONFIG));
+        assert!(text.ends_with(
+            "\n[profiles.web]\nfiles = [\"/srv/web/index.ts\"]\ndirectories = [\"/srv/web/src\"]\n"
+        ));
+        assert!(Config::from_toml(&text).unwrap().profile("web").is_some());
+        assert!(insert_profile(&mut document, "base", &[], &["/tmp".to_string()]).is_err());
+
+        let mut empty = DocumentMut::new();
+        insert_profile(&mut empty, "solo", &["/a.rs".to_string()], &[]).unwrap();
+        assert_eq!(empty.to_string(), "[profiles.solo]\nfiles = [\"/a.rs\"]\n");
+    }
+
+    #[test]
+    fn removes_only_profiles_nothing_inherits() {
+        let mut document: DocumentMut = CONFIG.parse().unwrap();
+        let err = remove_profile(&mut document, "base").unwrap_err();
+        assert!(err.contains("inherited by child"));
+        remove_profile(&mut document, "child").unwrap();
+        remove_profile(&mut document, "base").unwrap();
+        assert_eq!(document.to_string(), "# Shared settings\nformat = \"markdown\"\n");
+        assert!(remove_profile(&mut document, "base").is_err());
+    }
+
+    #[test]
+    fn shows_a_profile_as_written() {
+        let document: DocumentMut = CONFIG.parse().unwrap();
+        assert_eq!(
+            profile_toml(&document, "base").unwrap(),
+            "\n[profiles.base]\n# The API service\ndirectories = [\"~/app/api\"]\n"
+        );
+        assert!(profile_toml(&document, "missing").is_none());
+    }
+}
@@ -0,0 +1,84 @@
+use std::env;
+use std::path::{Component, Path, PathBuf};
+
+// Looked for in the current directory and each parent, nearest first
+const FILE_NAMES: [&str; 2] = [".fdllm.toml", "fdllm.toml"];
+
+// Keys holding paths, rebased onto the project config's directory; `project.path` and `roots`
+// are handled separately
+const PATH_LISTS: [&str; 2] = ["files", "directories"];
+const PATH_KEYS: [&str; 2] = ["output_path", "signing_key"];
+
+pub fn discover() -> Option<PathBuf> {
+    let cwd = env::current_dir().ok()?;
+    cwd.ancestors()
+        .flat_map(|dir| FILE_NAMES.iter().map(move |name| dir.join(name)))
+        .find(|path| path.is_file())
+}
+
+// Relative paths in a project config mean the same thing from any subdirectory; absolute, `~`
+// and labeled (`backend:src`, `git:HEAD:path`) entries are left alone
+fn rebase_path(value: &mut toml::Value, base: &Path) {
+    if let toml::Value::String(path) = value {
+        if !Path::new(path.as_str()).is_absolute() && !path.starts_with('~') && !path.contains(':') {
+            let relative: PathBuf = Path::new(path.as_str())
+                .components()
+                .filter(|component| *component != Component::CurDir)
+                .collect();
+            let rebased = if relative.as_os_str().is_empty() { base.to_path_buf() } else { base.join(relative) };
+            *path = rebased.to_string_lossy().to_string();
+        }
+    }
+}
+
+fn rebase_table(table: &mut toml::Table, base: &Path) {
+    for key in PATH_LISTS {
+        if let Some(toml::Value::Array(entries)) = table.get_mut(key) {
+            for entry in entries {
+                rebase_path(entry, base);
+            }
+        }
+    }
+    for key in PATH_KEYS {
+        if let Some(value) = table.get_mut(key) {
+            rebase_path(value, base);
+        }
+    }
+    if let Some(toml::Value::Table(project)) = table.get_mut("project") {
+        if let Some(path) = project.get_mut("path") {
+            rebase_path(path, base);
+        }
+    }
+    if let Some(toml::Value::Table(roots)) = table.get_mut("roots") {
+        for (_, path) in roots.iter_mut() {
+            rebase_path(path, base);
+        }
+    }
+}
+
+pub fn rebase(table: &mut toml::Table, base: &Path) {
+    rebase_table(table, base);
+    if let Some(toml::Value::Table(profiles)) = table.get_mut("profiles") {
+        for (_, profile) in profiles.iter_mut() {
+            if let toml::Value::Table(profile) = profile {
+                rebase_table(profile, base);
+            }
+        }
+    }
+}
+
+// Top-level settings in the project config replace the global ones; profiles are merged by name,
+// a project profile replacing a global one of the same name as a whole
+pub fn merge(mut global: toml::Table, local: toml::Table) -> toml::Table {
+    for (key, value) in local {
+        match (key.as_str(), value, global.get_mut("profiles")) {
+            ("profiles", toml::Value::Table(profiles), Some(toml::Value::Table(global_profiles))) => {
+                global_profiles.extend(profiles);
+            }
+            (_, value, _) => {
+                global.insert(key, value);
+            }
+        }
+    }
+    global
+}
@@ -0,0 +1,41 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Root files that orient a reader before the code, in the order they are bundled
+const ROOT_PREFIXES: [&str; 3] = ["readme", "architecture", "contributing"];
+
+// Index pages of a docs/ directory
+const DOCS_DIRECTORIES: [&str; 2] = ["docs", "doc"];
+const INDEX_PREFIXES: [&str; 2] = ["index", "readme"];
+
+// Files in `dir` whose name starts with one of `prefixes` (case-insensitively), grouped by prefix
+fn files_named(dir: &Path, prefixes: &[&str]) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().is_file())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect();
+    names.sort();
+
+    let mut found = Vec::new();
+    for prefix in prefixes {
+        for name in &names {
+            if name.to_lowercase().starts_with(prefix) {
+                found.push(dir.join(name));
+            }
+        }
+    }
+    found
+}
+
+// `include_docs = true`: README*, ARCHITECTURE*, CONTRIBUTING* and docs/ index pages at `root`
+pub fn find(root: &Path) -> Vec<PathBuf> {
+    let mut found = files_named(root, &ROOT_PREFIXES);
+    for dir in DOCS_DIRECTORIES {
+        found.extend(files_named(&root.join(dir), &INDEX_PREFIXES));
+    }
+    found
+}
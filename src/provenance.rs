@@ -0,0 +1,151 @@
+use ed25519_dalek::{Signer, SigningKey};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{deadline, expand_tilde, Error};
+
+// What the footer needs beyond the bundle itself
+pub struct Settings {
+    pub config_path: PathBuf,
+    // Repository whose HEAD is recorded (the project path, or the working directory)
+    pub git_dir: PathBuf,
+    pub signing_key: Option<String>,
+}
+
+// Running hash over (header, content hash) pairs in bundle order
+pub struct Manifest {
+    hasher: Sha256,
+    files: usize,
+}
+
+impl Manifest {
+    pub fn new() -> Manifest {
+        Manifest {
+            hasher: Sha256::new(),
+            files: 0,
+        }
+    }
+
+    pub fn add(&mut self, header: &str, content: &str) {
+        self.hasher.update(header.as_bytes());
+        self.hasher.update([0]);
+        self.hasher.update(hex(&Sha256::digest(content.as_bytes())).as_bytes());
+        self.hasher.update(b"\n");
+        self.files += 1;
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn config_hash(path: &Path) -> String {
+    match fs::read(path) {
+        Ok(content) => hex(&Sha256::digest(&content)),
+        Err(_) => "none".to_string(),
+    }
+}
+
+fn git_commit(dir: &Path) -> String {
+    let output = deadline::output(Command::new("git").arg("-C").arg(dir).args(["rev-parse", "HEAD"]));
+    let commit = match output {
+        Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        _ => return "none".to_string(),
+    };
+
+    let dirty = deadline::output(Command::new("git").arg("-C").arg(dir).args(["status", "--porcelain"]))
+        .map(|output| !output.stdout.is_empty())
+        .unwrap_or(false);
+    if dirty {
+        format!("{}-dirty", commit)
+    } else {
+        commit
+    }
+}
+
+// When a bundle was made, as stamped on it: the epoch under --deterministic
+pub fn now() -> SystemTime {
+    if crate::deterministic() {
+        UNIX_EPOCH
+    } else {
+        SystemTime::now()
+    }
+}
+
+// RFC 3339 UTC timestamp without pulling in a date library
+pub fn utc_timestamp(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3_600,
+        rem % 3_600 / 60,
+        rem % 60
+    )
+}
+
+// The key file holds the 32-byte seed, either raw or as 64 hex characters
+fn load_signing_key(path: &str) -> Result<SigningKey, Error> {
+    let path = expand_tilde(path);
+    let content = fs::read(&path)
+        .map_err(|err| Error::Config(format!("Failed to read signing key {}: {}", path.display(), err)))?;
+
+    let text = String::from_utf8_lossy(&content);
+    let text = text.trim();
+    let seed: Vec<u8> = if text.len() == 64 && text.chars().all(|c| c.is_ascii_hexdigit()) {
+        (0..32)
+            .map(|i| u8::from_str_radix(&text[i * 2..i * 2 + 2], 16).unwrap())
+            .collect()
+    } else {
+        content
+    };
+
+    match <[u8; 32]>::try_from(seed.as_slice()) {
+        Ok(seed) => Ok(SigningKey::from_bytes(&seed)),
+        Err(_) => Err(Error::Config(format!(
+            "Signing key {} must be a 32-byte ed25519 seed (raw or hex)",
+            path.display()
+        ))),
+    }
+}
+
+// Footer appended to `bundle`; the signature covers everything before its own line
+pub fn footer(settings: &Settings, manifest: Manifest, bundle: &str) -> Result<String, Error> {
+    let mut footer = String::from("# NOTE: Provenance:\n");
+    footer.push_str(&format!("fdllm_version: {}\n", env!("CARGO_PKG_VERSION")));
+    footer.push_str(&format!("config_sha256: {}\n", config_hash(&settings.config_path)));
+    footer.push_str(&format!("git_commit: {}\n", git_commit(&settings.git_dir)));
+    footer.push_str(&format!("timestamp: {}\n", utc_timestamp(now())));
+    footer.push_str(&format!("files: {}\n", manifest.files));
+    footer.push_str(&format!("manifest_sha256: {}\n", hex(&manifest.hasher.finalize())));
+
+    if let Some(path) = &settings.signing_key {
+        // A configured key that cannot be used must not silently produce an unsigned bundle
+        let key = load_signing_key(path)?;
+        footer.push_str(&format!("public_key_ed25519: {}\n", hex(key.verifying_key().as_bytes())));
+        let message = format!("{}{}", bundle, footer);
+        let signature = key.sign(message.as_bytes());
+        footer.push_str(&format!("signature_ed25519: {}\n", hex(&signature.to_bytes())));
+    }
+
+    Ok(footer)
+}
@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_TOP: usize = 3;
+
+pub struct Settings {
+    pub top: usize,
+    // The profile's walked directories, as resolved paths
+    pub directories: Vec<PathBuf>,
+    // False for an ad-hoc run without a config file, where exclude_paths has nowhere to go
+    pub configured: bool,
+}
+
+// `41k` reads better than `41327` in a hint
+fn approximate(tokens: usize) -> String {
+    if tokens >= 1000 {
+        format!("{}k", (tokens + 500) / 1000)
+    } else {
+        tokens.to_string()
+    }
+}
+
+// The heaviest first-level subdirectories of the walked directories, with the exclude that
+// would drop each one; files directly inside a walked directory aren't candidates
+pub fn report(settings: &Settings, files: &[(PathBuf, usize)]) {
+    let total: usize = files.iter().map(|(_, tokens)| tokens).sum();
+    if total == 0 {
+        return;
+    }
+
+    let mut per_directory: HashMap<(&Path, String), usize> = HashMap::new();
+    for (path, tokens) in files {
+        for directory in &settings.directories {
+            let relative = match path.strip_prefix(directory) {
+                Ok(relative) => relative,
+                Err(_) => continue,
+            };
+            let mut components = relative.components();
+            let first = components.next();
+            if let (Some(first), Some(_)) = (first, components.next()) {
+                let name = first.as_os_str().to_string_lossy().to_string();
+                *per_directory.entry((directory.as_path(), name)).or_insert(0) += tokens;
+            }
+            break;
+        }
+    }
+    if per_directory.is_empty() {
+        return;
+    }
+
+    let mut heaviest: Vec<_> = per_directory.into_iter().collect();
+    heaviest.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    info!("Heaviest directories:");
+    for ((directory, name), tokens) in heaviest.into_iter().take(settings.top) {
+        if settings.configured {
+            info!(
+                "  {}  {}%: add '{}/**' to exclude_paths to save ~{} tokens",
+                directory.join(&name).display(),
+                tokens * 100 / total,
+                name,
+                approximate(tokens)
+            );
+        } else {
+            info!(
+                "  {}  {}%: ~{} tokens",
+                directory.join(&name).display(),
+                tokens * 100 / total,
+                approximate(tokens)
+            );
+        }
+    }
+    if !settings.configured {
+        info!("Run `fdllm init` for a config whose exclude_paths can leave these out");
+    }
+}
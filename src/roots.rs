@@ -0,0 +1,100 @@
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::expand_tilde;
+
+// A labeled project root (`backend = "~/app/api"`)
+pub struct Root {
+    pub label: String,
+    pub path: PathBuf,
+    canonical: Option<PathBuf>,
+}
+
+pub fn load_roots(roots: Option<&BTreeMap<String, String>>) -> Vec<Root> {
+    let mut loaded = Vec::new();
+    for (label, path) in roots.into_iter().flatten() {
+        let path = expand_tilde(path);
+        if !path.is_dir() {
+            eprintln!("Root '{}' not found or not a directory: {}", label, path.display());
+        }
+        let canonical = fs::canonicalize(&path).ok();
+        loaded.push(Root {
+            label: label.clone(),
+            path,
+            canonical,
+        });
+    }
+    loaded
+}
+
+// `backend:src/main.rs` resolves against the `backend` root; anything else is a plain path
+pub fn resolve(entry: &str, roots: &[Root]) -> PathBuf {
+    if let Some((label, rest)) = entry.split_once(':') {
+        if let Some(root) = roots.iter().find(|root| root.label == label) {
+            return root.path.join(rest.trim_start_matches('/'));
+        }
+    }
+    expand_tilde(entry)
+}
+
+// Header path for a file: `backend/src/main.rs` when it lives below a root
+pub fn display(entry: &str, path: &Path, roots: &[Root]) -> String {
+    if roots.is_empty() {
+        return entry.to_string();
+    }
+
+    let canonical = match fs::canonicalize(path) {
+        Ok(canonical) => canonical,
+        Err(_) => return entry.to_string(),
+    };
+
+    // The most specific root wins when roots are nested
+    roots
+        .iter()
+        .filter_map(|root| {
+            let root_path = root.canonical.as_ref()?;
+            let relative = canonical.strip_prefix(root_path).ok()?;
+            Some((root_path.components().count(), root, relative))
+        })
+        .max_by_key(|(depth, _, _)| *depth)
+        .map(|(_, root, relative)| Path::new(&root.label).join(relative).to_string_lossy().to_string())
+        .unwrap_or_else(|| entry.to_string())
+}
+
+// `rewrite_paths = [{ from = "/home/alice/work/", to = "" }]`: a prefix replaced in headers and
+// tree titles, so bundles from different machines read the same
+#[derive(Deserialize, JsonSchema, Clone)]
+pub struct PathRewrite {
+    pub from: String,
+    pub to: String,
+}
+
+// The first rule whose `from` (with `~` expanded) prefixes the path applies
+pub fn rewrite(path: &str, rules: &[PathRewrite]) -> String {
+    for rule in rules {
+        let expanded = if rule.from.starts_with('~') {
+            let mut expanded = expand_tilde(&rule.from).to_string_lossy().to_string();
+            if rule.from.ends_with('/') && !expanded.ends_with('/') {
+                expanded.push('/');
+            }
+            expanded
+        } else {
+            rule.from.clone()
+        };
+        if let Some(rest) = path.strip_prefix(&expanded) {
+            return format!("{}{}", rule.to, rest);
+        }
+    }
+    path.to_string()
+}
+
+// A rendered tree starts with its root's path
+pub fn rewrite_tree(tree: &str, rules: &[PathRewrite]) -> String {
+    match tree.split_once('\n') {
+        Some((first, rest)) => format!("{}\n{}", rewrite(first, rules), rest),
+        None => rewrite(tree, rules),
+    }
+}
@@ -0,0 +1,87 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+// `--send-keys tmux:PANE`: the bundle is pasted into a pane running a terminal LLM client
+#[derive(Clone)]
+pub struct SendKeys {
+    // Any tmux target: `%3`, `2`, `work:1.2`
+    pub pane: String,
+    // Type the path of a file holding the bundle instead of the bundle (--send-path)
+    pub path: bool,
+}
+
+impl SendKeys {
+    pub fn parse(value: &str) -> Option<SendKeys> {
+        let pane = value.strip_prefix("tmux:")?;
+        (!pane.is_empty()).then(|| SendKeys {
+            pane: pane.to_string(),
+            path: false,
+        })
+    }
+
+    pub fn label(&self) -> String {
+        format!("tmux {}", self.pane)
+    }
+}
+
+fn tmux(args: &[&str], input: Option<&str>) -> Result<String, String> {
+    let mut child = Command::new("tmux")
+        .args(args)
+        .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| match err.kind() {
+            std::io::ErrorKind::NotFound => "tmux not found".to_string(),
+            _ => format!("Failed to run tmux: {}", err),
+        })?;
+    if let Some(input) = input {
+        let written = child.stdin.take().expect("stdin is piped").write_all(input.as_bytes());
+        if let Err(err) = written {
+            let _ = child.wait();
+            return Err(format!("Failed to write to tmux: {}", err));
+        }
+    }
+    let output = child.wait_with_output().map_err(|err| format!("Failed to run tmux: {}", err))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        Err(format!("tmux: {}", String::from_utf8_lossy(&output.stderr).trim()))
+    }
+}
+
+// The pane's id (`%3`), checked before anything is delivered. Some tmux versions print nothing
+// and succeed for a target that matches no pane.
+pub fn pane_id(pane: &str) -> Result<String, String> {
+    let id = tmux(&["display-message", "-p", "-t", pane, "#{pane_id}"], None)?;
+    if id.is_empty() {
+        return Err("no such pane".to_string());
+    }
+    Ok(id)
+}
+
+// Pasted rather than typed key by key: a buffer paste is one write however large the bundle,
+// and bracketed paste (-p) keeps clients from submitting at the first newline. Nothing presses
+// Enter, so a question can still be added before sending. The pane is then brought forward.
+pub fn send(pane_id: &str, text: &str) -> Result<(), String> {
+    let buffer = format!("fdllm-{}", std::process::id());
+    tmux(&["load-buffer", "-b", &buffer, "-"], Some(text))?;
+    tmux(&["paste-buffer", "-p", "-d", "-b", &buffer, "-t", pane_id], None)?;
+    let _ = tmux(&["select-window", "-t", pane_id], None);
+    let _ = tmux(&["select-pane", "-t", pane_id], None);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_tmux_targets() {
+        assert_eq!(SendKeys::parse("tmux:%3").unwrap().pane, "%3");
+        assert_eq!(SendKeys::parse("tmux:work:1.2").unwrap().pane, "work:1.2");
+        assert_eq!(SendKeys::parse("tmux:2").unwrap().label(), "tmux 2");
+        assert!(SendKeys::parse("tmux:").is_none());
+        assert!(SendKeys::parse("screen:1").is_none());
+    }
+}
@@ -0,0 +1,159 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::exit_codes;
+
+// Overrides for the current conversation, kept next to the project instead of in the config
+const SESSION_FILE: &str = ".fdllm.session.toml";
+
+// A session left behind longer than this no longer applies
+const SESSION_TTL: Duration = Duration::from_secs(12 * 3600);
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Session {
+    // Seconds since the Unix epoch when the session was started
+    created: u64,
+    // Paths or globs (matched against the listed path, the path relative to the working
+    // directory, and the file name) dropped from the bundle
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    // Extra files bundled after the profile's own
+    #[serde(default)]
+    pub add: Vec<String>,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+fn session_path() -> PathBuf {
+    PathBuf::from(SESSION_FILE)
+}
+
+fn read() -> Option<Session> {
+    let content = fs::read_to_string(session_path()).ok()?;
+    match toml::from_str(&content) {
+        Ok(session) => Some(session),
+        Err(err) => {
+            eprintln!("Ignoring invalid {}: {}", SESSION_FILE, err);
+            None
+        }
+    }
+}
+
+fn expired(session: &Session) -> bool {
+    now().saturating_sub(session.created) > SESSION_TTL.as_secs()
+}
+
+// The active session, if one exists and hasn't timed out
+pub fn load() -> Option<Session> {
+    let session = read()?;
+    if expired(&session) {
+        info!("Session overrides in {} have expired; run `fdllm session reset`", SESSION_FILE);
+        return None;
+    }
+    Some(session)
+}
+
+fn save(session: &Session) {
+    let content = toml::to_string(session).expect("Failed to serialize session");
+    let header = "# Temporary fdllm overrides; `fdllm session reset` removes them\n";
+    if let Err(err) = fs::write(session_path(), format!("{}{}", header, content)) {
+        eprintln!("Failed to write {}: {}", SESSION_FILE, err);
+        std::process::exit(1);
+    }
+}
+
+impl Session {
+    pub fn excludes(&self, entry: &str, path: &Path) -> bool {
+        let relative = std::env::current_dir()
+            .ok()
+            .and_then(|cwd| path.strip_prefix(cwd).ok().map(Path::to_path_buf));
+        let canonical = fs::canonicalize(path).ok();
+        self.exclude.iter().any(|pattern| {
+            if let (Some(canonical), Ok(excluded)) = (&canonical, fs::canonicalize(pattern)) {
+                if *canonical == excluded || canonical.starts_with(&excluded) {
+                    return true;
+                }
+            }
+            let pattern = match glob::Pattern::new(pattern) {
+                Ok(pattern) => pattern,
+                Err(_) => return false,
+            };
+            pattern.matches(entry)
+                || relative.as_ref().is_some_and(|relative| pattern.matches_path(relative))
+                || path.file_name().is_some_and(|name| pattern.matches(&name.to_string_lossy()))
+        })
+    }
+}
+
+// `fdllm exclude <path>... --for-session` and `fdllm add <path>... --for-session`
+pub fn override_command(command: &str, args: &[String]) {
+    let for_session = args.iter().any(|arg| arg == "--for-session");
+    let paths: Vec<&String> = args.iter().filter(|arg| *arg != "--for-session").collect();
+    if paths.is_empty() || paths.iter().any(|path| path.starts_with('-')) {
+        eprintln!("Usage: fdllm {} <path>... --for-session", command);
+        std::process::exit(exit_codes::CONFIG);
+    }
+    if !for_session {
+        eprintln!("Only session overrides are supported; pass --for-session");
+        std::process::exit(exit_codes::CONFIG);
+    }
+
+    let mut session = read().filter(|session| !expired(session)).unwrap_or_else(|| Session {
+        created: now(),
+        ..Default::default()
+    });
+    let list = if command == "exclude" { &mut session.exclude } else { &mut session.add };
+    for path in paths {
+        if !list.contains(path) {
+            list.push(path.clone());
+        }
+    }
+    save(&session);
+    info!(
+        "Session: {} excluded, {} added (in {}, until `fdllm session reset`)",
+        session.exclude.len(),
+        session.add.len(),
+        SESSION_FILE
+    );
+}
+
+pub fn run(args: &[String]) {
+    match args.first().map(String::as_str) {
+        None | Some("show") => match read() {
+            Some(session) => {
+                let state = if expired(&session) { " (expired)" } else { "" };
+                info!("Session overrides in {}{}:", SESSION_FILE, state);
+                for path in &session.exclude {
+                    info!("  exclude {}", path);
+                }
+                for path in &session.add {
+                    info!("  add     {}", path);
+                }
+            }
+            None => info!("No session overrides"),
+        },
+        Some("reset") => {
+            let path = session_path();
+            if path.exists() {
+                if let Err(err) = fs::remove_file(&path) {
+                    eprintln!("Failed to remove {}: {}", path.display(), err);
+                    std::process::exit(1);
+                }
+                info!("Session overrides cleared");
+            } else {
+                info!("No session overrides");
+            }
+        }
+        _ => {
+            eprintln!("Usage: fdllm session [show|reset]");
+            std::process::exit(exit_codes::CONFIG);
+        }
+    }
+}
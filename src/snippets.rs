@@ -0,0 +1,176 @@
+use copypasta::{ClipboardContext, ClipboardProvider};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, IsTerminal, Read};
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use crate::{copy_bundle, exit_codes, get_state_dir, Bundle, Error, Section};
+
+// Copied text ending with this marker is picked up by clip-watch
+const SENTINEL: &str = "%%fdllm";
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Serialize, Deserialize)]
+pub struct Snippet {
+    pub name: String,
+    pub content: String,
+}
+
+fn pending_path() -> PathBuf {
+    get_state_dir().join("snippets.json")
+}
+
+pub fn load_pending() -> Vec<Snippet> {
+    let path = pending_path();
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(_) => return Vec::new(),
+    };
+    match serde_json::from_str(&content) {
+        Ok(snippets) => snippets,
+        Err(err) => {
+            eprintln!("Failed to parse pending snippets {}: {}", path.display(), err);
+            Vec::new()
+        }
+    }
+}
+
+fn save_pending(snippets: &[Snippet]) {
+    let path = pending_path();
+    let content = serde_json::to_string_pretty(snippets).expect("Failed to serialize snippets");
+    if let Err(err) = fs::write(&path, content) {
+        eprintln!("Failed to write pending snippets {}: {}", path.display(), err);
+    }
+}
+
+pub fn append_pending(snippet: Snippet) {
+    let mut snippets = load_pending();
+    snippets.push(snippet);
+    save_pending(&snippets);
+}
+
+pub fn clear_pending() {
+    let path = pending_path();
+    if path.exists() {
+        if let Err(err) = fs::remove_file(&path) {
+            eprintln!("Failed to clear pending snippets {}: {}", path.display(), err);
+        }
+    }
+}
+
+// `some text %%fdllm` -> `some text`; None when the sentinel is missing
+fn strip_sentinel(text: &str) -> Option<String> {
+    let trimmed = text.trim_end();
+    let content = trimmed.strip_suffix(SENTINEL)?.trim_end();
+    if content.is_empty() {
+        None
+    } else {
+        Some(content.to_string())
+    }
+}
+
+pub fn clip_watch(args: &[String]) {
+    if !args.is_empty() {
+        eprintln!("Usage: fdllm clip-watch");
+        std::process::exit(exit_codes::CONFIG);
+    }
+
+    let mut ctx = match ClipboardContext::new() {
+        Ok(ctx) => ctx,
+        Err(err) => {
+            eprintln!("Failed to access clipboard: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    info!(
+        "Watching clipboard; copy text ending with '{}' to collect it (Ctrl-C to stop)",
+        SENTINEL
+    );
+
+    // Whatever is on the clipboard at startup was not copied for us
+    let mut last = ctx.get_contents().unwrap_or_default();
+    let mut collected = load_pending().len();
+
+    loop {
+        thread::sleep(POLL_INTERVAL);
+
+        let current = match ctx.get_contents() {
+            Ok(current) => current,
+            Err(_) => continue,
+        };
+        if current == last {
+            continue;
+        }
+
+        if let Some(content) = strip_sentinel(&current) {
+            collected += 1;
+            let lines = content.lines().count();
+            append_pending(Snippet {
+                name: format!("clipboard #{}", collected),
+                content,
+            });
+            info!("Collected snippet #{} ({} lines)", collected, lines);
+        }
+        last = current;
+    }
+}
+
+// `cmd | fdllm snip --name "prod error log"` copies the piped text as a named section;
+// with --append-to-session it is held for the next bundle instead
+pub fn snip(args: &[String]) -> Result<(), Error> {
+    let usage = "Usage: <command> | fdllm snip [--name NAME] [--append-to-session]";
+    let mut name = None;
+    let mut append = false;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--name" => match iter.next() {
+                Some(value) => name = Some(value.clone()),
+                None => {
+                    eprintln!("--name expects a snippet name");
+                    std::process::exit(exit_codes::CONFIG);
+                }
+            },
+            "--append-to-session" => append = true,
+            _ => {
+                eprintln!("{}", usage);
+                std::process::exit(exit_codes::CONFIG);
+            }
+        }
+    }
+
+    if io::stdin().is_terminal() {
+        eprintln!("{}", usage);
+        std::process::exit(exit_codes::CONFIG);
+    }
+    let mut content = String::new();
+    if let Err(err) = io::stdin().read_to_string(&mut content) {
+        eprintln!("Failed to read stdin: {}", err);
+        std::process::exit(1);
+    }
+    let content = content.trim_end().to_string();
+    if content.is_empty() {
+        eprintln!("Nothing on stdin to snip");
+        std::process::exit(exit_codes::EMPTY);
+    }
+
+    let name = name.unwrap_or_else(|| "stdin".to_string());
+    if append {
+        let lines = content.lines().count();
+        append_pending(Snippet {
+            name: name.clone(),
+            content,
+        });
+        info!("Saved snippet '{}' ({} lines) for the next bundle", name, lines);
+        return Ok(());
+    }
+
+    copy_bundle(Bundle {
+        sections: vec![Section { title: name, body: content }],
+        ..Default::default()
+    })
+}
@@ -0,0 +1,39 @@
+use std::fs;
+use std::path::Path;
+
+// Linux pseudo filesystems: their "regular" files can block on read or never end
+const PSEUDO_ROOTS: [&str; 3] = ["/proc", "/sys", "/dev"];
+
+// FIFOs, sockets and device nodes, which a read can hang on; None for anything else
+#[cfg(unix)]
+pub fn kind(path: &Path) -> Option<&'static str> {
+    use std::os::unix::fs::FileTypeExt;
+
+    let file_type = fs::metadata(path).ok()?.file_type();
+    if file_type.is_fifo() {
+        Some("FIFO")
+    } else if file_type.is_socket() {
+        Some("socket")
+    } else if file_type.is_block_device() {
+        Some("block device")
+    } else if file_type.is_char_device() {
+        Some("character device")
+    } else {
+        None
+    }
+}
+
+#[cfg(not(unix))]
+pub fn kind(_path: &Path) -> Option<&'static str> {
+    None
+}
+
+pub fn is_pseudo(path: &Path) -> bool {
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    PSEUDO_ROOTS.iter().any(|root| path.starts_with(root))
+}
+
+// For readers that skip such files quietly
+pub fn safe_to_read(path: &Path) -> bool {
+    kind(path).is_none() && !is_pseudo(path)
+}
@@ -0,0 +1,132 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use crate::{output, tokens, Error};
+
+// The bundle while it is put together: a string until it would pass `max_memory`, then a temp
+// file that every later piece is appended to. A failed write is kept for `finish` to return,
+// and everything after it is dropped
+pub struct Assembly {
+    content: String,
+    limit: Option<u64>,
+    spill: Option<Spill>,
+    error: Option<Error>,
+}
+
+pub struct Spill {
+    pub path: PathBuf,
+    file: BufWriter<File>,
+    pub bytes: usize,
+    // Counted piece by piece, since the whole bundle is never in memory again
+    pub tokens: usize,
+    pub encodings: BTreeMap<String, usize>,
+}
+
+pub enum Assembled {
+    Memory(String),
+    Spilled(Spill),
+}
+
+impl Spill {
+    fn write(&mut self, piece: &str) -> Result<(), Error> {
+        self.file
+            .write_all(piece.as_bytes())
+            .map_err(|err| Error::Output(format!("Failed to write {}: {}", self.path.display(), err)))?;
+        self.bytes += piece.len();
+        let o200k = tokens::count(piece);
+        self.tokens += o200k;
+        for (name, count) in tokens::count_all(piece, o200k) {
+            *self.encodings.entry(name).or_default() += count;
+        }
+        Ok(())
+    }
+
+    // For a run that delivers nothing (--dry-run)
+    pub fn discard(self) {
+        drop(self.file);
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+impl Assembly {
+    pub fn new(content: String, limit: Option<u64>) -> Assembly {
+        Assembly {
+            content,
+            limit,
+            spill: None,
+            error: None,
+        }
+    }
+
+    pub fn push_str(&mut self, piece: &str) {
+        if self.error.is_none() {
+            if let Err(err) = self.try_push_str(piece) {
+                self.error = Some(err);
+            }
+        }
+    }
+
+    fn try_push_str(&mut self, piece: &str) -> Result<(), Error> {
+        if let Some(spill) = &mut self.spill {
+            return spill.write(piece);
+        }
+        match self.limit {
+            Some(limit) if (self.content.len() + piece.len()) as u64 > limit => {
+                let (path, file) = output::create_fallback()
+                    .map_err(|err| Error::Output(format!("Failed to create a file in the temp directory: {}", err)))?;
+                // Kept here first, so a failed write below still removes the file
+                let spill = self.spill.insert(Spill {
+                    path,
+                    file: BufWriter::new(file),
+                    bytes: 0,
+                    tokens: 0,
+                    encodings: BTreeMap::new(),
+                });
+                spill.write(&std::mem::take(&mut self.content))?;
+                spill.write(piece)
+            }
+            _ => {
+                self.content.push_str(piece);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.content.is_empty() && self.spill.is_none()
+    }
+
+    pub fn spilled(&self) -> bool {
+        self.spill.is_some()
+    }
+
+    // Everything so far, as long as it is still in memory
+    pub fn in_memory(&self) -> Option<&str> {
+        match self.spill {
+            Some(_) => None,
+            None => Some(&self.content),
+        }
+    }
+
+    pub fn finish(self) -> Result<Assembled, Error> {
+        match self.spill {
+            Some(mut spill) => {
+                let flushed = match self.error {
+                    Some(err) => Err(err),
+                    None => spill
+                        .file
+                        .flush()
+                        .map_err(|err| Error::Output(format!("Failed to write {}: {}", spill.path.display(), err))),
+                };
+                if let Err(err) = flushed {
+                    spill.discard();
+                    return Err(err);
+                }
+                Ok(Assembled::Spilled(spill))
+            }
+            None => Ok(Assembled::Memory(self.content)),
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use std::io::{self, IsTerminal, Read};
+
+// One path per line, as `git ls-files` and `fd` print them; NUL-separated when the input has any
+// NUL (`git ls-files -z`, `fd -0`), so names with newlines survive. Blank lines are skipped.
+pub fn split(input: &str) -> Vec<String> {
+    let separator = if input.contains('\0') { '\0' } else { '\n' };
+    input
+        .split(separator)
+        .map(|path| if separator == '\n' { path.trim_end_matches('\r') } else { path })
+        .filter(|path| !path.trim().is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+// `fdllm --stdin`: the paths piped in, a terminal being a mistake rather than a list to type
+pub fn read() -> Vec<String> {
+    if io::stdin().is_terminal() {
+        eprintln!("--stdin reads paths piped in, e.g. `git ls-files '*.rs' | fdllm --stdin`");
+        std::process::exit(crate::exit_codes::CONFIG);
+    }
+    let mut input = String::new();
+    if let Err(err) = io::stdin().read_to_string(&mut input) {
+        eprintln!("Failed to read stdin: {}", err);
+        std::process::exit(1);
+    }
+    split(&input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_path_lists() {
+        assert_eq!(split("src/main.rs\r\n\nsrc/lib.rs\n"), ["src/main.rs", "src/lib.rs"]);
+        assert_eq!(split("a b.rs\0line\nbreak.rs\0"), ["a b.rs", "line\nbreak.rs"]);
+        assert!(split("\n  \n").is_empty());
+    }
+}
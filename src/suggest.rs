@@ -0,0 +1,52 @@
+use std::io::{self, BufRead, IsTerminal, Write};
+
+// How many close matches a "did you mean" offers
+const MAX_SUGGESTIONS: usize = 5;
+
+// Levenshtein distance over characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+// Names within a third of the typed name's length (at least 2 edits), or containing it, closest first
+pub fn close_matches<'a>(name: &str, candidates: &[&'a str]) -> Vec<&'a str> {
+    let lowered = name.to_lowercase();
+    let limit = (name.chars().count() / 3).max(2);
+    let mut scored: Vec<(usize, &str)> = candidates
+        .iter()
+        .map(|candidate| (edit_distance(&lowered, &candidate.to_lowercase()), *candidate))
+        .filter(|(distance, candidate)| *distance <= limit || candidate.to_lowercase().contains(&lowered))
+        .collect();
+    scored.sort();
+    scored.into_iter().take(MAX_SUGGESTIONS).map(|(_, candidate)| candidate).collect()
+}
+
+// Only when a person is there to answer: both stdin and stderr are terminals
+pub fn interactive() -> bool {
+    io::stdin().is_terminal() && io::stderr().is_terminal()
+}
+
+// A numbered pick list on stderr; None when cancelled or the answer is not a listed number
+pub fn pick(prompt: &str, choices: &[&str]) -> Option<String> {
+    eprintln!("{}", prompt);
+    for (index, choice) in choices.iter().enumerate() {
+        eprintln!("  {}) {}", index + 1, choice);
+    }
+    eprint!("Number (Enter to cancel): ");
+    let _ = io::stderr().flush();
+
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer).ok()?;
+    let index: usize = answer.trim().parse().ok()?;
+    choices.get(index.checked_sub(1)?).map(|choice| choice.to_string())
+}
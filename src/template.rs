@@ -0,0 +1,208 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+// What fdllm fills in itself; `vars` from the config and --var add to these
+pub const BUILTINS: [&str; 8] = ["header", "tree", "sections", "files", "file_count", "profile", "footer", "prompt"];
+
+// The Handlebars subset a prompt needs: `{{name}}`, `{{#if name}}...{{else}}...{{/if}}` (true
+// when the value isn't blank) and `{{! comments }}`. Values go in as they are, unescaped.
+#[derive(Debug, PartialEq)]
+enum Node {
+    Text(String),
+    Var(String),
+    If(String, Vec<Node>, Vec<Node>),
+}
+
+// A `template` file, checked when it is loaded, with the values known before collecting
+pub struct Template {
+    nodes: Vec<Node>,
+    values: BTreeMap<String, String>,
+    // The template's own text, which the token budget counts as fixed
+    pub literal: String,
+}
+
+fn line_of(source: &str, offset: usize) -> usize {
+    source[..offset].matches('\n').count() + 1
+}
+
+// An `{{#if}}` waiting for its `{{/if}}`
+struct Open {
+    name: String,
+    // What came before the block, which it is added to once closed
+    before: Vec<Node>,
+    // The nodes up to `{{else}}`, once it is seen
+    then: Option<Vec<Node>>,
+    offset: usize,
+}
+
+fn parse(source: &str, known: &dyn Fn(&str) -> bool) -> Result<Vec<Node>, String> {
+    let mut open: Vec<Open> = Vec::new();
+    let mut nodes = Vec::new();
+    let mut rest = source;
+    while let Some(start) = rest.find("{{") {
+        let offset = source.len() - rest.len() + start;
+        if start > 0 {
+            nodes.push(Node::Text(rest[..start].to_string()));
+        }
+        let Some(end) = rest[start..].find("}}") else {
+            return Err(format!("line {}: `{{{{` is never closed", line_of(source, offset)));
+        };
+        let tag = rest[start + 2..start + end].trim();
+        rest = &rest[start + end + 2..];
+        // As in Handlebars, a block tag or comment alone on its line takes the line with it
+        if tag.starts_with(['!', '#', '/']) || tag == "else" {
+            let before = &source[source[..offset].rfind('\n').map_or(0, |newline| newline + 1)..offset];
+            let line_end = rest.find('\n').map_or(rest.len(), |newline| newline + 1);
+            if before.trim().is_empty() && rest[..line_end].trim().is_empty() {
+                if let Some(Node::Text(text)) = nodes.last_mut() {
+                    text.truncate(text.len() - before.len());
+                }
+                rest = &rest[line_end..];
+            }
+        }
+        let check = |name: &str| {
+            if known(name) {
+                Ok(name.to_string())
+            } else {
+                Err(format!("line {}: unknown variable `{}`", line_of(source, offset), name))
+            }
+        };
+        if tag.starts_with('!') {
+            continue;
+        } else if let Some(name) = tag.strip_prefix("#if ") {
+            let name = check(name.trim())?;
+            open.push(Open {
+                name,
+                before: std::mem::take(&mut nodes),
+                then: None,
+                offset,
+            });
+        } else if tag == "else" {
+            let Some(block) = open.last_mut().filter(|block| block.then.is_none()) else {
+                return Err(format!("line {}: `{{{{else}}}}` outside an `{{{{#if}}}}`", line_of(source, offset)));
+            };
+            block.then = Some(std::mem::take(&mut nodes));
+        } else if tag == "/if" {
+            let Some(Open { name, before, then, .. }) = open.pop() else {
+                return Err(format!("line {}: `{{{{/if}}}}` with no `{{{{#if}}}}`", line_of(source, offset)));
+            };
+            let (then, otherwise) = match then {
+                Some(then) => (then, std::mem::take(&mut nodes)),
+                None => (std::mem::take(&mut nodes), Vec::new()),
+            };
+            nodes = before;
+            nodes.push(Node::If(name, then, otherwise));
+        } else {
+            nodes.push(Node::Var(check(tag)?));
+        }
+    }
+    if !rest.is_empty() {
+        nodes.push(Node::Text(rest.to_string()));
+    }
+    match open.last() {
+        Some(block) => Err(format!("line {}: `{{{{#if {}}}}}` is never closed", line_of(source, block.offset), block.name)),
+        None => Ok(nodes),
+    }
+}
+
+fn literal(nodes: &[Node], text: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(part) => text.push_str(part),
+            Node::Var(_) => {}
+            Node::If(_, then, otherwise) => {
+                literal(then, text);
+                literal(otherwise, text);
+            }
+        }
+    }
+}
+
+fn render_nodes(nodes: &[Node], values: &BTreeMap<&str, &str>, out: &mut String) {
+    let value = |name: &str| values.get(name).copied().unwrap_or("");
+    for node in nodes {
+        match node {
+            Node::Text(part) => out.push_str(part),
+            Node::Var(name) => out.push_str(value(name)),
+            Node::If(name, then, otherwise) => {
+                let branch = if value(name).trim().is_empty() { otherwise } else { then };
+                render_nodes(branch, values, out);
+            }
+        }
+    }
+}
+
+impl Template {
+    pub fn new(source: &str, values: BTreeMap<String, String>) -> Result<Template, String> {
+        let nodes = parse(source, &|name| BUILTINS.contains(&name) || values.contains_key(name))?;
+        let mut text = String::new();
+        literal(&nodes, &mut text);
+        Ok(Template { nodes, values, literal: text })
+    }
+
+    pub fn load(path: &Path, values: BTreeMap<String, String>) -> Result<Template, String> {
+        let source = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+        Template::new(&source, values)
+    }
+
+    // `filled` holds the values only known once the bundle is collected (tree, files, ...)
+    pub fn render(&self, filled: &BTreeMap<&str, String>) -> String {
+        let mut values: BTreeMap<&str, &str> = self.values.iter().map(|(key, value)| (key.as_str(), value.as_str())).collect();
+        values.extend(filled.iter().map(|(key, value)| (*key, value.as_str())));
+        let mut out = String::new();
+        render_nodes(&self.nodes, &values, &mut out);
+        out
+    }
+}
+
+// `--var KEY=VALUE`
+pub fn parse_var(value: &str) -> Option<(String, String)> {
+    let (key, value) = value.split_once('=')?;
+    let key = key.trim();
+    (!key.is_empty()).then(|| (key.to_string(), value.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+    }
+
+    #[test]
+    fn renders_variables_and_conditionals() {
+        let template = Template::new(
+            "{{! framing }}Task: {{task}}\n{{#if tree}}Layout:\n{{tree}}{{else}}No tree.\n{{/if}}{{files}}({{file_count}} files)",
+            values(&[("task", "find the bug")]),
+        )
+        .unwrap();
+        assert_eq!(template.literal, "Task: \nLayout:\nNo tree.\n( files)");
+        let mut filled = BTreeMap::new();
+        filled.insert("files", "a.rs\n".to_string());
+        filled.insert("file_count", "1".to_string());
+        assert_eq!(template.render(&filled), "Task: find the bug\nNo tree.\na.rs\n(1 files)");
+        filled.insert("tree", "src/\n".to_string());
+        assert_eq!(template.render(&filled), "Task: find the bug\nLayout:\nsrc/\na.rs\n(1 files)");
+    }
+
+    #[test]
+    fn drops_lines_holding_only_block_tags() {
+        let template = Template::new("{{! note }}\nA\n  {{#if files}}\n{{files}}\n  {{/if}}\nB\n", BTreeMap::new()).unwrap();
+        let mut filled = BTreeMap::new();
+        assert_eq!(template.render(&filled), "A\nB\n");
+        filled.insert("files", "a.rs".to_string());
+        assert_eq!(template.render(&filled), "A\na.rs\nB\n");
+    }
+
+    #[test]
+    fn rejects_malformed_templates() {
+        assert_eq!(Template::new("{{nope}}", BTreeMap::new()).err().unwrap(), "line 1: unknown variable `nope`");
+        assert!(Template::new("\n{{#if tree}}x", BTreeMap::new()).err().unwrap().starts_with("line 2:"));
+        assert!(Template::new("{{/if}}", BTreeMap::new()).is_err());
+        assert!(Template::new("{{else}}", BTreeMap::new()).is_err());
+        assert!(Template::new("{{files", BTreeMap::new()).is_err());
+        assert_eq!(parse_var("task=fix it"), Some(("task".to_string(), "fix it".to_string())));
+        assert_eq!(parse_var("=x"), None);
+    }
+}
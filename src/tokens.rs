@@ -0,0 +1,47 @@
+use std::collections::BTreeMap;
+use tiktoken_rs::{cl100k_base_singleton, o200k_base_singleton};
+
+// The encoding behind the reported counts (GPT-4o and newer); other models' tokenizers land
+// within a few percent of it
+pub const ENCODING: &str = "o200k_base";
+
+// Rough token estimate (~4 characters per token for English text and code)
+pub fn estimate(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+// Exact BPE token count of the text as the model will see it
+pub fn count(text: &str) -> usize {
+    o200k_base_singleton().encode_ordinary(text).len()
+}
+
+// Encoders `fdllm stats` compares, in column order. Only the two tiktoken encodings are real;
+// Claude's tokenizer is not available offline, so `claude~` uses Anthropic's rule of thumb of
+// ~3.5 characters per token. A llama column would need the tokenizer.json that ships with the
+// gated weights, so there is none.
+pub const ENCODERS: [&str; 3] = ["o200k", "cl100k", "claude~"];
+
+// `o200k` is the text's `count`, which every caller already has
+pub fn count_all(text: &str, o200k: usize) -> BTreeMap<String, usize> {
+    let counts = [
+        o200k,
+        cl100k_base_singleton().encode_ordinary(text).len(),
+        (text.chars().count() as f64 / 3.5).ceil() as usize,
+    ];
+    ENCODERS.iter().map(|name| name.to_string()).zip(counts).collect()
+}
+
+// How many of the largest files the summary names
+const SUMMARY_TOP: usize = 10;
+
+pub fn summary(total: usize, files: &[(String, usize)]) {
+    info!("Tokens ({}): {} total, {} file(s)", ENCODING, total, files.len());
+    let mut largest: Vec<&(String, usize)> = files.iter().collect();
+    largest.sort_by_key(|file| std::cmp::Reverse(file.1));
+    for (header, count) in largest.iter().take(SUMMARY_TOP) {
+        info!("  {:>8}  {}", count, header);
+    }
+    if largest.len() > SUMMARY_TOP {
+        info!("  ({} more)", largest.len() - SUMMARY_TOP);
+    }
+}
@@ -0,0 +1,93 @@
+use ignore::WalkBuilder;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::path::Path;
+use std::process::Command;
+
+use crate::{deadline, tuning, unicode_paths, EXCLUDED_NAMES};
+
+// How the project tree is drawn
+#[derive(Deserialize, JsonSchema, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TreeRenderer {
+    // Built in: no external tools, honours ignore rules
+    #[default]
+    Native,
+    // `eza --tree --icons --git`, falling back to the native tree when eza is missing
+    Eza,
+}
+
+fn eza_tree(path: &Path, level: Option<u32>) -> Option<String> {
+    let mut command = Command::new("eza");
+    command.arg("--tree").arg("--icons").arg("--git").arg(path);
+    if let Some(level) = level {
+        command.arg("-L").arg(level.to_string());
+    }
+    let output = deadline::output(&mut command).ok()?;
+    if output.status.success() {
+        Some(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        None
+    }
+}
+
+// `eza --tree`-style drawing: the root, then one `├── name` line per entry, depth-first
+pub fn native_tree(path: &Path, level: Option<u32>, gitignore: bool) -> String {
+    let mut walker = WalkBuilder::new(path);
+    walker
+        .standard_filters(gitignore)
+        .hidden(false)
+        .require_git(false)
+        .same_file_system(!tuning::follow_mounts())
+        .max_depth(level.map(|level| level as usize))
+        .sort_by_file_name(|a, b| unicode_paths::nfc(&a.to_string_lossy()).cmp(&unicode_paths::nfc(&b.to_string_lossy())))
+        .filter_entry(|entry| {
+            let file_name = entry.file_name().to_string_lossy();
+            entry.depth() == 0 || !EXCLUDED_NAMES.iter().any(|&excluded| file_name.contains(excluded))
+        });
+
+    let mut entries: Vec<(usize, String)> = Vec::new();
+    for entry in walker.build().flatten() {
+        if deadline::expired() {
+            deadline::skip(format!("rest of the tree for {}", path.display()));
+            break;
+        }
+        if entry.depth() > 0 {
+            let name = unicode_paths::nfc(&entry.file_name().to_string_lossy());
+            entries.push((entry.depth(), name));
+        }
+    }
+
+    // An entry is its parent's last child when no sibling follows before the walk climbs out
+    let mut last = vec![false; entries.len()];
+    let mut sibling_follows: Vec<bool> = Vec::new();
+    for (index, (depth, _)) in entries.iter().enumerate().rev() {
+        sibling_follows.resize(depth + 1, false);
+        last[index] = !sibling_follows[*depth];
+        sibling_follows[*depth] = true;
+    }
+
+    let mut output = format!("{}\n", path.display());
+    let mut open_levels: Vec<bool> = Vec::new();
+    for ((depth, name), last) in entries.iter().zip(last) {
+        open_levels.truncate(depth - 1);
+        for open in &open_levels {
+            output.push_str(if *open { "│   " } else { "    " });
+        }
+        output.push_str(if last { "└── " } else { "├── " });
+        output.push_str(name);
+        output.push('\n');
+        open_levels.push(!last);
+    }
+    output
+}
+
+pub fn render(path: &Path, level: Option<u32>, renderer: TreeRenderer, gitignore: bool) -> String {
+    if renderer == TreeRenderer::Eza {
+        if let Some(tree) = eza_tree(path, level) {
+            return tree;
+        }
+        eprintln!("eza is unavailable or failed; using the built-in tree");
+    }
+    native_tree(path, level, gitignore)
+}
@@ -0,0 +1,336 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use toml_edit::{Array, DocumentMut, InlineTable, Item, Value};
+
+use crate::{
+    active_profile, command_line_options, docs, get_config_path, load_config, porcelain_record, profile_directories,
+    profile_file_entries, profiles, quietly, render_profile, roots, tokens, Config, Error, Options,
+};
+
+// `fdllm trim` takes a file or directory out of the way in one of these ways
+enum Decision {
+    Exclude,
+    Outline,
+    Truncate(usize),
+}
+
+// A bundled file: its tokens, those of its header and fences, and what it would come to as an
+// outline (None when an outline doesn't shorten it)
+struct Measured {
+    path: PathBuf,
+    tokens: usize,
+    overhead: usize,
+    outline: Option<usize>,
+}
+
+// A file, or a first-level subdirectory of a walked directory (as `prune` reports them)
+struct Candidate {
+    path: PathBuf,
+    directory: bool,
+    // `exclude_paths` entry that leaves it out of its walked directory
+    walked: Option<String>,
+    // The `files` entry naming it, when it is listed rather than walked
+    listed: Option<String>,
+    outline: Option<usize>,
+    overhead: usize,
+}
+
+// Canonical where possible, so listed entries and bundled paths compare as the same file
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn ask(prompt: &str) -> Option<String> {
+    eprint!("{}", prompt);
+    let _ = io::stderr().flush();
+    let mut answer = String::new();
+    match io::stdin().lock().read_line(&mut answer) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(answer.trim().to_lowercase()),
+    }
+}
+
+// The bundled files with their tokens, largest first, and the subdirectories that gather
+// several of them; each subdirectory comes before its own largest file
+fn candidates(files: &[Measured], directories: &[PathBuf], listed: &HashMap<PathBuf, String>) -> Vec<(Candidate, usize)> {
+    let mut found: Vec<(Candidate, usize)> = Vec::new();
+    let mut per_directory: HashMap<PathBuf, (String, usize)> = HashMap::new();
+    for Measured { path, tokens: count, overhead, outline } in files {
+        let mut walked = None;
+        for directory in directories {
+            let Ok(relative) = path.strip_prefix(directory) else { continue };
+            walked = Some(glob::Pattern::escape(&relative.to_string_lossy()));
+            let mut components = relative.components();
+            if let (Some(first), Some(_)) = (components.next(), components.next()) {
+                let name = first.as_os_str().to_string_lossy().to_string();
+                let entry = per_directory
+                    .entry(directory.join(&name))
+                    .or_insert_with(|| (format!("{}/**", glob::Pattern::escape(&name)), 0));
+                entry.1 += count;
+            }
+            break;
+        }
+        let candidate = Candidate {
+            path: path.clone(),
+            directory: false,
+            walked,
+            listed: listed.get(&canonical(path)).cloned(),
+            outline: *outline,
+            overhead: *overhead,
+        };
+        found.push((candidate, *count));
+    }
+    for (path, (pattern, count)) in per_directory {
+        let candidate = Candidate {
+            path,
+            directory: true,
+            walked: Some(pattern),
+            listed: None,
+            outline: None,
+            overhead: 0,
+        };
+        found.push((candidate, count));
+    }
+    found.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| b.0.directory.cmp(&a.0.directory)).then_with(|| a.0.path.cmp(&b.0.path)));
+    found
+}
+
+// `fdllm trim [profile]`: while the bundle is over its token budget, offers the largest files
+// and directories one at a time to exclude, outline or truncate, then saves the decisions into
+// the profile's exclude_paths, files, outline_paths and truncate_paths
+pub fn run(profile_name: Option<&str>, save: bool) -> Result<(), Error> {
+    let config_path = get_config_path()?;
+    let config = load_config(&config_path)?;
+    let options = command_line_options();
+    let mut active = active_profile(&config, profile_name, options)?;
+    let name = profile_name.unwrap_or("default");
+    let Some(budget) = active.max_tokens else {
+        return Err(Error::Config(format!(
+            "{} has no token budget to trim to; set max_tokens or pass --max-tokens",
+            name
+        )));
+    };
+
+    // Measured as `fdllm copy` would bundle it, with nothing trimmed to fit yet
+    let directories = profile_directories(&active);
+    let listed: HashMap<PathBuf, String> = profile_file_entries(&active)
+        .into_iter()
+        .map(|entry| (canonical(&roots::resolve(&entry, &active.roots)), entry))
+        .collect();
+    active.max_tokens = None;
+    let measuring = Options {
+        max_tokens: None,
+        ..options.clone()
+    };
+    let rendered = quietly(|| render_profile(&mut active, profile_name, &measuring))?;
+    if let crate::spill::Assembled::Spilled(spilled) = rendered.assembled {
+        spilled.discard();
+    }
+    let mut total = rendered.tokens;
+    if total <= budget {
+        info!("{} is {} tokens, within its {} token budget; nothing to trim", name, total, budget);
+        return Ok(());
+    }
+    let files: Vec<Measured> = rendered
+        .slots
+        .into_iter()
+        .flatten()
+        .map(|entry| {
+            let overhead = entry.tokens.saturating_sub(tokens::count(&entry.content));
+            let outline = overhead + tokens::count(&docs::docs_only(&entry.path, entry.content));
+            Measured {
+                path: entry.path,
+                tokens: entry.tokens,
+                overhead,
+                outline: Some(outline).filter(|outline| *outline < entry.tokens),
+            }
+        })
+        .collect();
+
+    info!("{} is {} tokens, {} over its {} token budget", name, total, total - budget, budget);
+    let mut remaining: HashMap<PathBuf, usize> = files.iter().map(|file| (file.path.clone(), file.tokens)).collect();
+    let mut decisions: Vec<(Candidate, Decision)> = Vec::new();
+    for (candidate, _) in candidates(&files, &directories, &listed) {
+        if total <= budget {
+            break;
+        }
+        // What is still there of it after the decisions so far
+        let current: usize = remaining
+            .iter()
+            .filter(|(path, _)| path.starts_with(&candidate.path))
+            .map(|(_, count)| count)
+            .sum();
+        if current == 0 || decisions.iter().any(|(decided, _)| candidate.path.starts_with(&decided.path)) {
+            continue;
+        }
+        let over = total - budget;
+        let choices = match (candidate.directory, candidate.outline) {
+            (true, _) => "[e]xclude, [s]kip (Enter), [q]uit",
+            (false, Some(_)) => "[e]xclude, [o]utline, [t]runcate, [s]kip (Enter), [q]uit",
+            (false, None) => "[e]xclude, [t]runcate, [s]kip (Enter), [q]uit",
+        };
+        eprintln!(
+            "{}{}: {} tokens ({} over budget)",
+            candidate.path.display(),
+            if candidate.directory { "/" } else { "" },
+            current,
+            over
+        );
+        let decision = loop {
+            match ask(&format!("  {}? ", choices)).as_deref() {
+                None | Some("q") => break None,
+                Some("s" | "") => break Some(None),
+                Some("e") => break Some(Some(Decision::Exclude)),
+                Some("o") if candidate.outline.is_some() && !candidate.directory => break Some(Some(Decision::Outline)),
+                Some("t") if !candidate.directory => {
+                    // Enough to fit, unless that would leave almost nothing of the file
+                    let content = current.saturating_sub(candidate.overhead);
+                    let suggested = content.saturating_sub(over).max(content / 10).max(1);
+                    let Some(answer) = ask(&format!("  Keep how many tokens? [{}] ", suggested)) else { break None };
+                    if answer.is_empty() {
+                        break Some(Some(Decision::Truncate(suggested)));
+                    }
+                    match answer.parse::<usize>() {
+                        Ok(limit) if limit > 0 && limit < content => break Some(Some(Decision::Truncate(limit))),
+                        _ => eprintln!("  Expected a number of tokens below {}", content),
+                    }
+                }
+                _ => {}
+            }
+        };
+        let Some(decision) = decision else { break };
+        let Some(decision) = decision else { continue };
+        let kept = match decision {
+            Decision::Exclude => 0,
+            Decision::Outline => candidate.outline.unwrap_or(current),
+            Decision::Truncate(limit) => limit + candidate.overhead,
+        };
+        total -= current - kept;
+        remaining.retain(|path, _| !path.starts_with(&candidate.path));
+        if kept > 0 {
+            remaining.insert(candidate.path.clone(), kept);
+        }
+        decisions.push((candidate, decision));
+    }
+
+    if total <= budget {
+        info!("Now ~{} tokens, within the {} token budget", total, budget);
+    } else {
+        info!("Still ~{} tokens, {} over the {} token budget", total, total - budget, budget);
+    }
+    porcelain_record("tokens", total);
+    if decisions.is_empty() {
+        return Ok(());
+    }
+    let Some(profile_name) = profile_name else {
+        info!("Trimming the top-level configuration isn't saved; name a profile to keep the decisions");
+        return Ok(());
+    };
+    let confirmed = save
+        || ask(&format!(
+            "Save {} decision(s) to profile '{}' in {}? [y/N] ",
+            decisions.len(),
+            profile_name,
+            config_path.display()
+        ))
+        .is_some_and(|answer| answer == "y" || answer == "yes");
+    if !confirmed {
+        info!("Not saved");
+        return Ok(());
+    }
+    save_decisions(&config_path, &config, profile_name, &decisions)
+}
+
+fn push_unique(array: &mut Array, value: &str) {
+    if !array.iter().any(|existing| existing.as_str() == Some(value)) {
+        array.push(value);
+    }
+}
+
+// The profile's array under `key`, created when missing; a new exclude_paths starts with the
+// top-level patterns the profile was using, since its own list replaces them
+fn profile_array<'d>(document: &'d mut DocumentMut, profile_name: &str, key: &str, seed: &[String]) -> Result<&'d mut Array, Error> {
+    let profile = document["profiles"][profile_name]
+        .as_table_like_mut()
+        .ok_or_else(|| Error::Config(format!("Profile '{}' is not a table", profile_name)))?;
+    let item = profile.entry(key).or_insert_with(|| {
+        let mut array = Array::new();
+        for value in seed {
+            array.push(value.as_str());
+        }
+        toml_edit::value(array)
+    });
+    item.as_array_mut()
+        .ok_or_else(|| Error::Config(format!("{} of profile '{}' is not a list", key, profile_name)))
+}
+
+fn save_decisions(config_path: &Path, config: &Config, profile_name: &str, decisions: &[(Candidate, Decision)]) -> Result<(), Error> {
+    let mut document = profiles::read_document(config_path)?;
+    if profiles::profiles(&document).is_none_or(|profiles| !profiles.contains_key(profile_name)) {
+        return Err(Error::Config(format!(
+            "Profile '{}' is not defined in {}; add the decisions to its config by hand",
+            profile_name,
+            config_path.display()
+        )));
+    }
+    let inherited_excludes = config
+        .profiles
+        .as_ref()
+        .and_then(|profiles| profiles.get(profile_name))
+        .is_some_and(|profile| profile.exclude_paths.is_some());
+    let seed = match inherited_excludes {
+        true => Vec::new(),
+        false => config.exclude_paths.clone().unwrap_or_default(),
+    };
+
+    let mut unsaved = Vec::new();
+    for (candidate, decision) in decisions {
+        // Matched against the path as bundled, so it is written the same way
+        let pattern = glob::Pattern::escape(&candidate.path.to_string_lossy());
+        match decision {
+            Decision::Exclude => {
+                if let Some(entry) = &candidate.listed {
+                    let files = profile_array(&mut document, profile_name, "files", &[])?;
+                    let position = files.iter().position(|value| value.as_str() == Some(entry));
+                    match position {
+                        Some(index) => {
+                            // The next entry takes over the removed one's leading whitespace, so
+                            // `["a", "b"]` loses "a" as `["b"]` rather than `[ "b"]`
+                            let prefix = files.get(index).and_then(|value| value.decor().prefix()).cloned();
+                            files.remove(index);
+                            if let (Some(prefix), Some(next)) = (prefix, files.get_mut(index)) {
+                                next.decor_mut().set_prefix(prefix);
+                            }
+                        }
+                        None => unsaved.push(candidate.path.display().to_string()),
+                    }
+                } else if let Some(walked) = &candidate.walked {
+                    push_unique(profile_array(&mut document, profile_name, "exclude_paths", &seed)?, walked);
+                } else {
+                    // Project docs and the like, found neither in `files` nor in a walk
+                    unsaved.push(candidate.path.display().to_string());
+                }
+            }
+            Decision::Outline => push_unique(profile_array(&mut document, profile_name, "outline_paths", &[])?, &pattern),
+            Decision::Truncate(limit) => {
+                let profile = document["profiles"][profile_name]
+                    .as_table_like_mut()
+                    .ok_or_else(|| Error::Config(format!("Profile '{}' is not a table", profile_name)))?;
+                let limits = profile
+                    .entry("truncate_paths")
+                    .or_insert_with(|| Item::Value(Value::InlineTable(InlineTable::new())))
+                    .as_table_like_mut()
+                    .ok_or_else(|| Error::Config(format!("truncate_paths of profile '{}' is not a table", profile_name)))?;
+                limits.insert(&pattern, toml_edit::value(*limit as i64));
+            }
+        }
+    }
+    profiles::save(config_path, &document)?;
+    info!("Saved {} decision(s) to profile '{}' in {}", decisions.len() - unsaved.len(), profile_name, config_path.display());
+    for path in unsaved {
+        eprintln!("Not saved: {} comes from an inherited profile or the project docs; exclude it there", path);
+    }
+    Ok(())
+}
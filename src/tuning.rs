@@ -0,0 +1,51 @@
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+use std::sync::OnceLock;
+use std::thread;
+
+// std's own default buffer size
+const DEFAULT_IO_BUFFER_SIZE: usize = 8 * 1024;
+
+// Walker and reader settings (`threads`, `io_buffer_size`, `follow_mounts`) for slow network
+// filesystems or small machines
+#[derive(Clone, Copy, Default)]
+pub struct Tuning {
+    pub threads: Option<usize>,
+    pub io_buffer_size: Option<usize>,
+    pub follow_mounts: Option<bool>,
+}
+
+// Set once from the active profile; modes keep the defaults
+static TUNING: OnceLock<Tuning> = OnceLock::new();
+
+pub fn set(tuning: Tuning) {
+    let _ = TUNING.set(tuning);
+}
+
+fn current() -> Tuning {
+    TUNING.get().copied().unwrap_or_default()
+}
+
+pub fn threads() -> usize {
+    current()
+        .threads
+        .filter(|threads| *threads > 0)
+        .unwrap_or_else(|| thread::available_parallelism().map(|n| n.get()).unwrap_or(1))
+}
+
+pub fn io_buffer_size() -> usize {
+    current().io_buffer_size.filter(|size| *size > 0).unwrap_or(DEFAULT_IO_BUFFER_SIZE)
+}
+
+pub fn follow_mounts() -> bool {
+    current().follow_mounts.unwrap_or(true)
+}
+
+pub fn read_to_string(path: &Path) -> io::Result<String> {
+    let file = File::open(path)?;
+    let capacity = file.metadata().map(|metadata| metadata.len() as usize).unwrap_or(0);
+    let mut content = String::with_capacity(capacity);
+    BufReader::with_capacity(io_buffer_size(), file).read_to_string(&mut content)?;
+    Ok(content)
+}
@@ -0,0 +1,53 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use unicode_normalization::UnicodeNormalization;
+
+// macOS filesystems hand out decomposed (NFD) names while Linux keeps whatever bytes were
+// written, so `café.rs` can be spelled two ways. Everything user-facing goes through NFC.
+
+pub fn nfc(text: &str) -> String {
+    text.nfc().collect()
+}
+
+// Key for comparing and sorting paths regardless of how the platform spelled them
+pub fn key(path: &Path) -> String {
+    nfc(&path.to_string_lossy())
+}
+
+// The path as spelled on disk: when `path` doesn't exist as given, each missing component is
+// matched against its directory's entries by normalized name
+pub fn locate(path: &Path) -> Option<PathBuf> {
+    if path.exists() {
+        return Some(path.to_path_buf());
+    }
+    let mut located = PathBuf::new();
+    for component in path.components() {
+        let name = match component {
+            Component::Normal(name) => name,
+            other => {
+                located.push(other);
+                continue;
+            }
+        };
+        let candidate = located.join(name);
+        if candidate.exists() {
+            located = candidate;
+            continue;
+        }
+        let wanted = nfc(&name.to_string_lossy());
+        let dir = if located.as_os_str().is_empty() { Path::new(".") } else { located.as_path() };
+        let entry = fs::read_dir(dir)
+            .ok()?
+            .flatten()
+            .find(|entry| nfc(&entry.file_name().to_string_lossy()) == wanted)?;
+        located.push(entry.file_name());
+    }
+    Some(located)
+}
+
+// Drops entries that name the same file once normalized, keeping the first
+pub fn dedup(entries: Vec<String>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    entries.into_iter().filter(|entry| seen.insert(nfc(entry))).collect()
+}
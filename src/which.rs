@@ -0,0 +1,96 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+
+use crate::{
+    active_profile, collect_profile_directory, command_line_options, exit_codes, expand_glob, get_config_path, git,
+    is_glob, load_config, porcelain, porcelain_record, profile_directories, roots, unicode_paths, ActiveProfile, Error,
+    QUIET,
+};
+
+fn same_file(candidate: &Path, target: &str) -> bool {
+    fs::canonicalize(candidate).is_ok_and(|path| unicode_paths::key(&path) == target)
+}
+
+// Why the profile would bundle the file, or None when it wouldn't
+fn inclusion(profile: &ActiveProfile, target: &str) -> Option<String> {
+    for entry in &profile.files {
+        if let Some((rev, path)) = git::parse_revision_entry(entry) {
+            if same_file(&roots::resolve(path, &profile.roots), target) {
+                return Some(format!("listed in files at {}", rev));
+            }
+            continue;
+        }
+        if is_glob(entry, &profile.roots) {
+            if expand_glob(entry, &profile.roots).iter().any(|path| same_file(path, target)) {
+                return Some(format!("matches files pattern {}", entry));
+            }
+            continue;
+        }
+        let path = roots::resolve(entry, &profile.roots);
+        let path = unicode_paths::locate(&path).unwrap_or(path);
+        if same_file(&path, target) {
+            return Some("listed in files".to_string());
+        }
+    }
+    for dir_path in profile_directories(profile) {
+        // Walked like a real run, so extension filters and excludes apply
+        if collect_profile_directory(&dir_path, profile)
+            .iter()
+            .any(|path| same_file(path, target))
+        {
+            return Some(format!("via directory {}", dir_path.display()));
+        }
+    }
+    None
+}
+
+pub fn run(args: &[String]) -> Result<(), Error> {
+    let path = match args {
+        [path] => PathBuf::from(path),
+        _ => {
+            eprintln!("Usage: fdllm which <path>");
+            std::process::exit(exit_codes::CONFIG);
+        }
+    };
+    let target = match unicode_paths::locate(&path).and_then(|path| fs::canonicalize(path).ok()) {
+        Some(target) if target.is_file() => target,
+        _ => {
+            eprintln!("Not a file: {}", path.display());
+            std::process::exit(exit_codes::CONFIG);
+        }
+    };
+    let target_key = unicode_paths::key(&target);
+
+    let config = load_config(&get_config_path()?)?;
+    let mut names: Vec<&String> = config.profiles.iter().flat_map(|profiles| profiles.keys()).collect();
+    names.sort();
+
+    // Every profile is walked, so their own progress messages are silenced
+    QUIET.store(true, Ordering::Relaxed);
+    let mut matches = Vec::new();
+    if let Some(reason) = inclusion(&active_profile(&config, None, command_line_options())?, &target_key) {
+        matches.push(("(default)".to_string(), reason));
+    }
+    for name in names {
+        if let Some(reason) = inclusion(&active_profile(&config, Some(name), command_line_options())?, &target_key) {
+            matches.push((name.clone(), reason));
+        }
+    }
+    QUIET.store(false, Ordering::Relaxed);
+
+    if porcelain() {
+        for (name, _) in &matches {
+            porcelain_record("profile", name);
+        }
+        return Ok(());
+    }
+    if matches.is_empty() {
+        println!("No profile includes {}", target.display());
+        return Ok(());
+    }
+    for (name, reason) in &matches {
+        println!("{}: {}", name, reason);
+    }
+    Ok(())
+}